@@ -0,0 +1,29 @@
+CREATE TABLE term_meta (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    dictionary_id INTEGER NOT NULL,
+
+    expression TEXT NOT NULL,
+    mode TEXT NOT NULL CHECK (mode IN ('freq', 'pitch', 'ipa')),
+    -- NULL when the metadata is not restricted to a reading
+    reading TEXT,
+    -- Numeric frequency value for 'freq' rows, used for sorting
+    frequency REAL,
+
+    -- TermMetaData must be stored as a JSON string in SQLite
+    data TEXT NOT NULL,
+
+    FOREIGN KEY (dictionary_id) REFERENCES dictionary (id) ON DELETE CASCADE
+);
+
+--  ──────────────────────────── Speed Indices ────────────────────────────
+CREATE INDEX idx_term_meta__dictionary_id ON term_meta(dictionary_id);
+CREATE INDEX idx_term_meta__expression ON term_meta(expression);
+
+--  ──────────────────────── Automatic updated_at ─────────────────────
+CREATE TRIGGER trig_term_meta__update_timestamp 
+AFTER UPDATE ON term_meta 
+BEGIN
+    UPDATE term_meta SET updated_at = CURRENT_TIMESTAMP WHERE id = OLD.id;
+END;
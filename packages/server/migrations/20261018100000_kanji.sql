@@ -0,0 +1,54 @@
+CREATE TABLE kanji_entry (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    dictionary_id INTEGER NOT NULL,
+
+    character TEXT NOT NULL,
+    onyomi TEXT NOT NULL,
+    kunyomi TEXT NOT NULL,
+    tags TEXT NOT NULL,
+
+    -- Vec<String> must be stored as a JSON string in SQLite
+    meanings TEXT NOT NULL,
+    -- KanjiStats must be stored as a JSON string in SQLite
+    stats TEXT NOT NULL,
+
+    FOREIGN KEY (dictionary_id) REFERENCES dictionary (id) ON DELETE CASCADE
+);
+
+CREATE TABLE kanji_meta (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    dictionary_id INTEGER NOT NULL,
+
+    character TEXT NOT NULL,
+    mode TEXT NOT NULL CHECK (mode IN ('freq')),
+    -- Numeric frequency value, used for sorting
+    frequency REAL,
+
+    -- Frequency must be stored as a JSON string in SQLite
+    data TEXT NOT NULL,
+
+    FOREIGN KEY (dictionary_id) REFERENCES dictionary (id) ON DELETE CASCADE
+);
+
+--  ──────────────────────────── Speed Indices ────────────────────────────
+CREATE INDEX idx_kanji_entry__dictionary_id ON kanji_entry(dictionary_id);
+CREATE INDEX idx_kanji_entry__character ON kanji_entry(character);
+CREATE INDEX idx_kanji_meta__dictionary_id ON kanji_meta(dictionary_id);
+CREATE INDEX idx_kanji_meta__character ON kanji_meta(character);
+
+--  ──────────────────────── Automatic updated_at ─────────────────────
+CREATE TRIGGER trig_kanji_entry__update_timestamp 
+AFTER UPDATE ON kanji_entry 
+BEGIN
+    UPDATE kanji_entry SET updated_at = CURRENT_TIMESTAMP WHERE id = OLD.id;
+END;
+
+CREATE TRIGGER trig_kanji_meta__update_timestamp 
+AFTER UPDATE ON kanji_meta 
+BEGIN
+    UPDATE kanji_meta SET updated_at = CURRENT_TIMESTAMP WHERE id = OLD.id;
+END;
@@ -0,0 +1,8 @@
+-- Filled in by the server after migrating, see Db::normalize_dictionary_entries
+ALTER TABLE dictionary_entry ADD COLUMN expression_normalized TEXT NOT NULL DEFAULT '';
+ALTER TABLE dictionary_entry ADD COLUMN reading_normalized TEXT NOT NULL DEFAULT '';
+
+--  ──────────────────────────── Speed Indices ────────────────────────────
+CREATE INDEX idx_dictionary_entry__reading ON dictionary_entry(reading);
+CREATE INDEX idx_dictionary_entry__expression_normalized ON dictionary_entry(expression_normalized);
+CREATE INDEX idx_dictionary_entry__reading_normalized ON dictionary_entry(reading_normalized);
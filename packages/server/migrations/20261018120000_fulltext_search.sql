@@ -0,0 +1,54 @@
+-- The rowid of each row is the id of the dictionary_entry it indexes.
+-- The trigram tokenizer lets LIKE patterns use the index, which works for Japanese
+-- text without word boundaries as well as for English glosses.
+CREATE VIRTUAL TABLE dictionary_entry_fts USING fts5(
+    expression,
+    reading,
+    -- Plain text of the definitions, one line per text node
+    glossary,
+    tokenize = 'trigram'
+);
+
+--  ─────────────────────────── Glossary Extraction ───────────────────────────
+-- Keeps plain strings, "text" of text definitions and "content" of structured content.
+-- Deinflection definitions are arrays nested directly in the definitions array, skip them.
+CREATE VIEW dictionary_entry_glossary AS
+SELECT
+    dictionary_entry.id AS id,
+    (
+        SELECT group_concat(node.value, char(10))
+        FROM json_tree(dictionary_entry.definitions) AS node
+        WHERE node.type = 'text'
+            AND (typeof(node.key) = 'integer' OR node.key IN ('text', 'content'))
+            AND substr(node.fullkey, instr(node.fullkey, ']') + 1, 1) != '['
+    ) AS glossary
+FROM dictionary_entry;
+
+INSERT INTO dictionary_entry_fts (rowid, expression, reading, glossary)
+SELECT dictionary_entry.id, expression, reading, coalesce(glossary, '')
+FROM dictionary_entry
+JOIN dictionary_entry_glossary ON dictionary_entry_glossary.id = dictionary_entry.id;
+
+--  ──────────────────────────── Automatic sync ────────────────────────────
+CREATE TRIGGER trig_dictionary_entry__fts_insert
+AFTER INSERT ON dictionary_entry
+BEGIN
+    INSERT INTO dictionary_entry_fts (rowid, expression, reading, glossary)
+    SELECT NEW.id, NEW.expression, NEW.reading, coalesce(glossary, '')
+    FROM dictionary_entry_glossary WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER trig_dictionary_entry__fts_delete
+AFTER DELETE ON dictionary_entry
+BEGIN
+    DELETE FROM dictionary_entry_fts WHERE rowid = OLD.id;
+END;
+
+CREATE TRIGGER trig_dictionary_entry__fts_update
+AFTER UPDATE OF expression, reading, definitions ON dictionary_entry
+BEGIN
+    DELETE FROM dictionary_entry_fts WHERE rowid = OLD.id;
+    INSERT INTO dictionary_entry_fts (rowid, expression, reading, glossary)
+    SELECT NEW.id, NEW.expression, NEW.reading, coalesce(glossary, '')
+    FROM dictionary_entry_glossary WHERE id = NEW.id;
+END;
@@ -0,0 +1,28 @@
+-- Settings chosen by the user, kept apart from the columns filled from the index so that
+-- replacing or updating a dictionary keeps them.
+CREATE TABLE dictionary_settings (
+    dictionary_id INTEGER PRIMARY KEY,
+
+    -- Entries of dictionaries with a higher priority come first
+    priority INTEGER NOT NULL DEFAULT 0,
+    -- Disabled dictionaries are left out of every lookup
+    enabled BOOLEAN NOT NULL DEFAULT 1 CHECK (enabled IN (0, 1)),
+    -- Name shown instead of the title
+    alias TEXT,
+    -- Whether definitions are shown collapsed at first
+    collapsed BOOLEAN NOT NULL DEFAULT 0 CHECK (collapsed IN (0, 1)),
+    -- Whether entries sharing a sequence number are merged into one result
+    merge_mode TEXT NOT NULL DEFAULT 'merge' CHECK (merge_mode IN ('merge', 'separate')),
+
+    FOREIGN KEY (dictionary_id) REFERENCES dictionary (id) ON DELETE CASCADE
+);
+
+INSERT INTO dictionary_settings (dictionary_id) SELECT id FROM dictionary;
+
+--  ──────────────────────────── Default settings ────────────────────────────
+CREATE TRIGGER trig_dictionary__settings_insert
+AFTER INSERT ON dictionary
+BEGIN
+    INSERT INTO dictionary_settings (dictionary_id) VALUES (NEW.id);
+END;
+
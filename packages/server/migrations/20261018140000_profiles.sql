@@ -0,0 +1,76 @@
+-- A set of dictionary settings and lookup options, selected per request.
+-- The default profile has id 1 and can't be removed.
+CREATE TABLE profile (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    name TEXT NOT NULL UNIQUE,
+    -- Most entries returned by a lookup, NULL for no limit
+    max_results INTEGER,
+    -- Characters looked at by a scan when the request gives no length
+    scan_length INTEGER NOT NULL DEFAULT 16,
+    -- Whether lookups also match the deinflected forms of the text
+    deinflect BOOLEAN NOT NULL DEFAULT 1 CHECK (deinflect IN (0, 1)),
+    -- Columns matched by a lookup when the request doesn't say
+    search_field TEXT NOT NULL DEFAULT 'any' CHECK (search_field IN ('expression', 'reading', 'any'))
+);
+
+INSERT INTO profile (id, name) VALUES (1, 'default');
+
+--  ────────────────────────── Settings per profile ──────────────────────────
+-- SQLite can't change the primary key of a table, so the settings are moved aside and copied
+-- into a table keyed by profile as well, as the settings of the default profile.
+DROP TRIGGER trig_dictionary__settings_insert;
+ALTER TABLE dictionary_settings RENAME TO dictionary_settings_old;
+
+CREATE TABLE dictionary_settings (
+    profile_id INTEGER NOT NULL,
+    dictionary_id INTEGER NOT NULL,
+
+    -- Entries of dictionaries with a higher priority come first
+    priority INTEGER NOT NULL DEFAULT 0,
+    -- Disabled dictionaries are left out of every lookup
+    enabled BOOLEAN NOT NULL DEFAULT 1 CHECK (enabled IN (0, 1)),
+    -- Name shown instead of the title
+    alias TEXT,
+    -- Whether definitions are shown collapsed at first
+    collapsed BOOLEAN NOT NULL DEFAULT 0 CHECK (collapsed IN (0, 1)),
+    -- Whether entries sharing a sequence number are merged into one result
+    merge_mode TEXT NOT NULL DEFAULT 'merge' CHECK (merge_mode IN ('merge', 'separate')),
+
+    PRIMARY KEY (profile_id, dictionary_id),
+    FOREIGN KEY (profile_id) REFERENCES profile (id) ON DELETE CASCADE,
+    FOREIGN KEY (dictionary_id) REFERENCES dictionary (id) ON DELETE CASCADE
+);
+
+INSERT INTO dictionary_settings (
+    profile_id, dictionary_id, priority, enabled, alias, collapsed, merge_mode
+)
+SELECT 1, dictionary_id, priority, enabled, alias, collapsed, merge_mode
+FROM dictionary_settings_old;
+
+DROP TABLE dictionary_settings_old;
+
+--  ──────────────────────────── Speed Indices ────────────────────────────
+CREATE INDEX idx_dictionary_settings__dictionary_id ON dictionary_settings(dictionary_id);
+
+--  ──────────────────────────── Default settings ────────────────────────────
+CREATE TRIGGER trig_dictionary__settings_insert
+AFTER INSERT ON dictionary
+BEGIN
+    INSERT INTO dictionary_settings (profile_id, dictionary_id) SELECT id, NEW.id FROM profile;
+END;
+
+CREATE TRIGGER trig_profile__settings_insert
+AFTER INSERT ON profile
+BEGIN
+    INSERT INTO dictionary_settings (profile_id, dictionary_id) SELECT NEW.id, id FROM dictionary;
+END;
+
+--  ──────────────────────── Automatic updated_at ─────────────────────
+CREATE TRIGGER trig_profile__update_timestamp
+AFTER UPDATE ON profile
+BEGIN
+    UPDATE profile SET updated_at = CURRENT_TIMESTAMP WHERE id = OLD.id;
+END;
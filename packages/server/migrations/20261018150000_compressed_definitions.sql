@@ -0,0 +1,25 @@
+-- Definitions are stored as zstd frames of their JSON, compressed with a dictionary trained on
+-- the definitions of each dictionary. Rows imported before are compressed by `dict compress`,
+-- the dictionaries they belong to have no row here until then.
+CREATE TABLE definition_compression (
+    dictionary_id INTEGER PRIMARY KEY,
+
+    -- Id written in the frames compressed with the trained dictionary, NULL when there were
+    -- too few definitions to train one
+    trained_id INTEGER,
+    trained BLOB,
+    -- Bytes of the definitions as JSON and as stored
+    raw_size INTEGER NOT NULL DEFAULT 0,
+    stored_size INTEGER NOT NULL DEFAULT 0,
+
+    FOREIGN KEY (dictionary_id) REFERENCES dictionary (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_definition_compression__trained_id ON definition_compression(trained_id);
+
+--  ─────────────────────────── Glossary Extraction ───────────────────────────
+-- SQLite can't read compressed definitions, the glossary is now extracted by the writer,
+-- which fills the full-text index along with the entries.
+DROP TRIGGER trig_dictionary_entry__fts_insert;
+DROP TRIGGER trig_dictionary_entry__fts_update;
+DROP VIEW dictionary_entry_glossary;
@@ -0,0 +1,2 @@
+-- Merged lookups fetch the entries sharing a sequence number with the ones that matched.
+CREATE INDEX idx_dictionary_entry__dictionary_id_sequence ON dictionary_entry(dictionary_id, sequence);
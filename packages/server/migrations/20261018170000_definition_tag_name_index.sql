@@ -0,0 +1,4 @@
+-- Tags are resolved by the dictionary of an entry and their name, the new index covers lookups
+-- by dictionary alone as well.
+CREATE INDEX idx_definition_tag__dictionary_id_name ON definition_tag(dictionary_id, name);
+DROP INDEX idx_definition_tag__dictionary_id;
@@ -1,11 +1,29 @@
+use crate::db::query::{
+    DEFAULT_PAGE_SIZE, DictionarySettingsUpdate, FulltextField, FulltextMode, KanjiReadingField,
+    MAX_PAGE_SIZE, ProfileUpdate, SearchField,
+};
+use crate::db::tables::{MergeMode, Profile};
 use crate::server::serve;
 use crate::util::config::Config;
-use crate::util::dict::Dict;
+use crate::util::dict::{Dict, DictFormat, OnConflict};
+use crate::util::html::{DefinitionFormat, RenderHtml};
+use crate::util::merge::ResultMode;
+use crate::util::progress::TerminalProgress;
+use crate::util::ranking::Ranking;
+use crate::util::translator::{MAX_SCAN_LENGTH, Translator};
+use crate::util::updater::{HttpFetcher, Updater};
+use crate::util::validation::{ValidationMode, ValidationReport};
 use crate::{db::Db, util::lexer::Lexer};
+use anyhow::bail;
+use clap::builder::{RangedI64ValueParser, RangedU64ValueParser};
 use clap::{Parser, Subcommand};
 use serde_json::json;
+use std::path::PathBuf;
 use std::sync::Arc;
 
+/// Issues printed after validating, the written report has all of them.
+const REPORT_PRINT_LIMIT: usize = 50;
+
 #[derive(Parser, Debug)]
 #[command(name = "hanayomi")]
 #[command(version, about = "(TODO) Some dictionary tools", long_about = None)]
@@ -35,6 +53,12 @@ enum Commands {
         action: DictCommands,
     },
 
+    #[command(about = "Manage the profiles")]
+    Profile {
+        #[command(subcommand)]
+        action: ProfileCommands,
+    },
+
     #[command(about = "Manage the Lexer")]
     Lexer {
         #[command(subcommand)]
@@ -51,15 +75,37 @@ enum DictCommands {
 
         #[arg(long)]
         dictionary: String,
+
+        #[arg(long, value_enum, default_value_t = DictFormat::Yomitan)]
+        format: DictFormat,
+
+        #[arg(long, value_enum, default_value_t = OnConflict::Error)]
+        on_conflict: OnConflict,
+
+        /// Check every row against the schema and import nothing if any is invalid
+        #[arg(long, conflicts_with = "lenient")]
+        strict: bool,
+
+        /// Check every row against the schema and skip the invalid ones
+        #[arg(long)]
+        lenient: bool,
+
+        /// Where to write the validation report, defaults to the temp directory of the workdir
+        #[arg(long)]
+        report: Option<PathBuf>,
     },
 
     #[command(about = "List all dictionaries")]
     List {
         #[arg(long)]
         workdir: Option<String>,
+
+        /// Profile whose settings are used, defaults to the default profile
+        #[arg(long)]
+        profile: Option<String>,
     },
 
-    #[command(about = "List all dictionaries")]
+    #[command(about = "Delete a dictionary along with its files")]
     Delete {
         #[arg(long)]
         workdir: Option<String>,
@@ -68,15 +114,274 @@ enum DictCommands {
         id: i32,
     },
 
+    #[command(about = "Change the settings of a dictionary")]
+    Set {
+        #[arg(long)]
+        workdir: Option<String>,
+
+        #[arg(long)]
+        id: i32,
+
+        /// Entries of dictionaries with a higher priority come first
+        #[arg(long)]
+        priority: Option<i32>,
+
+        /// Whether the dictionary is used in lookups
+        #[arg(long)]
+        enabled: Option<bool>,
+
+        /// Name shown instead of the title, an empty alias removes it
+        #[arg(long)]
+        alias: Option<String>,
+
+        /// Whether definitions are shown collapsed at first
+        #[arg(long)]
+        collapsed: Option<bool>,
+
+        #[arg(long, value_enum)]
+        merge_mode: Option<MergeMode>,
+
+        /// Profile whose settings are used, defaults to the default profile
+        #[arg(long)]
+        profile: Option<String>,
+    },
+
+    #[command(about = "Remove leftover files and rows, then compact the database")]
+    Gc {
+        #[arg(long)]
+        workdir: Option<String>,
+    },
+
+    #[command(about = "Show how much compressing definitions saved")]
+    Compression {
+        #[arg(long)]
+        workdir: Option<String>,
+    },
+
+    #[command(about = "Compress definitions imported before they were stored compressed")]
+    Compress {
+        #[arg(long)]
+        workdir: Option<String>,
+    },
+
+    #[command(about = "Export a dictionary as a Yomitan archive")]
+    Export {
+        #[arg(long)]
+        workdir: Option<String>,
+
+        #[arg(long)]
+        id: i32,
+
+        #[arg(long)]
+        out: PathBuf,
+    },
+
+    #[command(about = "Check updatable dictionaries for a newer revision")]
+    CheckUpdates {
+        #[arg(long)]
+        workdir: Option<String>,
+
+        #[arg(long, help = "Only check this dictionary")]
+        id: Option<i32>,
+    },
+
+    #[command(about = "Install the latest revision of updatable dictionaries")]
+    Update {
+        #[arg(long)]
+        workdir: Option<String>,
+
+        #[arg(long, help = "Only update this dictionary")]
+        id: Option<i32>,
+    },
+
     #[command(about = "Query the dictionary")]
     Query {
         #[arg(long)]
         workdir: Option<String>,
         #[arg(long)]
         expression: String,
+        /// Defaults to the search field of the profile
+        #[arg(long, value_enum)]
+        field: Option<SearchField>,
+        #[arg(long, value_enum, default_value_t = ResultMode::Flat)]
+        mode: ResultMode,
+        /// Comma-separated keys among length, exact, frequency, priority, score and tags
+        #[arg(long, default_value_t)]
+        sort: Ranking,
+        /// Return the tags of the entries with their definitions
+        #[arg(long)]
+        resolve_tags: bool,
+        /// Render the definitions as sanitized HTML
+        #[arg(long, value_enum, default_value_t = DefinitionFormat::Json)]
+        format: DefinitionFormat,
+        #[arg(long)]
+        profile: Option<String>,
+    },
+
+    #[command(about = "Scan text for the longest dictionary match")]
+    Scan {
+        #[arg(long)]
+        workdir: Option<String>,
+        #[arg(long)]
+        text: String,
+        /// Defaults to the scan length of the profile, 64 at most
+        #[arg(long, value_parser = RangedU64ValueParser::<usize>::new().range(1..=MAX_SCAN_LENGTH as u64))]
+        max_length: Option<usize>,
+        /// Comma-separated keys among length, exact, frequency, priority, score and tags
+        #[arg(long, default_value_t)]
+        sort: Ranking,
+        /// Return the tags of the entries with their definitions
+        #[arg(long)]
+        resolve_tags: bool,
+        /// Render the definitions as sanitized HTML
+        #[arg(long, value_enum, default_value_t = DefinitionFormat::Json)]
+        format: DefinitionFormat,
+        #[arg(long)]
+        profile: Option<String>,
+    },
+
+    #[command(about = "Search expressions, readings and definitions")]
+    Search {
+        #[arg(long)]
+        workdir: Option<String>,
+        #[arg(long)]
+        text: String,
+        #[arg(long, value_enum, default_value_t = FulltextMode::Substring)]
+        mode: FulltextMode,
+        #[arg(long, value_enum, default_value_t = FulltextField::Any)]
+        field: FulltextField,
+        #[arg(long, default_value_t = 1)]
+        page: u32,
+        /// 100 at most
+        #[arg(
+            long,
+            default_value_t = DEFAULT_PAGE_SIZE,
+            value_parser = clap::value_parser!(u32).range(1..=MAX_PAGE_SIZE as i64)
+        )]
+        per_page: u32,
+        /// Render the definitions as sanitized HTML
+        #[arg(long, value_enum, default_value_t = DefinitionFormat::Json)]
+        format: DefinitionFormat,
+        #[arg(long)]
+        profile: Option<String>,
+    },
+
+    #[command(about = "Query the kanji dictionary")]
+    QueryKanji {
+        #[arg(long)]
+        workdir: Option<String>,
+        #[arg(long)]
+        character: String,
+        #[arg(long)]
+        profile: Option<String>,
+    },
+
+    #[command(about = "Find kanji by their onyomi or kunyomi")]
+    QueryKanjiReading {
+        #[arg(long)]
+        workdir: Option<String>,
+        #[arg(long)]
+        reading: String,
+        #[arg(long, value_enum, default_value_t = KanjiReadingField::Any)]
+        field: KanjiReadingField,
+        #[arg(long)]
+        profile: Option<String>,
     },
 }
 
+#[derive(Subcommand, Debug)]
+enum ProfileCommands {
+    #[command(about = "List all profiles")]
+    List {
+        #[arg(long)]
+        workdir: Option<String>,
+    },
+
+    #[command(about = "Create a profile with the default dictionary settings")]
+    Create {
+        #[arg(long)]
+        workdir: Option<String>,
+
+        #[arg(long)]
+        name: String,
+
+        #[command(flatten)]
+        options: ProfileOptions,
+    },
+
+    #[command(about = "Change the options of a profile")]
+    Set {
+        #[arg(long)]
+        workdir: Option<String>,
+
+        #[arg(long)]
+        name: String,
+
+        /// New name of the profile
+        #[arg(long)]
+        rename: Option<String>,
+
+        #[command(flatten)]
+        options: ProfileOptions,
+    },
+
+    #[command(about = "Delete a profile along with its dictionary settings")]
+    Delete {
+        #[arg(long)]
+        workdir: Option<String>,
+
+        #[arg(long)]
+        name: String,
+    },
+}
+
+#[derive(clap::Args, Debug)]
+struct ProfileOptions {
+    /// Most entries returned by a lookup, 0 removes the limit
+    #[arg(long, value_parser = RangedI64ValueParser::<i32>::new().range(0..))]
+    max_results: Option<i32>,
+
+    /// Number of characters looked at when scanning text, 64 at most
+    #[arg(long, value_parser = RangedI64ValueParser::<i32>::new().range(1..=MAX_SCAN_LENGTH as i64))]
+    scan_length: Option<i32>,
+
+    /// Whether lookups also try deinflected forms
+    #[arg(long)]
+    deinflect: Option<bool>,
+
+    /// Field matched by lookups
+    #[arg(long, value_enum)]
+    search_field: Option<SearchField>,
+}
+
+impl ProfileOptions {
+    fn into_update(self, name: Option<String>) -> ProfileUpdate {
+        ProfileUpdate {
+            name,
+            max_results: self.max_results,
+            scan_length: self.scan_length,
+            deinflect: self.deinflect,
+            search_field: self.search_field,
+        }
+    }
+}
+
+/// Finds the profile given with `--profile`, or the default one.
+async fn select_profile(db: &Db, name: Option<String>) -> anyhow::Result<Profile> {
+    let Some(profile) = db.query_selected_profile(name.as_deref()).await? else {
+        bail!("Profile {} not found", name.unwrap_or_default());
+    };
+    Ok(profile)
+}
+
+/// Finds the profile named `name`.
+async fn find_profile(db: &Db, name: &str) -> anyhow::Result<Profile> {
+    let Some(profile) = db.query_profile_by_name(name).await? else {
+        bail!("Profile {} not found", name);
+    };
+    Ok(profile)
+}
+
 #[derive(Subcommand, Debug)]
 enum LexerCommands {
     #[command(about = "Tokenize s sentence")]
@@ -106,36 +411,337 @@ pub async fn cli() -> anyhow::Result<()> {
             DictCommands::Parse {
                 workdir,
                 dictionary,
+                format,
+                on_conflict,
+                strict,
+                lenient,
+                report: report_path,
             } => {
                 let config = Config::new(workdir, host, port)?;
                 let config = Arc::new(config);
                 let db = Db::new(config.clone()).await?;
                 let dict = Dict::new(config.clone());
-                dict.parse_dict(dictionary, db).await?;
+                let progress = Arc::new(TerminalProgress::default());
+                if format != DictFormat::Yomitan {
+                    if strict || lenient {
+                        bail!("--strict and --lenient only apply to Yomitan dictionaries");
+                    }
+                    let dictionary = PathBuf::from(dictionary);
+                    dict.parse_source(dictionary, format, &db, on_conflict, progress)
+                        .await?;
+                    return Ok(());
+                }
+                let mode = match (strict, lenient) {
+                    (true, _) => ValidationMode::Strict,
+                    (_, true) => ValidationMode::Lenient,
+                    _ => ValidationMode::Normal,
+                };
+                let mut report = ValidationReport::new(mode);
+                let dictionary = PathBuf::from(dictionary);
+                let result = dict
+                    .parse_dict(dictionary.clone(), &db, on_conflict, &mut report, progress)
+                    .await;
+
+                if mode != ValidationMode::Normal {
+                    let report_path = report_path.unwrap_or_else(|| {
+                        let name = dictionary.file_stem().unwrap_or_default().to_string_lossy();
+                        config.dir.temp.join(format!("{}.report.json", name))
+                    });
+                    report.print(REPORT_PRINT_LIMIT);
+                    report.write(&report_path)?;
+                    println!("Report written to {}", report_path.display());
+                }
+                result?;
             }
-            DictCommands::List { workdir } => {
+            DictCommands::List { workdir, profile } => {
                 let config = Config::new(workdir, host, port)?;
                 let config = Arc::new(config);
                 let db = Db::new(config.clone()).await?;
-                let dictionaries = db.query_dictionaries().await?;
+                let profile = select_profile(&db, profile).await?;
+                let dictionaries = db.query_dictionaries(profile.id).await?;
                 println!("{}", json!(dictionaries));
             }
             DictCommands::Delete { workdir, id } => {
                 let config = Config::new(workdir, host, port)?;
                 let config = Arc::new(config);
                 let db = Db::new(config.clone()).await?;
-                let dictionary = db.query_delete_dictionary(id).await?;
+                let dict = Dict::new(config.clone());
+                let dictionary = dict.delete_dict(&db, id).await?;
+                println!("{}", json!(dictionary));
+            }
+            DictCommands::Set {
+                workdir,
+                id,
+                priority,
+                enabled,
+                alias,
+                collapsed,
+                merge_mode,
+                profile,
+            } => {
+                let config = Config::new(workdir, host, port)?;
+                let config = Arc::new(config);
+                let db = Db::new(config.clone()).await?;
+                let profile = select_profile(&db, profile).await?;
+                let update = DictionarySettingsUpdate {
+                    priority,
+                    enabled,
+                    alias,
+                    collapsed,
+                    merge_mode,
+                };
+                let Some(dictionary) = db
+                    .query_update_dictionary_settings(id, profile.id, &update)
+                    .await?
+                else {
+                    bail!("Dictionary {} not found", id);
+                };
                 println!("{}", json!(dictionary));
             }
+            DictCommands::Gc { workdir } => {
+                let config = Config::new(workdir, host, port)?;
+                let config = Arc::new(config);
+                let db = Db::new(config.clone()).await?;
+                let dict = Dict::new(config.clone());
+                let report = dict.gc(&db).await?;
+                println!("{}", json!(report));
+            }
+            DictCommands::Compression { workdir } => {
+                let config = Config::new(workdir, host, port)?;
+                let config = Arc::new(config);
+                let db = Db::new(config.clone()).await?;
+                let dictionaries = db.query_definition_compression().await?;
+                let raw_size: i64 = dictionaries.iter().map(|d| d.raw_size).sum();
+                let stored_size: i64 = dictionaries.iter().map(|d| d.stored_size).sum();
+                println!(
+                    "{}",
+                    json!({
+                        "dictionaries": dictionaries,
+                        "rawSize": raw_size,
+                        "storedSize": stored_size,
+                        "saved": raw_size - stored_size,
+                    })
+                );
+            }
+            DictCommands::Compress { workdir } => {
+                let config = Config::new(workdir, host, port)?;
+                let config = Arc::new(config);
+                let db = Db::new(config.clone()).await?;
+                let dictionaries = db.compress_dictionary_entries().await?;
+                if !dictionaries.is_empty() {
+                    db.vacuum().await?;
+                }
+                println!("{}", json!(dictionaries));
+            }
+            DictCommands::Export { workdir, id, out } => {
+                let config = Config::new(workdir, host, port)?;
+                let config = Arc::new(config);
+                let db = Db::new(config.clone()).await?;
+                let dict = Dict::new(config.clone());
+                dict.export_dict(&db, id, &out).await?;
+                println!("Exported to {}", out.display());
+            }
+            DictCommands::CheckUpdates { workdir, id } => {
+                let config = Config::new(workdir, host, port)?;
+                let config = Arc::new(config);
+                let db = Db::new(config.clone()).await?;
+                let updater = Updater::new(HttpFetcher::default());
+                let mut statuses = Vec::new();
+                for dictionary in db.query_updatable_dictionaries(id).await? {
+                    match updater.check(&dictionary).await {
+                        Ok(status) => statuses.push(status),
+                        Err(e) => eprintln!("{}: {:#}", dictionary.title, e),
+                    }
+                }
+                println!("{}", json!(statuses));
+            }
+            DictCommands::Update { workdir, id } => {
+                let config = Config::new(workdir, host, port)?;
+                let config = Arc::new(config);
+                let db = Db::new(config.clone()).await?;
+                let updater = Updater::new(HttpFetcher::default());
+                for dictionary in db.query_updatable_dictionaries(id).await? {
+                    let progress = Arc::new(TerminalProgress::default());
+                    match updater
+                        .update(config.clone(), &db, &dictionary, progress)
+                        .await
+                    {
+                        Ok(true) => println!("{}: updated", dictionary.title),
+                        Ok(false) => println!("{}: up to date", dictionary.title),
+                        Err(e) => eprintln!("{}: {:#}", dictionary.title, e),
+                    }
+                }
+            }
             DictCommands::Query {
                 workdir,
                 expression,
+                field,
+                mode,
+                sort,
+                resolve_tags,
+                format,
+                profile,
+            } => {
+                let config = Config::new(workdir, host, port)?;
+                let config = Arc::new(config);
+                let db = Db::new(config.clone()).await?;
+                let profile = select_profile(&db, profile).await?;
+                let field = field.unwrap_or(profile.search_field);
+                let translator = Translator::new()?;
+                match mode {
+                    ResultMode::Flat => {
+                        let mut definition = translator
+                            .find_terms(&db, &expression, field, &sort, resolve_tags, &profile)
+                            .await?;
+                        if format == DefinitionFormat::Html {
+                            definition.render_html();
+                        }
+                        println!("{}", json!(definition));
+                    }
+                    ResultMode::Merged => {
+                        let mut merged = translator
+                            .find_terms_merged(
+                                &db,
+                                &expression,
+                                field,
+                                &sort,
+                                resolve_tags,
+                                &profile,
+                            )
+                            .await?;
+                        if format == DefinitionFormat::Html {
+                            merged.render_html();
+                        }
+                        println!("{}", json!(merged));
+                    }
+                }
+            }
+            DictCommands::Scan {
+                workdir,
+                text,
+                max_length,
+                sort,
+                resolve_tags,
+                format,
+                profile,
             } => {
                 let config = Config::new(workdir, host, port)?;
                 let config = Arc::new(config);
                 let db = Db::new(config.clone()).await?;
-                let definition = db.query_dictionary_entry_by(expression).await?;
-                println!("{}", json!(definition));
+                let profile = select_profile(&db, profile).await?;
+                let max_length = max_length.unwrap_or(profile.scan_length.max(1) as usize);
+                let translator = Translator::new()?;
+                let mut result = translator
+                    .scan(&db, &text, max_length, &sort, resolve_tags, &profile)
+                    .await?;
+                if format == DefinitionFormat::Html {
+                    result.render_html();
+                }
+                println!("{}", json!(result));
+            }
+            DictCommands::Search {
+                workdir,
+                text,
+                mode,
+                field,
+                page,
+                per_page,
+                format,
+                profile,
+            } => {
+                let config = Config::new(workdir, host, port)?;
+                let config = Arc::new(config);
+                let db = Db::new(config.clone()).await?;
+                let profile = select_profile(&db, profile).await?;
+                let mut result = db
+                    .search_dictionary_entry_fulltext(
+                        &text,
+                        mode,
+                        field,
+                        page.max(1),
+                        per_page,
+                        profile.id,
+                    )
+                    .await?;
+                if format == DefinitionFormat::Html {
+                    result.render_html();
+                }
+                println!("{}", json!(result));
+            }
+            DictCommands::QueryKanji {
+                workdir,
+                character,
+                profile,
+            } => {
+                let config = Config::new(workdir, host, port)?;
+                let config = Arc::new(config);
+                let db = Db::new(config.clone()).await?;
+                let profile = select_profile(&db, profile).await?;
+                let entries = db
+                    .query_kanji_entry_by(character.clone(), profile.id)
+                    .await?;
+                let meta = db.query_kanji_meta_by(character, profile.id).await?;
+                println!("{}", json!({ "entries": entries, "meta": meta }));
+            }
+            DictCommands::QueryKanjiReading {
+                workdir,
+                reading,
+                field,
+                profile,
+            } => {
+                let config = Config::new(workdir, host, port)?;
+                let config = Arc::new(config);
+                let db = Db::new(config.clone()).await?;
+                let profile = select_profile(&db, profile).await?;
+                let entries = db
+                    .query_kanji_entry_by_reading(&reading, field, profile.id)
+                    .await?;
+                println!("{}", json!(entries));
+            }
+        },
+        Commands::Profile { action } => match action {
+            ProfileCommands::List { workdir } => {
+                let config = Config::new(workdir, host, port)?;
+                let config = Arc::new(config);
+                let db = Db::new(config.clone()).await?;
+                let profiles = db.query_profiles().await?;
+                println!("{}", json!(profiles));
+            }
+            ProfileCommands::Create {
+                workdir,
+                name,
+                options,
+            } => {
+                let config = Config::new(workdir, host, port)?;
+                let config = Arc::new(config);
+                let db = Db::new(config.clone()).await?;
+                let profile = db
+                    .query_create_profile(&name, &options.into_update(None))
+                    .await?;
+                println!("{}", json!(profile));
+            }
+            ProfileCommands::Set {
+                workdir,
+                name,
+                rename,
+                options,
+            } => {
+                let config = Config::new(workdir, host, port)?;
+                let config = Arc::new(config);
+                let db = Db::new(config.clone()).await?;
+                let profile = find_profile(&db, &name).await?;
+                let profile = db
+                    .query_update_profile(profile.id, &options.into_update(rename))
+                    .await?;
+                println!("{}", json!(profile));
+            }
+            ProfileCommands::Delete { workdir, name } => {
+                let config = Config::new(workdir, host, port)?;
+                let config = Arc::new(config);
+                let db = Db::new(config.clone()).await?;
+                let profile = find_profile(&db, &name).await?;
+                let profile = db.query_delete_profile(profile.id).await?;
+                println!("{}", json!(profile));
             }
         },
         Commands::Lexer { action } => match action {
@@ -1,5 +1,7 @@
+pub mod compression;
 pub mod query;
 pub mod tables;
+pub mod writer;
 
 use crate::util::config::Config;
 use sqlx::sqlite::SqlitePool;
@@ -17,6 +19,9 @@ impl Db {
         let pool = SqlitePool::connect(&file).await?;
         sqlx::migrate!("./migrations").run(&pool).await?;
 
-        Ok(Self { pool })
+        let db = Self { pool };
+        db.normalize_dictionary_entries().await?;
+        db.load_definition_decoders().await?;
+        Ok(db)
     }
 }
@@ -0,0 +1,234 @@
+use crate::schemas::dictionary_term_bank_v3::Definition;
+use sqlx::error::BoxDynError;
+use sqlx::sqlite::{Sqlite, SqliteTypeInfo, SqliteValueRef};
+use sqlx::{Decode, Type, TypeInfo};
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::{Arc, LazyLock, RwLock};
+use zstd::bulk::Compressor;
+use zstd::dict::DecoderDictionary;
+use zstd::zstd_safe;
+
+/// Largest dictionary trained for the definitions of a dictionary.
+const TRAINED_SIZE: usize = 64 * 1024;
+
+/// Entries needed to train a dictionary, with fewer of them plain frames are smaller.
+const MIN_TRAINING_SAMPLES: usize = 64;
+
+/// Entries a dictionary is trained on at most.
+pub const MAX_TRAINING_SAMPLES: usize = 10000;
+
+const COMPRESSION_LEVEL: i32 = zstd::DEFAULT_COMPRESSION_LEVEL;
+
+/// Dictionaries definitions were compressed with, by the id written in their frames.
+static DECODERS: LazyLock<RwLock<HashMap<u32, Arc<DecoderDictionary<'static>>>>> =
+    LazyLock::new(Default::default);
+
+pub fn has_decoder(trained_id: u32) -> bool {
+    DECODERS.read().unwrap().contains_key(&trained_id)
+}
+
+pub fn add_decoder(trained_id: u32, trained: &[u8]) {
+    let decoder = Arc::new(DecoderDictionary::copy(trained));
+    DECODERS.write().unwrap().insert(trained_id, decoder);
+}
+
+/// Compresses the definitions of one dictionary, with a dictionary trained on the first ones it
+/// is given when there are enough of them.
+pub struct DefinitionCompressor {
+    compressor: Compressor<'static>,
+    trained: Option<(u32, Vec<u8>)>,
+    /// Bytes of the definitions as JSON.
+    pub raw_size: i64,
+    /// Bytes of the definitions as stored.
+    pub stored_size: i64,
+}
+
+impl DefinitionCompressor {
+    /// `samples` are definitions as JSON, only the first [`MAX_TRAINING_SAMPLES`] are used.
+    pub fn train(samples: &[String]) -> anyhow::Result<Self> {
+        let samples = &samples[..samples.len().min(MAX_TRAINING_SAMPLES)];
+        // Training fails when the samples are too small or too alike, plain frames work then
+        let trained = (samples.len() >= MIN_TRAINING_SAMPLES)
+            .then(|| zstd::dict::from_samples(samples, TRAINED_SIZE).ok())
+            .flatten()
+            .and_then(|trained| {
+                let trained_id = zstd_safe::get_dict_id_from_dict(&trained)?;
+                Some((trained_id.get(), trained))
+            });
+        let compressor = match &trained {
+            Some((_, trained)) => Compressor::with_dictionary(COMPRESSION_LEVEL, trained)?,
+            None => Compressor::new(COMPRESSION_LEVEL)?,
+        };
+        if let Some((trained_id, trained)) = &trained {
+            add_decoder(*trained_id, trained);
+        }
+        Ok(Self {
+            compressor,
+            trained,
+            raw_size: 0,
+            stored_size: 0,
+        })
+    }
+
+    pub fn compress(&mut self, definitions: &str) -> anyhow::Result<Vec<u8>> {
+        let frame = self.compressor.compress(definitions.as_bytes())?;
+        self.raw_size += definitions.len() as i64;
+        self.stored_size += frame.len() as i64;
+        Ok(frame)
+    }
+
+    /// Id and content of the trained dictionary, if there is one.
+    pub fn trained(&self) -> Option<(u32, &[u8])> {
+        self.trained
+            .as_ref()
+            .map(|(trained_id, trained)| (*trained_id, trained.as_slice()))
+    }
+}
+
+/// The `definitions` column, a zstd frame of the JSON or the JSON itself for rows that weren't
+/// compressed yet.
+pub struct StoredDefinitions(Vec<u8>);
+
+impl Type<Sqlite> for StoredDefinitions {
+    fn type_info() -> SqliteTypeInfo {
+        <Vec<u8> as Type<Sqlite>>::type_info()
+    }
+
+    fn compatible(ty: &SqliteTypeInfo) -> bool {
+        matches!(ty.name(), "BLOB" | "TEXT")
+    }
+}
+
+impl<'r> Decode<'r, Sqlite> for StoredDefinitions {
+    fn decode(value: SqliteValueRef<'r>) -> Result<Self, BoxDynError> {
+        let bytes = <&[u8] as Decode<Sqlite>>::decode(value)?;
+        Ok(Self(bytes.to_vec()))
+    }
+}
+
+impl TryFrom<StoredDefinitions> for Vec<Definition> {
+    type Error = BoxDynError;
+
+    fn try_from(value: StoredDefinitions) -> Result<Self, Self::Error> {
+        let json = decompress(&value.0)?;
+        Ok(serde_json::from_slice(&json)?)
+    }
+}
+
+/// Whether stored definitions were compressed with a dictionary that isn't loaded.
+pub fn is_missing_decoder(stored: &[u8]) -> bool {
+    stored.starts_with(&zstd_safe::MAGICNUMBER.to_le_bytes())
+        && zstd_safe::get_dict_id_from_frame(stored)
+            .is_some_and(|trained_id| !DECODERS.read().unwrap().contains_key(&trained_id.get()))
+}
+
+/// JSON of stored definitions, which is returned as is when it isn't compressed.
+pub fn decompress(stored: &[u8]) -> Result<Vec<u8>, BoxDynError> {
+    if !stored.starts_with(&zstd_safe::MAGICNUMBER.to_le_bytes()) {
+        return Ok(stored.to_vec());
+    }
+
+    let mut json = Vec::new();
+    match zstd_safe::get_dict_id_from_frame(stored) {
+        Some(trained_id) => {
+            let decoder = DECODERS
+                .read()
+                .unwrap()
+                .get(&trained_id.get())
+                .cloned()
+                .ok_or_else(|| format!("Missing compression dictionary {}", trained_id))?;
+            zstd::Decoder::with_prepared_dictionary(stored, &decoder)?.read_to_end(&mut json)?;
+        }
+        None => {
+            zstd::Decoder::with_buffer(stored)?.read_to_end(&mut json)?;
+        }
+    }
+    Ok(json)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::query::SearchField;
+    use crate::db::tables::DictionaryEntry;
+    use crate::schemas::dictionary_index::DictionaryIndex;
+    use crate::test_util::temp_db;
+
+    #[test]
+    fn should_round_trip_definitions() {
+        let samples: Vec<String> = (0..1000)
+            .map(|i| {
+                format!(
+                    r#"[{{"type":"structured-content","content":[{{"tag":"span","content":"meaning {}"}}]}},"gloss {}"]"#,
+                    i,
+                    i * 7
+                )
+            })
+            .collect();
+        let mut compressor = DefinitionCompressor::train(&samples).unwrap();
+        assert!(compressor.trained().is_some());
+        for sample in &samples {
+            let frame = compressor.compress(sample).unwrap();
+            assert_eq!(decompress(&frame).unwrap(), sample.as_bytes());
+        }
+        assert!(compressor.stored_size < compressor.raw_size / 2);
+
+        // Too few to train on
+        let mut compressor = DefinitionCompressor::train(&samples[..2]).unwrap();
+        assert!(compressor.trained().is_none());
+        let frame = compressor.compress(&samples[0]).unwrap();
+        assert_eq!(decompress(&frame).unwrap(), samples[0].as_bytes());
+
+        // Rows that weren't compressed yet
+        assert_eq!(
+            decompress(samples[0].as_bytes()).unwrap(),
+            samples[0].as_bytes()
+        );
+        let definitions = Vec::<Definition>::try_from(StoredDefinitions(frame)).unwrap();
+        assert_eq!(definitions.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn should_load_decoders_of_dictionaries_imported_elsewhere() {
+        let (_config, db) = temp_db("compression-test").await;
+        let index: DictionaryIndex =
+            serde_json::from_str(r#"{"title":"Test","revision":"1","format":3}"#).unwrap();
+        let terms: Vec<_> = (0..200)
+            .map(|i| {
+                serde_json::from_value(serde_json::json!([
+                    format!("語{}", i),
+                    "よみ",
+                    "",
+                    "",
+                    0,
+                    [format!("meaning {} of the word", i)],
+                    0,
+                    ""
+                ]))
+                .unwrap()
+            })
+            .collect();
+        let mut writer = db.begin_dictionary(&index).await.unwrap();
+        writer.insert_terms(&terms).await.unwrap();
+        let dictionary_id = writer.commit().await.unwrap();
+        let compression = db.query_definition_compression().await.unwrap();
+        let trained_id = compression[0].trained_id.unwrap() as u32;
+
+        // As if another process had imported it
+        DECODERS.write().unwrap().remove(&trained_id);
+        let entries: Vec<DictionaryEntry> = db
+            .query_dictionary_rows("dictionary_entry", dictionary_id, 0, 1)
+            .await
+            .unwrap();
+        assert_eq!(entries[0].expression, "語0");
+        assert!(has_decoder(trained_id));
+
+        DECODERS.write().unwrap().remove(&trained_id);
+        let entries = db
+            .query_dictionary_entry_by_normalized(&["よみ".to_string()], SearchField::Reading, 1)
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 200);
+    }
+}
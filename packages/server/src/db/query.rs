@@ -1,177 +1,1080 @@
-use crate::db::tables::{DefinitionTag, Dictionary, DictionaryEntry};
-use crate::schemas::dictionary_index::DictionaryIndex;
-use crate::schemas::dictionary_tag_bank_v3::DictionaryTagBankV3;
-use crate::schemas::dictionary_term_bank_v3::DictionaryTermBankV3;
-use crate::util::progress::get_progress_bar;
-use sqlx::Row;
+use crate::db::compression::{self, DefinitionCompressor, MAX_TRAINING_SAMPLES};
+use crate::db::tables::{
+    DefinitionCompression, DefinitionTag, Dictionary, DictionaryEntry, KanjiEntry, KanjiMeta,
+    MergeMode, Profile, TermMeta,
+};
+use crate::schemas::dictionary_term_meta_bank_v3::TermMetaMode;
+use crate::util::kana;
+use crate::util::translator::MAX_SCAN_LENGTH;
+use anyhow::{Context, bail};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use validator::Validate;
 
 use super::*;
 
+/// Which columns of `dictionary_entry` a lookup is matched against.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum SearchField {
+    Expression,
+    Reading,
+    #[default]
+    Any,
+}
+
+/// Which readings of `kanji_entry` a reading lookup is matched against.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum KanjiReadingField {
+    Onyomi,
+    Kunyomi,
+    #[default]
+    Any,
+}
+
+/// The profile used when a request doesn't select one, it can't be removed.
+pub const DEFAULT_PROFILE_ID: i32 = 1;
+
+/// Tables holding the rows imported with a dictionary.
+pub const DICTIONARY_TABLES: [&str; 6] = [
+    "dictionary_entry",
+    "definition_tag",
+    "term_meta",
+    "kanji_entry",
+    "kanji_meta",
+    "definition_compression",
+];
+
+/// Terms bound per lookup query, kept well below the SQLite bind parameter limit.
+const MAX_TERM_BINDS: usize = 1000;
+
+/// Number of entries per page of full-text search results when no size is given.
+pub const DEFAULT_PAGE_SIZE: u32 = 20;
+
+/// Most entries per page of full-text search results.
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+/// How the text of a full-text search is matched against a column.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum FulltextMode {
+    Prefix,
+    Suffix,
+    #[default]
+    Substring,
+}
+
+/// Which columns of `dictionary_entry_fts` a full-text search is matched against.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum FulltextField {
+    Expression,
+    Reading,
+    /// Plain text of the definitions.
+    Glossary,
+    #[default]
+    Any,
+}
+
+/// Longest scan length a profile can default to, the longest a scan looks at.
+const MAX_PROFILE_SCAN_LENGTH: i32 = MAX_SCAN_LENGTH as i32;
+
+/// Options of a profile to create or change, the ones left out are kept or take their default.
+#[derive(Debug, Default, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileUpdate {
+    pub name: Option<String>,
+    /// 0 removes the limit.
+    #[validate(range(min = 0))]
+    pub max_results: Option<i32>,
+    #[validate(range(min = 1, max = MAX_PROFILE_SCAN_LENGTH))]
+    pub scan_length: Option<i32>,
+    pub deinflect: Option<bool>,
+    pub search_field: Option<SearchField>,
+}
+
+/// Changes to the settings of a dictionary, the ones left out are kept.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DictionarySettingsUpdate {
+    pub priority: Option<i32>,
+    pub enabled: Option<bool>,
+    /// An empty alias removes it.
+    pub alias: Option<String>,
+    pub collapsed: Option<bool>,
+    pub merge_mode: Option<MergeMode>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FulltextResult {
+    pub entries: Vec<DictionaryEntry>,
+    /// Number of matching entries across every page.
+    pub total: i64,
+    pub page: u32,
+    pub per_page: u32,
+}
+
+/// A frequency of a term from a frequency dictionary, see [`Db::query_term_frequencies`].
+#[derive(Debug, sqlx::FromRow)]
+pub struct TermFrequency {
+    pub dictionary_id: i32,
+    pub expression: String,
+    /// None when the frequency is not restricted to a reading.
+    pub reading: Option<String>,
+    pub frequency: f64,
+    /// Whether the dictionary counts occurrences, where more is more common, instead of ranks.
+    pub occurrence_based: bool,
+}
+
 impl Db {
-    pub async fn insert_dictionary_data(
-        &self,
-        dict: &DictionaryIndex,
-        entries: &DictionaryTermBankV3,
-        tags: &DictionaryTagBankV3,
-    ) -> anyhow::Result<i32> {
-        let mut tx = self.pool.begin().await?;
+    pub async fn vacuum(&self) -> anyhow::Result<()> {
+        sqlx::query("VACUUM").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    pub async fn analyze(&self) -> anyhow::Result<()> {
+        sqlx::query("ANALYZE").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    /// Size of the database in bytes, free pages included.
+    pub async fn query_database_size(&self) -> anyhow::Result<u64> {
+        let page_count: i64 = sqlx::query_scalar("PRAGMA page_count")
+            .fetch_one(&self.pool)
+            .await?;
+        let page_size: i64 = sqlx::query_scalar("PRAGMA page_size")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok((page_count * page_size) as u64)
+    }
+
+    /// Fills in the normalized columns of entries imported before they existed, a thousand
+    /// entries per statement. Entries are visited in order of id, so the ones that normalize
+    /// to an empty expression are only passed over.
+    pub async fn normalize_dictionary_entries(&self) -> anyhow::Result<()> {
+        let mut after_id = 0;
+        loop {
+            let rows: Vec<(i32, String, String)> = sqlx::query_as(
+                r#"--sql
+                SELECT id, expression, reading FROM dictionary_entry
+                WHERE expression_normalized = '' AND id > ? ORDER BY id LIMIT 1000
+                "#,
+            )
+            .bind(after_id)
+            .fetch_all(&self.pool)
+            .await?;
+            let Some((last_id, _, _)) = rows.last() else {
+                return Ok(());
+            };
+            after_id = *last_id;
 
-        let row = sqlx::query(
-            r#"--sql
-            INSERT INTO dictionary (
-                title, revision, author, description, attribution, url,
-                source_language, target_language, frequency_mode,
-                format, sequenced, minimum_yomitan_version,
-                is_updatable, index_url, download_url, tag_meta
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-            RETURNING id
-            "#,
-        )
-        .bind(&dict.title)
-        .bind(&dict.revision)
-        .bind(&dict.author)
-        .bind(&dict.description)
-        .bind(&dict.attribution)
-        .bind(&dict.url)
-        .bind(&dict.source_language)
-        .bind(&dict.target_language)
-        .bind(&dict.frequency_mode)
-        .bind(dict.get_format())
-        .bind(dict.sequenced)
-        .bind(&dict.minimum_yomitan_version)
-        .bind(dict.is_updatable)
-        .bind(&dict.index_url)
-        .bind(&dict.download_url)
-        .bind(serde_json::to_string(&dict.tag_meta)?)
-        .fetch_one(&mut *tx)
-        .await?;
-        let dictionary_id: i32 = row.get(0);
-
-        let pb = get_progress_bar(entries.len() as u64);
-        let chunk_size = 100;
-        for chunk in entries.chunks(chunk_size) {
             let mut query_builder = sqlx::QueryBuilder::new(
-                r#"-- sql
-                INSERT INTO dictionary_entry (
-                    dictionary_id, expression, reading, definitions,
-                    rules, score, sequence, definition_tags, expression_tags
-                )"#,
+                r#"--sql
+                UPDATE dictionary_entry
+                SET expression_normalized = normalized.column2,
+                    reading_normalized = normalized.column3
+                FROM ("#,
             );
-
-            query_builder.push_values(chunk, |mut b, entry| {
-                let expression = &entry.0;
-                pb.set_message(expression.to_string());
-                let defs_json = serde_json::to_string(&entry.5).unwrap();
-                b.push_bind(dictionary_id)
-                    .push_bind(&entry.0)
-                    .push_bind(&entry.1)
-                    .push_bind(defs_json)
-                    .push_bind(&entry.3)
-                    .push_bind(entry.4)
-                    .push_bind(entry.6)
-                    .push_bind(&entry.2)
-                    .push_bind(&entry.7);
+            query_builder.push_values(rows, |mut b, (id, expression, reading)| {
+                b.push_bind(id)
+                    .push_bind(kana::normalize(&expression))
+                    .push_bind(normalize_reading(&expression, &reading));
             });
-
-            let query = query_builder.build();
-            query.execute(&mut *tx).await?;
-            pb.inc(chunk_size as u64);
+            query_builder.push(") AS normalized WHERE dictionary_entry.id = normalized.column1");
+            query_builder.build().execute(&self.pool).await?;
         }
-        pb.finish_and_clear();
+    }
+
+    /// Compresses the definitions of dictionaries imported before they were stored compressed,
+    /// one dictionary per transaction, and returns how much each one saved. Their rows stay
+    /// readable until then, and the space saved is only freed once the database is compacted.
+    pub async fn compress_dictionary_entries(&self) -> anyhow::Result<Vec<DefinitionCompression>> {
+        let dictionary_ids: Vec<i32> = sqlx::query_scalar(
+            r#"--sql
+            SELECT id FROM dictionary
+            WHERE id NOT IN (SELECT dictionary_id FROM definition_compression)
+            ORDER BY id
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
 
-        for tag in tags {
+        let mut compressed = Vec::with_capacity(dictionary_ids.len());
+        for dictionary_id in dictionary_ids {
+            let mut tx = self.pool.begin().await?;
+            let samples: Vec<String> = sqlx::query_scalar(
+                r#"--sql
+                SELECT definitions FROM dictionary_entry
+                WHERE dictionary_id = ? ORDER BY id LIMIT ?
+                "#,
+            )
+            .bind(dictionary_id)
+            .bind(MAX_TRAINING_SAMPLES as i64)
+            .fetch_all(&mut *tx)
+            .await?;
+            let mut compressor = DefinitionCompressor::train(&samples)?;
+
+            let mut after_id = 0;
+            loop {
+                let rows = sqlx::query(
+                    r#"--sql
+                    SELECT id, definitions FROM dictionary_entry
+                    WHERE dictionary_id = ? AND id > ? ORDER BY id LIMIT 1000
+                    "#,
+                )
+                .bind(dictionary_id)
+                .bind(after_id)
+                .fetch_all(&mut *tx)
+                .await?;
+                let Some(last) = rows.last() else {
+                    break;
+                };
+                after_id = last.get("id");
+
+                for row in rows {
+                    let id: i32 = row.get("id");
+                    let definitions: String = row.get("definitions");
+                    sqlx::query(
+                        r#"--sql
+                        UPDATE dictionary_entry SET definitions = ? WHERE id = ?
+                        "#,
+                    )
+                    .bind(compressor.compress(&definitions)?)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+            }
+
+            let trained = compressor.trained();
             sqlx::query(
-                r#"-- sql
-                INSERT INTO definition_tag (dictionary_id, name, category, "order", notes, score)
-                VALUES (?, ?, ?, ?, ?, ?)
+                r#"--sql
+                INSERT INTO definition_compression (
+                    dictionary_id, trained_id, trained, raw_size, stored_size
+                ) VALUES (?, ?, ?, ?, ?)
                 "#,
             )
             .bind(dictionary_id)
-            .bind(&tag.0)
-            .bind(&tag.1)
-            .bind(tag.2)
-            .bind(&tag.3)
-            .bind(tag.4)
+            .bind(trained.map(|(trained_id, _)| trained_id as i64))
+            .bind(trained.map(|(_, trained)| trained))
+            .bind(compressor.raw_size)
+            .bind(compressor.stored_size)
             .execute(&mut *tx)
             .await?;
+            tx.commit().await?;
+            compressed.push(DefinitionCompression {
+                dictionary_id,
+                trained_id: trained.map(|(trained_id, _)| trained_id as i64),
+                raw_size: compressor.raw_size,
+                stored_size: compressor.stored_size,
+            });
+        }
+        Ok(compressed)
+    }
+
+    /// Prepares the trained dictionaries that aren't yet, so the definitions compressed with
+    /// them can be read. Dictionaries may have been imported by another process.
+    pub async fn load_definition_decoders(&self) -> anyhow::Result<()> {
+        let trained_ids: Vec<i64> = sqlx::query_scalar(
+            r#"--sql
+            SELECT trained_id FROM definition_compression WHERE trained_id IS NOT NULL
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        for trained_id in trained_ids {
+            if compression::has_decoder(trained_id as u32) {
+                continue;
+            }
+            let trained: Vec<u8> = sqlx::query_scalar(
+                r#"--sql
+                SELECT trained FROM definition_compression WHERE trained_id = ? LIMIT 1
+                "#,
+            )
+            .bind(trained_id)
+            .fetch_one(&self.pool)
+            .await?;
+            compression::add_decoder(trained_id as u32, &trained);
         }
+        Ok(())
+    }
 
-        tx.commit().await?;
-        Ok(dictionary_id)
+    /// Reads `rows` as `T`. Definitions compressed with a dictionary that isn't loaded, one
+    /// imported by another process, have the decoders loaded first.
+    async fn read_rows<T>(&self, rows: &[SqliteRow]) -> anyhow::Result<Vec<T>>
+    where
+        T: for<'r> FromRow<'r, SqliteRow>,
+    {
+        let is_missing_decoder = rows.iter().any(|row| {
+            row.try_get::<&[u8], _>("definitions")
+                .is_ok_and(compression::is_missing_decoder)
+        });
+        if is_missing_decoder {
+            self.load_definition_decoders().await?;
+        }
+        Ok(rows.iter().map(T::from_row).collect::<Result<_, _>>()?)
     }
 
-    pub async fn _vacuum(&self) -> anyhow::Result<()> {
-        sqlx::query("VACUUM").execute(&self.pool).await?;
-        Ok(())
+    pub async fn query_definition_compression(&self) -> anyhow::Result<Vec<DefinitionCompression>> {
+        let row: Vec<DefinitionCompression> = sqlx::query_as(
+            r#"--sql
+            SELECT dictionary_id, trained_id, raw_size, stored_size FROM definition_compression
+            ORDER BY dictionary_id
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(row)
     }
 
-    pub async fn query_dictionary_entry_by(
+    /// Finds entries of enabled dictionaries whose normalized expression or reading is one of
+    /// `terms`, ordered by the priority of their dictionary. `terms` must already be normalized
+    /// with [`kana::normalize`]. Many terms are looked up over several queries.
+    pub async fn query_dictionary_entry_by_normalized(
         &self,
-        expression: String,
+        terms: &[String],
+        field: SearchField,
+        profile_id: i32,
     ) -> anyhow::Result<Vec<DictionaryEntry>> {
-        let row: Vec<DictionaryEntry> = sqlx::query_as(
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+        let columns: &[&str] = match field {
+            SearchField::Expression => &["expression_normalized"],
+            SearchField::Reading => &["reading_normalized"],
+            SearchField::Any => &["expression_normalized", "reading_normalized"],
+        };
+        let mut seen = HashSet::new();
+        let mut entries: Vec<(i32, DictionaryEntry)> = Vec::new();
+        // Each column binds every term of the chunk
+        for chunk in terms.chunks(MAX_TERM_BINDS / columns.len()) {
+            let mut query_builder = sqlx::QueryBuilder::new(
+                r#"--sql
+                SELECT dictionary_entry.*, dictionary_settings.priority FROM dictionary_entry
+                JOIN dictionary_settings USING (dictionary_id)
+                WHERE dictionary_settings.enabled AND dictionary_settings.profile_id = "#,
+            );
+            query_builder.push_bind(profile_id).push(" AND (");
+            for (i, column) in columns.iter().enumerate() {
+                if i > 0 {
+                    query_builder.push(" OR ");
+                }
+                query_builder.push(column).push(" IN (");
+                let mut separated = query_builder.separated(", ");
+                for term in chunk {
+                    separated.push_bind(term);
+                }
+                separated.push_unseparated(")");
+            }
+            query_builder.push(")");
+
+            let rows = query_builder.build().fetch_all(&self.pool).await?;
+            let chunk_entries: Vec<DictionaryEntry> = self.read_rows(&rows).await?;
+            for (row, entry) in rows.iter().zip(chunk_entries) {
+                // An entry can match its expression in one chunk and its reading in another
+                if seen.insert(entry.id) {
+                    entries.push((row.get("priority"), entry));
+                }
+            }
+        }
+        entries.sort_by_key(|(priority, entry)| (Reverse(*priority), entry.id));
+        Ok(entries.into_iter().map(|(_, entry)| entry).collect())
+    }
+
+    /// Entries with the given `(dictionary_id, sequence)`, ordered by id. Sequences of 0 and
+    /// below are left out, they are shared by every entry without one. Many sequences are looked
+    /// up over several queries.
+    pub async fn query_dictionary_entry_by_sequence(
+        &self,
+        sequences: &[(i32, i32)],
+    ) -> anyhow::Result<Vec<DictionaryEntry>> {
+        if sequences.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut entries: Vec<DictionaryEntry> = Vec::new();
+        // Each sequence binds its dictionary id too
+        for chunk in sequences.chunks(MAX_TERM_BINDS / 2) {
+            let mut query_builder = sqlx::QueryBuilder::new(
+                r#"--sql
+                SELECT * FROM dictionary_entry
+                WHERE sequence > 0 AND (dictionary_id, sequence) IN ("#,
+            );
+            query_builder.push_values(chunk, |mut b, (dictionary_id, sequence)| {
+                b.push_bind(dictionary_id).push_bind(sequence);
+            });
+            query_builder.push(")");
+            let rows = query_builder.build().fetch_all(&self.pool).await?;
+            entries.extend(self.read_rows::<DictionaryEntry>(&rows).await?);
+        }
+        entries.sort_by_key(|entry| entry.id);
+        entries.dedup_by_key(|entry| entry.id);
+        Ok(entries)
+    }
+
+    /// Searches expressions, readings and definitions of enabled dictionaries, ordered by the
+    /// priority of their dictionary and then by score. `page` starts at 1.
+    pub async fn search_dictionary_entry_fulltext(
+        &self,
+        text: &str,
+        mode: FulltextMode,
+        field: FulltextField,
+        page: u32,
+        per_page: u32,
+        profile_id: i32,
+    ) -> anyhow::Result<FulltextResult> {
+        let columns: &[&str] = match field {
+            FulltextField::Expression => &["expression"],
+            FulltextField::Reading => &["reading"],
+            FulltextField::Glossary => &["glossary"],
+            FulltextField::Any => &["expression", "reading", "glossary"],
+        };
+        // Can't overflow with pages of at most MAX_PAGE_SIZE entries
+        let offset = i64::from(page.saturating_sub(1)) * i64::from(per_page);
+        let (pattern, escaped) = like_pattern(text, mode);
+        // Each column is searched separately, so every LIKE can use the trigram index
+        let push_matches = |query_builder: &mut sqlx::QueryBuilder<'_, sqlx::Sqlite>| {
+            for (i, column) in columns.iter().enumerate() {
+                if i > 0 {
+                    query_builder.push(" UNION ");
+                }
+                query_builder
+                    .push("SELECT rowid FROM dictionary_entry_fts WHERE ")
+                    .push(column)
+                    .push(" LIKE ")
+                    .push_bind(pattern.clone());
+                if escaped {
+                    query_builder.push(" ESCAPE '\\'");
+                }
+            }
+        };
+
+        let mut query_builder = sqlx::QueryBuilder::new(
+            r#"--sql
+            SELECT COUNT(*) FROM dictionary_entry
+            JOIN dictionary_settings USING (dictionary_id)
+            WHERE dictionary_settings.enabled AND dictionary_settings.profile_id = "#,
+        );
+        query_builder
+            .push_bind(profile_id)
+            .push(" AND dictionary_entry.id IN (");
+        push_matches(&mut query_builder);
+        query_builder.push(")");
+        let total: i64 = query_builder
+            .build_query_scalar()
+            .fetch_one(&self.pool)
+            .await?;
+
+        let mut query_builder = sqlx::QueryBuilder::new(
+            r#"--sql
+            SELECT dictionary_entry.* FROM dictionary_entry
+            JOIN dictionary_settings USING (dictionary_id)
+            WHERE dictionary_settings.enabled AND dictionary_settings.profile_id = "#,
+        );
+        query_builder
+            .push_bind(profile_id)
+            .push(" AND dictionary_entry.id IN (");
+        push_matches(&mut query_builder);
+        query_builder
+            .push(
+                r#")
+                ORDER BY dictionary_settings.priority DESC, dictionary_entry.score DESC,
+                    dictionary_entry.id
+                LIMIT "#,
+            )
+            .push_bind(per_page)
+            .push(" OFFSET ")
+            .push_bind(offset);
+        let rows = query_builder.build().fetch_all(&self.pool).await?;
+        let entries: Vec<DictionaryEntry> = self.read_rows(&rows).await?;
+
+        Ok(FulltextResult {
+            entries,
+            total,
+            page,
+            per_page,
+        })
+    }
+
+    pub async fn query_term_meta_by(
+        &self,
+        expression: String,
+        mode: Option<TermMetaMode>,
+        profile_id: i32,
+    ) -> anyhow::Result<Vec<TermMeta>> {
+        let row: Vec<TermMeta> = sqlx::query_as(
             r#"--sql
-            SELECT * FROM dictionary_entry WHERE expression = ?
+            SELECT term_meta.* FROM term_meta
+            JOIN dictionary_settings USING (dictionary_id)
+            WHERE dictionary_settings.enabled AND dictionary_settings.profile_id = ?
+                AND term_meta.expression = ? AND (? IS NULL OR term_meta.mode = ?)
+            ORDER BY dictionary_settings.priority DESC, term_meta.id
             "#,
         )
+        .bind(profile_id)
         .bind(&expression)
+        .bind(mode.map(|m| m.as_str()))
+        .bind(mode.map(|m| m.as_str()))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(row)
+    }
+
+    /// Frequencies of `expressions` in enabled dictionaries, ordered by the priority of their
+    /// dictionary. Many expressions are looked up over several queries.
+    pub async fn query_term_frequencies(
+        &self,
+        expressions: &[String],
+        profile_id: i32,
+    ) -> anyhow::Result<Vec<TermFrequency>> {
+        if expressions.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut frequencies: Vec<(i32, TermFrequency)> = Vec::new();
+        // The profile takes up a bind of each query
+        for chunk in expressions.chunks(MAX_TERM_BINDS - 1) {
+            let mut query_builder = sqlx::QueryBuilder::new(
+                r#"--sql
+                SELECT term_meta.dictionary_id, term_meta.expression, term_meta.reading,
+                    term_meta.frequency,
+                    dictionary.frequency_mode IS 'occurrence-based' AS occurrence_based,
+                    dictionary_settings.priority
+                FROM term_meta
+                JOIN dictionary ON dictionary.id = term_meta.dictionary_id
+                JOIN dictionary_settings USING (dictionary_id)
+                WHERE dictionary_settings.enabled AND dictionary_settings.profile_id = "#,
+            );
+            query_builder
+                .push_bind(profile_id)
+                .push(" AND term_meta.mode = 'freq' AND term_meta.frequency IS NOT NULL")
+                .push(" AND term_meta.expression IN (");
+            let mut separated = query_builder.separated(", ");
+            for expression in chunk {
+                separated.push_bind(expression);
+            }
+            separated.push_unseparated(")");
+
+            let rows = query_builder.build().fetch_all(&self.pool).await?;
+            for row in rows {
+                frequencies.push((row.get("priority"), TermFrequency::from_row(&row)?));
+            }
+        }
+        frequencies
+            .sort_by_key(|(priority, frequency)| (Reverse(*priority), frequency.dictionary_id));
+        Ok(frequencies
+            .into_iter()
+            .map(|(_, frequency)| frequency)
+            .collect())
+    }
+
+    /// Tags named `names` defined by the dictionaries `dictionary_ids`, ordered by id. Many
+    /// names are looked up over several queries.
+    pub async fn query_definition_tags_in(
+        &self,
+        dictionary_ids: &[i32],
+        names: &[String],
+    ) -> anyhow::Result<Vec<DefinitionTag>> {
+        if dictionary_ids.is_empty() || names.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut tags: Vec<DefinitionTag> = Vec::new();
+        // Only the names are split up, there is one dictionary id per dictionary
+        for chunk in names.chunks(MAX_TERM_BINDS) {
+            let mut query_builder = sqlx::QueryBuilder::new(
+                r#"--sql
+                SELECT * FROM definition_tag WHERE dictionary_id IN ("#,
+            );
+            let mut separated = query_builder.separated(", ");
+            for dictionary_id in dictionary_ids {
+                separated.push_bind(dictionary_id);
+            }
+            separated.push_unseparated(") AND name IN (");
+            let mut separated = query_builder.separated(", ");
+            for name in chunk {
+                separated.push_bind(name);
+            }
+            separated.push_unseparated(")");
+            tags.extend(
+                query_builder
+                    .build_query_as::<DefinitionTag>()
+                    .fetch_all(&self.pool)
+                    .await?,
+            );
+        }
+        tags.sort_by_key(|tag| tag.id);
+        Ok(tags)
+    }
+
+    pub async fn query_kanji_entry_by(
+        &self,
+        character: String,
+        profile_id: i32,
+    ) -> anyhow::Result<Vec<KanjiEntry>> {
+        let row: Vec<KanjiEntry> = sqlx::query_as(
+            r#"--sql
+            SELECT kanji_entry.* FROM kanji_entry
+            JOIN dictionary_settings USING (dictionary_id)
+            WHERE dictionary_settings.enabled AND dictionary_settings.profile_id = ?
+                AND kanji_entry.character = ?
+            ORDER BY dictionary_settings.priority DESC, kanji_entry.id
+            "#,
+        )
+        .bind(profile_id)
+        .bind(&character)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(row)
+    }
+
+    /// Kanji with `reading` among their onyomi or kunyomi. Readings are compared whole, kana
+    /// normalized and without the `.` and `-` marking okurigana and affixes, so `たべる`
+    /// matches the kunyomi `た.べる` and `しょう` the onyomi `ショウ`.
+    pub async fn query_kanji_entry_by_reading(
+        &self,
+        reading: &str,
+        field: KanjiReadingField,
+        profile_id: i32,
+    ) -> anyhow::Result<Vec<KanjiEntry>> {
+        let reading = kana::normalize(reading);
+        // Onyomi are written in katakana, kunyomi in hiragana
+        let onyomi = format!(" {} ", kana::to_katakana(&reading));
+        let kunyomi = format!(" {} ", reading);
+        let row: Vec<KanjiEntry> = sqlx::query_as(
+            r#"--sql
+            SELECT kanji_entry.* FROM kanji_entry
+            JOIN dictionary_settings USING (dictionary_id)
+            WHERE dictionary_settings.enabled AND dictionary_settings.profile_id = ?
+                AND (
+                    ? AND instr(
+                        ' ' || replace(replace(kanji_entry.onyomi, '.', ''), '-', '') || ' ', ?
+                    ) > 0
+                    OR ? AND instr(
+                        ' ' || replace(replace(kanji_entry.kunyomi, '.', ''), '-', '') || ' ', ?
+                    ) > 0
+                )
+            ORDER BY dictionary_settings.priority DESC, kanji_entry.id
+            "#,
+        )
+        .bind(profile_id)
+        .bind(field != KanjiReadingField::Kunyomi)
+        .bind(&onyomi)
+        .bind(field != KanjiReadingField::Onyomi)
+        .bind(&kunyomi)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(row)
+    }
+
+    pub async fn query_kanji_meta_by(
+        &self,
+        character: String,
+        profile_id: i32,
+    ) -> anyhow::Result<Vec<KanjiMeta>> {
+        let row: Vec<KanjiMeta> = sqlx::query_as(
+            r#"--sql
+            SELECT kanji_meta.* FROM kanji_meta
+            JOIN dictionary_settings USING (dictionary_id)
+            WHERE dictionary_settings.enabled AND dictionary_settings.profile_id = ?
+                AND kanji_meta.character = ?
+            ORDER BY dictionary_settings.priority DESC, kanji_meta.id
+            "#,
+        )
+        .bind(profile_id)
+        .bind(&character)
         .fetch_all(&self.pool)
         .await?;
         Ok(row)
     }
 
-    pub async fn query_dictionaries(&self) -> anyhow::Result<Vec<Dictionary>> {
+    pub async fn query_dictionaries(&self, profile_id: i32) -> anyhow::Result<Vec<Dictionary>> {
         let row: Vec<Dictionary> = sqlx::query_as(
             r#"--sql
-            SELECT * FROM dictionary
+            SELECT dictionary.*, dictionary_settings.* FROM dictionary
+            JOIN dictionary_settings ON dictionary_settings.dictionary_id = dictionary.id
+                AND dictionary_settings.profile_id = ?
+            ORDER BY dictionary_settings.priority DESC, dictionary.id
             "#,
         )
+        .bind(profile_id)
         .fetch_all(&self.pool)
         .await?;
         Ok(row)
     }
 
-    pub async fn query_dictionary(&self, dictionary_id: i32) -> anyhow::Result<Option<Dictionary>> {
+    pub async fn query_dictionary(
+        &self,
+        dictionary_id: i32,
+        profile_id: i32,
+    ) -> anyhow::Result<Option<Dictionary>> {
         let row: Option<Dictionary> = sqlx::query_as(
             r#"--sql
-            SELECT * FROM dictionary WHERE id = ?
+            SELECT dictionary.*, dictionary_settings.* FROM dictionary
+            JOIN dictionary_settings ON dictionary_settings.dictionary_id = dictionary.id
+                AND dictionary_settings.profile_id = ?
+            WHERE dictionary.id = ?
             "#,
         )
+        .bind(profile_id)
         .bind(dictionary_id)
         .fetch_optional(&self.pool)
         .await?;
         Ok(row)
     }
 
-    //TODO: delete dictionary directory after delete
+    /// Dictionaries with an index url to check for updates, optionally only `dictionary_id`,
+    /// along with their settings in the default profile.
+    pub async fn query_updatable_dictionaries(
+        &self,
+        dictionary_id: Option<i32>,
+    ) -> anyhow::Result<Vec<Dictionary>> {
+        let row: Vec<Dictionary> = sqlx::query_as(
+            r#"--sql
+            SELECT dictionary.*, dictionary_settings.* FROM dictionary
+            JOIN dictionary_settings ON dictionary_settings.dictionary_id = dictionary.id
+                AND dictionary_settings.profile_id = ?
+            WHERE is_updatable = 1 AND index_url IS NOT NULL AND (? IS NULL OR dictionary.id = ?)
+            "#,
+        )
+        .bind(DEFAULT_PROFILE_ID)
+        .bind(dictionary_id)
+        .bind(dictionary_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(row)
+    }
+
+    /// Dictionaries sharing `title`, the most recently imported last, along with their settings
+    /// in the default profile.
+    pub async fn query_dictionaries_by_title(
+        &self,
+        title: &str,
+    ) -> anyhow::Result<Vec<Dictionary>> {
+        let row: Vec<Dictionary> = sqlx::query_as(
+            r#"--sql
+            SELECT dictionary.*, dictionary_settings.* FROM dictionary
+            JOIN dictionary_settings ON dictionary_settings.dictionary_id = dictionary.id
+                AND dictionary_settings.profile_id = ?
+            WHERE title = ? ORDER BY dictionary.id
+            "#,
+        )
+        .bind(DEFAULT_PROFILE_ID)
+        .bind(title)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(row)
+    }
+
+    /// Removes a dictionary along with its rows and the settings of every profile, returning it
+    /// with the settings of the default profile as it was when removed. `before_commit` runs
+    /// once the rows are gone but not yet committed, and nothing is removed if it fails.
     pub async fn query_delete_dictionary(
         &self,
         dictionary_id: i32,
+        before_commit: impl FnOnce() -> anyhow::Result<()>,
     ) -> anyhow::Result<Option<Dictionary>> {
-        let row: Option<Dictionary> = sqlx::query_as(
+        let mut tx = self.pool.begin().await?;
+        let dictionary: Option<Dictionary> = sqlx::query_as(
+            r#"--sql
+            SELECT dictionary.*, dictionary_settings.* FROM dictionary
+            JOIN dictionary_settings ON dictionary_settings.dictionary_id = dictionary.id
+                AND dictionary_settings.profile_id = ?
+            WHERE dictionary.id = ?
+            "#,
+        )
+        .bind(DEFAULT_PROFILE_ID)
+        .bind(dictionary_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(dictionary) = dictionary else {
+            return Ok(None);
+        };
+        sqlx::query(
             r#"--sql
             DELETE FROM dictionary WHERE id = ?
-            RETURNING *
             "#,
         )
         .bind(dictionary_id)
+        .execute(&mut *tx)
+        .await?;
+        before_commit()?;
+        tx.commit().await?;
+        Ok(Some(dictionary))
+    }
+
+    pub async fn query_dictionary_ids(&self) -> anyhow::Result<Vec<i32>> {
+        let row: Vec<i32> = sqlx::query_scalar(
+            r#"--sql
+            SELECT id FROM dictionary ORDER BY id
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(row)
+    }
+
+    /// Removes rows left without their dictionary or profile, which happens when they were
+    /// written with foreign keys off. Returns the number of rows removed from each table.
+    pub async fn query_delete_orphan_rows(&self) -> anyhow::Result<Vec<(&'static str, u64)>> {
+        let mut tx = self.pool.begin().await?;
+        let mut removed = Vec::new();
+        for table in DICTIONARY_TABLES {
+            let result = sqlx::query(&format!(
+                "DELETE FROM {} WHERE dictionary_id NOT IN (SELECT id FROM dictionary)",
+                table
+            ))
+            .execute(&mut *tx)
+            .await?;
+            removed.push((table, result.rows_affected()));
+        }
+        let result = sqlx::query(
+            r#"--sql
+            DELETE FROM dictionary_settings
+            WHERE dictionary_id NOT IN (SELECT id FROM dictionary)
+                OR profile_id NOT IN (SELECT id FROM profile)
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+        removed.push(("dictionary_settings", result.rows_affected()));
+        let result = sqlx::query(
+            r#"--sql
+            DELETE FROM dictionary_entry_fts WHERE rowid NOT IN (SELECT id FROM dictionary_entry)
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+        removed.push(("dictionary_entry_fts", result.rows_affected()));
+        tx.commit().await?;
+        Ok(removed)
+    }
+
+    /// Applies `update` to the settings of a dictionary in a profile and returns the dictionary
+    /// afterwards.
+    pub async fn query_update_dictionary_settings(
+        &self,
+        dictionary_id: i32,
+        profile_id: i32,
+        update: &DictionarySettingsUpdate,
+    ) -> anyhow::Result<Option<Dictionary>> {
+        sqlx::query(
+            r#"--sql
+            UPDATE dictionary_settings SET
+                priority = coalesce(?, priority),
+                enabled = coalesce(?, enabled),
+                alias = CASE WHEN ? IS NULL THEN alias ELSE nullif(?, '') END,
+                collapsed = coalesce(?, collapsed),
+                merge_mode = coalesce(?, merge_mode)
+            WHERE dictionary_id = ? AND profile_id = ?
+            "#,
+        )
+        .bind(update.priority)
+        .bind(update.enabled)
+        .bind(&update.alias)
+        .bind(&update.alias)
+        .bind(update.collapsed)
+        .bind(update.merge_mode)
+        .bind(dictionary_id)
+        .bind(profile_id)
+        .execute(&self.pool)
+        .await?;
+        self.query_dictionary(dictionary_id, profile_id).await
+    }
+
+    pub async fn query_profiles(&self) -> anyhow::Result<Vec<Profile>> {
+        let row: Vec<Profile> = sqlx::query_as(
+            r#"--sql
+            SELECT * FROM profile ORDER BY id
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(row)
+    }
+
+    pub async fn query_profile(&self, profile_id: i32) -> anyhow::Result<Option<Profile>> {
+        let row: Option<Profile> = sqlx::query_as(
+            r#"--sql
+            SELECT * FROM profile WHERE id = ?
+            "#,
+        )
+        .bind(profile_id)
         .fetch_optional(&self.pool)
         .await?;
         Ok(row)
     }
 
+    /// The profile named `name`, or the default profile when no name is given.
+    pub async fn query_selected_profile(
+        &self,
+        name: Option<&str>,
+    ) -> anyhow::Result<Option<Profile>> {
+        match name {
+            Some(name) => self.query_profile_by_name(name).await,
+            None => self.query_profile(DEFAULT_PROFILE_ID).await,
+        }
+    }
+
+    pub async fn query_profile_by_name(&self, name: &str) -> anyhow::Result<Option<Profile>> {
+        let row: Option<Profile> = sqlx::query_as(
+            r#"--sql
+            SELECT * FROM profile WHERE name = ?
+            "#,
+        )
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row)
+    }
+
+    /// Creates a profile named `name` in which every dictionary has the default settings.
+    pub async fn query_create_profile(
+        &self,
+        name: &str,
+        update: &ProfileUpdate,
+    ) -> anyhow::Result<Profile> {
+        let row: Profile = sqlx::query_as(
+            r#"--sql
+            INSERT INTO profile (name) VALUES (?)
+            RETURNING *
+            "#,
+        )
+        .bind(name)
+        .fetch_one(&self.pool)
+        .await?;
+        let profile = self.query_update_profile(row.id, update).await?;
+        profile.context("Profile was removed while being created")
+    }
+
+    /// Applies `update` to a profile and returns it afterwards.
+    pub async fn query_update_profile(
+        &self,
+        profile_id: i32,
+        update: &ProfileUpdate,
+    ) -> anyhow::Result<Option<Profile>> {
+        let row: Option<Profile> = sqlx::query_as(
+            r#"--sql
+            UPDATE profile SET
+                name = coalesce(?, name),
+                max_results = CASE WHEN ? IS NULL THEN max_results ELSE nullif(?, 0) END,
+                scan_length = coalesce(?, scan_length),
+                deinflect = coalesce(?, deinflect),
+                search_field = coalesce(?, search_field)
+            WHERE id = ?
+            RETURNING *
+            "#,
+        )
+        .bind(&update.name)
+        .bind(update.max_results)
+        .bind(update.max_results)
+        .bind(update.scan_length)
+        .bind(update.deinflect)
+        .bind(update.search_field)
+        .bind(profile_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row)
+    }
+
+    /// Removes a profile along with its dictionary settings. The default profile is kept.
+    pub async fn query_delete_profile(&self, profile_id: i32) -> anyhow::Result<Option<Profile>> {
+        if profile_id == DEFAULT_PROFILE_ID {
+            bail!("The default profile can't be removed");
+        }
+        let row: Option<Profile> = sqlx::query_as(
+            r#"--sql
+            DELETE FROM profile WHERE id = ?
+            RETURNING *
+            "#,
+        )
+        .bind(profile_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row)
+    }
+
+    /// Rows of `table` belonging to `dictionary_id` with an id above `after_id`, in id order.
+    /// Used to read a whole dictionary a page at a time.
+    pub async fn query_dictionary_rows<T>(
+        &self,
+        table: &str,
+        dictionary_id: i32,
+        after_id: i32,
+        limit: u32,
+    ) -> anyhow::Result<Vec<T>>
+    where
+        T: for<'r> FromRow<'r, SqliteRow>,
+    {
+        let rows = sqlx::query(&format!(
+            r#"--sql
+            SELECT * FROM {} WHERE dictionary_id = ? AND id > ? ORDER BY id LIMIT ?
+            "#,
+            table
+        ))
+        .bind(dictionary_id)
+        .bind(after_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        self.read_rows(&rows).await
+    }
+
     pub async fn query_definition_tag_by(
         &self,
         name: String,
+        profile_id: i32,
     ) -> anyhow::Result<Vec<DefinitionTag>> {
         let row: Vec<DefinitionTag> = sqlx::query_as(
             r#"--sql
-            SELECT * FROM definition_tag WHERE name = ?
+            SELECT definition_tag.* FROM definition_tag
+            JOIN dictionary_settings USING (dictionary_id)
+            WHERE dictionary_settings.enabled AND dictionary_settings.profile_id = ?
+                AND definition_tag.name = ?
+            ORDER BY dictionary_settings.priority DESC, definition_tag.id
             "#,
         )
+        .bind(profile_id)
         .bind(&name)
         .fetch_all(&self.pool)
         .await?;
         Ok(row)
     }
 }
+
+/// An empty reading means the reading is the same as the expression.
+pub fn normalize_reading(expression: &str, reading: &str) -> String {
+    if reading.is_empty() {
+        kana::normalize(expression)
+    } else {
+        kana::normalize(reading)
+    }
+}
+
+/// Builds a LIKE pattern for `mode`, escaping wildcards in `text`.
+/// Returns whether an ESCAPE clause is needed, which keeps the index usable when it isn't.
+fn like_pattern(text: &str, mode: FulltextMode) -> (String, bool) {
+    let mut escaped = false;
+    let mut pattern = String::with_capacity(text.len() + 2);
+    if mode != FulltextMode::Prefix {
+        pattern.push('%');
+    }
+    for c in text.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            pattern.push('\\');
+            escaped = true;
+        }
+        pattern.push(c);
+    }
+    if mode != FulltextMode::Suffix {
+        pattern.push('%');
+    }
+    (pattern, escaped)
+}
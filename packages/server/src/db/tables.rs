@@ -1,4 +1,11 @@
-use crate::schemas::{dictionary_index::TagMeta, dictionary_term_bank_v3::Definition};
+use crate::db::compression::StoredDefinitions;
+use crate::db::query::SearchField;
+use crate::schemas::{
+    dictionary_index::TagMeta,
+    dictionary_kanji_bank_v3::KanjiStats,
+    dictionary_term_bank_v3::Definition,
+    dictionary_term_meta_bank_v3::{Frequency, TermMetaData},
+};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
@@ -36,10 +43,59 @@ pub struct Dictionary {
     // Otherwise, you can leave this out if you use separate Tag files
     #[sqlx(json)]
     pub tag_meta: Option<TagMeta>,
+
+    #[sqlx(flatten)]
+    pub settings: DictionarySettings,
 }
 
+/// Settings of a dictionary chosen by the user in one profile, see `dictionary_settings`.
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 #[serde(rename_all = "camelCase")]
+pub struct DictionarySettings {
+    /// Entries of dictionaries with a higher priority come first.
+    pub priority: i32,
+    /// Disabled dictionaries are left out of every lookup.
+    pub enabled: bool,
+    /// Name shown instead of the title.
+    pub alias: Option<String>,
+    /// Whether definitions are shown collapsed at first.
+    pub collapsed: bool,
+    pub merge_mode: MergeMode,
+}
+
+/// Dictionary settings and lookup options selected per request, see `profile`.
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    pub id: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+
+    pub name: String,
+    /// Most entries returned by a lookup, `None` for no limit.
+    pub max_results: Option<i32>,
+    /// Characters looked at by a scan when the request gives no length.
+    pub scan_length: i32,
+    /// Whether lookups also match the deinflected forms of the text.
+    pub deinflect: bool,
+    /// Columns matched by a lookup when the request doesn't say.
+    pub search_field: SearchField,
+}
+
+/// Whether entries of a sequenced dictionary that share a sequence number are merged.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum MergeMode {
+    #[default]
+    Merge,
+    Separate,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
 pub struct DictionaryEntry {
     pub id: i32,
     pub created_at: DateTime<Utc>,
@@ -48,7 +104,7 @@ pub struct DictionaryEntry {
 
     pub expression: String,
     pub reading: String,
-    #[sqlx(json)]
+    #[sqlx(try_from = "StoredDefinitions")]
     pub definitions: Vec<Definition>,
     pub rules: String,
     pub score: f32,
@@ -57,8 +113,19 @@ pub struct DictionaryEntry {
     pub expression_tags: String,
 }
 
+/// How much compressing the definitions of a dictionary saved.
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 #[serde(rename_all = "camelCase")]
+pub struct DefinitionCompression {
+    pub dictionary_id: i32,
+    /// Id of the dictionary trained for the definitions, if there were enough to train one.
+    pub trained_id: Option<i64>,
+    pub raw_size: i64,
+    pub stored_size: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
 pub struct DefinitionTag {
     pub id: i32,
     pub created_at: DateTime<Utc>,
@@ -71,3 +138,52 @@ pub struct DefinitionTag {
     pub notes: String,
     pub score: f32,
 }
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct TermMeta {
+    pub id: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub dictionary_id: i32,
+
+    pub expression: String,
+    pub mode: String,
+    pub reading: Option<String>,
+    pub frequency: Option<f64>,
+    #[sqlx(json)]
+    pub data: TermMetaData,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct KanjiEntry {
+    pub id: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub dictionary_id: i32,
+
+    pub character: String,
+    pub onyomi: String,
+    pub kunyomi: String,
+    pub tags: String,
+    #[sqlx(json)]
+    pub meanings: Vec<String>,
+    #[sqlx(json)]
+    pub stats: KanjiStats,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct KanjiMeta {
+    pub id: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub dictionary_id: i32,
+
+    pub character: String,
+    pub mode: String,
+    pub frequency: Option<f64>,
+    #[sqlx(json)]
+    pub data: Frequency,
+}
@@ -4,6 +4,11 @@
  */
 
 import { Definition } from "../schemas/dictionary_term_bank_v3_types.ts";
+import {
+  Frequency,
+  TermMetaData,
+  TermMetaMode,
+} from "../schemas/dictionary_term_meta_bank_v3_types.ts";
 
 /**
  * Main dictionary table structure
@@ -55,6 +60,143 @@ export interface Dictionary {
   // Metadata stored as JSON
   /** Tag metadata in JSON format */
   tagMeta?: Record<string, any> | null;
+
+  /** Settings chosen by the user */
+  settings: DictionarySettings;
+}
+
+/**
+ * Whether entries sharing a sequence number are merged into one result
+ */
+export type MergeMode = "merge" | "separate";
+
+/**
+ * Settings of a dictionary chosen by the user, kept separately for every profile
+ */
+export interface DictionarySettings {
+  /** Entries of dictionaries with a higher priority come first */
+  priority: number;
+  /** Disabled dictionaries are left out of every lookup */
+  enabled: boolean;
+  /** Name shown instead of the title */
+  alias?: string | null;
+  /** Whether definitions are shown collapsed at first */
+  collapsed: boolean;
+  /** Whether entries sharing a sequence number are merged */
+  mergeMode: MergeMode;
+}
+
+/**
+ * Body of `PATCH /dictionaries/{id}`, settings left out are kept
+ */
+export interface DictionarySettingsUpdate {
+  priority?: number;
+  enabled?: boolean;
+  /** An empty alias removes it */
+  alias?: string;
+  collapsed?: boolean;
+  mergeMode?: MergeMode;
+}
+
+/**
+ * Columns matched by a lookup
+ */
+export type SearchField = "expression" | "reading" | "any";
+
+/**
+ * A set of dictionary settings and lookup options. Requests pick one with the `profile` query
+ * param or the `X-Profile` header, the profile with id 1 is used when they don't.
+ */
+export interface Profile {
+  /** Unique identifier for the profile */
+  id: number;
+  /** Timestamp when profile was created */
+  createdAt: string;
+  /** Timestamp when profile was last updated */
+  updatedAt: string;
+
+  /** Unique name of the profile */
+  name: string;
+  /** Most entries returned by a lookup, no limit when null */
+  maxResults?: number | null;
+  /** Characters looked at by a scan when the request gives no length */
+  scanLength: number;
+  /** Whether lookups also match deinflected forms */
+  deinflect: boolean;
+  /** Columns matched by a lookup when the request doesn't say */
+  searchField: SearchField;
+}
+
+/**
+ * Body of `POST /profiles` and `PATCH /profiles/{name}`, options left out are kept
+ */
+export interface ProfileUpdate {
+  /** Required when creating a profile */
+  name?: string;
+  /** 0 removes the limit */
+  maxResults?: number;
+  scanLength?: number;
+  deinflect?: boolean;
+  searchField?: SearchField;
+}
+
+/**
+ * Result of checking an updatable dictionary for a newer revision
+ */
+export interface UpdateStatus {
+  /** ID of the dictionary */
+  dictionaryId: number;
+  /** Title of the dictionary */
+  title: string;
+  /** Revision currently imported */
+  revision: string;
+  /** Revision found in the remote index */
+  latestRevision: string;
+  /** Whether the remote revision is newer */
+  updateAvailable: boolean;
+  /** Where the latest revision is downloaded from */
+  downloadUrl?: string | null;
+}
+
+/**
+ * Step of a dictionary import
+ */
+export type ImportPhase = "extract" | "parse" | "insert" | "copy";
+
+export type JobStatus = "queued" | "running" | "completed" | "failed" | "cancelled";
+
+/**
+ * Counts of one phase of an import
+ */
+export interface PhaseProgress {
+  /** Phase counted */
+  phase: ImportPhase;
+  /** Banks, files or entries done */
+  position: number;
+  /** Banks, files or entries in the phase, 0 when not known in advance */
+  length: number;
+}
+
+/**
+ * State of a dictionary import or update running in the background
+ */
+export interface JobState {
+  /** ID of the job */
+  id: number;
+  /** Current status */
+  status: JobStatus;
+  /** Step started last, null until the job starts */
+  phase?: ImportPhase | null;
+  /** Every step started so far, in order */
+  phases: PhaseProgress[];
+  /** Bank or file being processed */
+  message?: string | null;
+  /** ID of the imported dictionary once completed */
+  dictionaryId?: number | null;
+  /** Error message if the import failed */
+  error?: string | null;
+  /** Timestamp when the job finished, it is removed an hour later */
+  finishedAt?: string | null;
 }
 
 /**
@@ -87,11 +229,152 @@ export interface DictionaryEntry {
   expressionTags: string;
 }
 
+/**
+ * Dictionary entry found by a lookup, with the inflections that lead to it
+ */
+export interface DictionaryEntryMatch extends DictionaryEntry {
+  /** The text that was looked up */
+  source: string;
+  /** Chains of inflection rules from the entry to the source, empty for exact matches */
+  inflectionRuleChains: string[][];
+  /** Set with `resolve_tags` */
+  resolvedTags?: ResolvedTags;
+}
+
+/**
+ * Tags of an entry resolved with the definition tags of its dictionary, falling back to the
+ * tagMeta of the dictionary. Sorted by order, category and name
+ */
+export interface ResolvedTags {
+  expression: DefinitionTag[];
+  definition: DefinitionTag[];
+}
+
+/**
+ * Key of the `sort` param of `GET /dictionary_entries/search` and `/scan`, a comma-separated
+ * list where each key breaks the ties of the ones before it. Defaults to
+ * `length,exact,frequency,priority,score,tags`
+ */
+export type SortKey = "length" | "exact" | "frequency" | "priority" | "score" | "tags";
+
+/**
+ * `mode` of `GET /dictionary_entries/search`, `merged` returns MergedEntryMatch
+ */
+export type ResultMode = "flat" | "merged";
+
+/**
+ * `format` of `GET /dictionary_entries/search`, `/scan` and `/fulltext`. With `html` every
+ * definition but deinflections is returned as a sanitized `{ type: "html", html }` definition,
+ * with images pointing to `/media/{dictionaryId}/...`
+ */
+export type DefinitionFormat = "json" | "html";
+
+/**
+ * Body of `POST /dictionary_entries/batch`, which returns the results of each expression keyed
+ * by it, as DictionaryEntryMatch[] or MergedEntryMatch[] depending on `mode`
+ */
+export interface BatchSearchBody {
+  /** Expressions or readings to look up, 500 at most */
+  expressions: string[];
+  /** Defaults to the search field of the profile */
+  field?: SearchField;
+  mode?: ResultMode;
+  /** Comma-separated SortKey list */
+  sort?: string;
+  /** Results per expression, defaults to the limit of the profile */
+  limit?: number;
+  resolveTags?: boolean;
+  format?: DefinitionFormat;
+}
+
+/**
+ * A spelling of a merged result
+ */
+export interface Headword {
+  expression: string;
+  reading: string;
+  /** Tags of the expression without duplicates */
+  tags: string[];
+  /** Set with `resolve_tags`, sorted by order, category and name */
+  resolvedTags?: DefinitionTag[];
+}
+
+/**
+ * Definitions a single dictionary gives for a merged result
+ */
+export interface DictionaryDefinitions {
+  dictionaryId: number;
+  /** Entries the definitions come from */
+  entryIds: number[];
+  definitions: Definition[];
+  /** Tags of the definitions without duplicates */
+  tags: string[];
+  /** Set with `resolve_tags`, sorted by order, category and name */
+  resolvedTags?: DefinitionTag[];
+}
+
+/**
+ * Entries of one word merged by sequence number, along with entries of other dictionaries
+ * spelled the same way
+ */
+export interface MergedEntryMatch {
+  /** Every spelling of the word */
+  headwords: Headword[];
+  /** Ordered by dictionary priority */
+  definitions: DictionaryDefinitions[];
+  /** Sequence number the entries were merged by, null for an entry that stands alone */
+  sequence: number | null;
+  /** Deinflection rules of every entry without duplicates */
+  rules: string[];
+  /** Highest score of the entries */
+  score: number;
+  /** The text that was looked up */
+  source: string;
+  /** Chains of inflection rules from the word to the source, empty for exact matches */
+  inflectionRuleChains: string[][];
+}
+
+/**
+ * Entries found for a single prefix of scanned text
+ */
+export interface ScanGroup {
+  /** The prefix of the scanned text */
+  source: string;
+  /** Length of the prefix, same unit as JavaScript string length */
+  sourceLength: number;
+  /** Entries matching the prefix or one of its deinflected forms */
+  entries: DictionaryEntryMatch[];
+}
+
+/**
+ * Result of scanning text for the longest dictionary match
+ */
+export interface ScanResult {
+  /** Length of the longest prefix with a match, 0 when nothing was found */
+  matchedLength: number;
+  /** Groups ordered from the longest to the shortest prefix */
+  groups: ScanGroup[];
+}
+
+/**
+ * A page of full-text search results
+ */
+export interface FulltextResult {
+  /** Entries on this page, ordered by score */
+  entries: DictionaryEntry[];
+  /** Number of matching entries across every page */
+  total: number;
+  /** Page number, starting at 1 */
+  page: number;
+  /** Maximum number of entries per page */
+  perPage: number;
+}
+
 /**
  * Definition tag/category metadata
  */
 export interface DefinitionTag {
-  /** Unique identifier for the tag */
+  /** Unique identifier for the tag, 0 for tags resolved from tagMeta or not described */
   id: number;
   /** Timestamp when tag was created */
   createdAt: string;
@@ -110,3 +393,77 @@ export interface DefinitionTag {
   score: number;
 }
 
+/**
+ * Frequency, pitch accent or IPA metadata for a term
+ */
+export interface TermMeta {
+  /** Unique identifier for the metadata */
+  id: number;
+  /** Timestamp when metadata was created */
+  createdAt: string;
+  /** ID of the parent dictionary */
+  dictionaryId: number;
+
+  /** The expression/text of the term */
+  expression: string;
+  /** Type of metadata */
+  mode: TermMetaMode;
+  /** Reading the metadata is restricted to, if any */
+  reading?: string | null;
+  /** Numeric frequency value for frequency metadata */
+  frequency?: number | null;
+  /** Metadata payload, shape depends on mode */
+  data: TermMetaData;
+}
+
+/**
+ * Individual kanji character entry
+ */
+export interface KanjiEntry {
+  /** Unique identifier for the entry */
+  id: number;
+  /** Timestamp when entry was created */
+  createdAt: string;
+  /** ID of the parent dictionary */
+  dictionaryId: number;
+
+  /** The kanji character */
+  character: string;
+  /** Space-separated onyomi readings */
+  onyomi: string;
+  /** Space-separated kunyomi readings */
+  kunyomi: string;
+  /** Space-separated tags for the character */
+  tags: string;
+  /** Meanings of the character */
+  meanings: string[];
+  /** Stats for the character, keyed by tag name */
+  stats: Record<string, string>;
+}
+
+/**
+ * `field` of `GET /kanji/readings`, which returns the KanjiEntry[] having `reading` among
+ * these readings
+ */
+export type KanjiReadingField = "onyomi" | "kunyomi" | "any";
+
+/**
+ * Frequency metadata for a kanji character
+ */
+export interface KanjiMeta {
+  /** Unique identifier for the metadata */
+  id: number;
+  /** Timestamp when metadata was created */
+  createdAt: string;
+  /** ID of the parent dictionary */
+  dictionaryId: number;
+
+  /** The kanji character */
+  character: string;
+  /** Type of metadata */
+  mode: "freq";
+  /** Numeric frequency value */
+  frequency?: number | null;
+  /** Frequency payload */
+  data: Frequency;
+}
@@ -0,0 +1,551 @@
+use crate::db::Db;
+use crate::db::compression::DefinitionCompressor;
+use crate::db::query::{DICTIONARY_TABLES, normalize_reading};
+use crate::schemas::dictionary_index::DictionaryIndex;
+use crate::schemas::dictionary_kanji_bank_v3::DictionaryKanjiBankV3Row;
+use crate::schemas::dictionary_kanji_meta_bank_v3::DictionaryKanjiMetaBankV3Row;
+use crate::schemas::dictionary_tag_bank_v3::DictionaryTagBankV3Row;
+use crate::schemas::dictionary_term_bank_v3::DictionaryTermBankV3Row;
+use crate::schemas::dictionary_term_meta_bank_v3::DictionaryTermMetaBankV3Row;
+use crate::util::kana;
+use serde_json::Value;
+use sqlx::{Row, Sqlite, Transaction};
+use std::mem;
+
+/// Rows inserted per statement, kept well below the SQLite bind parameter limit.
+const CHUNK_SIZE: usize = 500;
+
+/// Inserts the banks of a single dictionary inside one transaction, so an import that fails
+/// halfway leaves nothing behind. Banks can be written one at a time as they are parsed.
+pub struct DictionaryWriter {
+    tx: Transaction<'static, Sqlite>,
+    dictionary_id: i32,
+    /// Trained on the first terms that are inserted.
+    compressor: Option<DefinitionCompressor>,
+}
+
+impl Db {
+    /// Inserts the dictionary row and returns a writer for its banks.
+    pub async fn begin_dictionary(
+        &self,
+        dict: &DictionaryIndex,
+    ) -> anyhow::Result<DictionaryWriter> {
+        let mut tx = self.pool.begin().await?;
+
+        let row = sqlx::query(
+            r#"--sql
+            INSERT INTO dictionary (
+                title, revision, author, description, attribution, url,
+                source_language, target_language, frequency_mode,
+                format, sequenced, minimum_yomitan_version,
+                is_updatable, index_url, download_url, tag_meta
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            RETURNING id
+            "#,
+        )
+        .bind(&dict.title)
+        .bind(&dict.revision)
+        .bind(&dict.author)
+        .bind(&dict.description)
+        .bind(&dict.attribution)
+        .bind(&dict.url)
+        .bind(&dict.source_language)
+        .bind(&dict.target_language)
+        .bind(&dict.frequency_mode)
+        .bind(dict.get_format())
+        .bind(dict.sequenced)
+        .bind(&dict.minimum_yomitan_version)
+        .bind(dict.is_updatable.unwrap_or(false))
+        .bind(&dict.index_url)
+        .bind(&dict.download_url)
+        .bind(serde_json::to_string(&dict.tag_meta)?)
+        .fetch_one(&mut *tx)
+        .await?;
+        let dictionary_id: i32 = row.get(0);
+
+        Ok(DictionaryWriter {
+            tx,
+            dictionary_id,
+            compressor: None,
+        })
+    }
+
+    /// Overwrites the dictionary row with `dict` and removes its banks, keeping the id and
+    /// every column that doesn't come from the index. Nothing changes until the writer commits.
+    pub async fn begin_dictionary_replace(
+        &self,
+        dictionary_id: i32,
+        dict: &DictionaryIndex,
+    ) -> anyhow::Result<DictionaryWriter> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"--sql
+            UPDATE dictionary SET
+                title = ?, revision = ?, author = ?, description = ?, attribution = ?, url = ?,
+                source_language = ?, target_language = ?, frequency_mode = ?,
+                format = ?, sequenced = ?, minimum_yomitan_version = ?,
+                is_updatable = ?, index_url = ?, download_url = ?, tag_meta = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(&dict.title)
+        .bind(&dict.revision)
+        .bind(&dict.author)
+        .bind(&dict.description)
+        .bind(&dict.attribution)
+        .bind(&dict.url)
+        .bind(&dict.source_language)
+        .bind(&dict.target_language)
+        .bind(&dict.frequency_mode)
+        .bind(dict.get_format())
+        .bind(dict.sequenced)
+        .bind(&dict.minimum_yomitan_version)
+        .bind(dict.is_updatable.unwrap_or(false))
+        .bind(&dict.index_url)
+        .bind(&dict.download_url)
+        .bind(serde_json::to_string(&dict.tag_meta)?)
+        .bind(dictionary_id)
+        .execute(&mut *tx)
+        .await?;
+
+        for table in DICTIONARY_TABLES {
+            sqlx::query(&format!("DELETE FROM {} WHERE dictionary_id = ?", table))
+                .bind(dictionary_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        Ok(DictionaryWriter {
+            tx,
+            dictionary_id,
+            compressor: None,
+        })
+    }
+}
+
+impl DictionaryWriter {
+    pub fn dictionary_id(&self) -> i32 {
+        self.dictionary_id
+    }
+
+    /// Inserts the terms along with their rows of the full-text index. Definitions are
+    /// compressed with a dictionary trained on the first terms inserted.
+    pub async fn insert_terms(
+        &mut self,
+        entries: &[DictionaryTermBankV3Row],
+    ) -> anyhow::Result<()> {
+        let definitions = entries
+            .iter()
+            .map(|entry| serde_json::to_value(&entry.5))
+            .collect::<Result<Vec<Value>, _>>()?;
+        let definitions_json = definitions
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<Result<Vec<String>, _>>()?;
+        let compressor = match &mut self.compressor {
+            Some(compressor) => compressor,
+            None => self
+                .compressor
+                .insert(DefinitionCompressor::train(&definitions_json)?),
+        };
+        let stored = definitions_json
+            .iter()
+            .map(|json| compressor.compress(json))
+            .collect::<anyhow::Result<Vec<Vec<u8>>>>()?;
+
+        for (i, chunk) in entries.chunks(CHUNK_SIZE).enumerate() {
+            let offset = i * CHUNK_SIZE;
+            let mut query_builder = sqlx::QueryBuilder::new(
+                r#"-- sql
+                INSERT INTO dictionary_entry (
+                    dictionary_id, expression, reading, definitions,
+                    rules, score, sequence, definition_tags, expression_tags,
+                    expression_normalized, reading_normalized
+                )"#,
+            );
+
+            query_builder.push_values(chunk.iter().enumerate(), |mut b, (j, entry)| {
+                b.push_bind(self.dictionary_id)
+                    .push_bind(&entry.0)
+                    .push_bind(&entry.1)
+                    .push_bind(&stored[offset + j])
+                    .push_bind(&entry.3)
+                    .push_bind(entry.4)
+                    .push_bind(entry.6)
+                    // No tags are stored as an empty string
+                    .push_bind(entry.2.as_deref().unwrap_or_default())
+                    .push_bind(&entry.7)
+                    .push_bind(kana::normalize(&entry.0))
+                    .push_bind(normalize_reading(&entry.0, &entry.1));
+            });
+            query_builder.push(" RETURNING id");
+
+            // RETURNING gives the ids in no particular order, but AUTOINCREMENT hands them out
+            // increasing in the order of the values
+            let mut ids: Vec<i64> = query_builder
+                .build_query_scalar()
+                .fetch_all(&mut *self.tx)
+                .await?;
+            ids.sort_unstable();
+
+            let mut query_builder = sqlx::QueryBuilder::new(
+                r#"-- sql
+                INSERT INTO dictionary_entry_fts (rowid, expression, reading, glossary)"#,
+            );
+
+            query_builder.push_values(chunk.iter().enumerate(), |mut b, (j, entry)| {
+                b.push_bind(ids[j])
+                    .push_bind(&entry.0)
+                    .push_bind(&entry.1)
+                    .push_bind(glossary(&definitions[offset + j]));
+            });
+
+            query_builder.build().execute(&mut *self.tx).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn insert_term_metas(
+        &mut self,
+        metas: &[DictionaryTermMetaBankV3Row],
+    ) -> anyhow::Result<()> {
+        for chunk in metas.chunks(CHUNK_SIZE) {
+            let mut query_builder = sqlx::QueryBuilder::new(
+                r#"-- sql
+                INSERT INTO term_meta (dictionary_id, expression, mode, reading, frequency, data)"#,
+            );
+
+            query_builder.push_values(chunk, |mut b, meta| {
+                let data_json = serde_json::to_string(&meta.2).unwrap();
+                b.push_bind(self.dictionary_id)
+                    .push_bind(&meta.0)
+                    .push_bind(meta.1.as_str())
+                    .push_bind(meta.2.reading())
+                    .push_bind(meta.2.frequency())
+                    .push_bind(data_json);
+            });
+
+            query_builder.build().execute(&mut *self.tx).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn insert_kanji(&mut self, kanji: &[DictionaryKanjiBankV3Row]) -> anyhow::Result<()> {
+        for chunk in kanji.chunks(CHUNK_SIZE) {
+            let mut query_builder = sqlx::QueryBuilder::new(
+                r#"-- sql
+                INSERT INTO kanji_entry (
+                    dictionary_id, character, onyomi, kunyomi, tags, meanings, stats
+                )"#,
+            );
+
+            query_builder.push_values(chunk, |mut b, entry| {
+                let meanings_json = serde_json::to_string(&entry.4).unwrap();
+                let stats_json = serde_json::to_string(&entry.5).unwrap();
+                b.push_bind(self.dictionary_id)
+                    .push_bind(&entry.0)
+                    .push_bind(&entry.1)
+                    .push_bind(&entry.2)
+                    .push_bind(&entry.3)
+                    .push_bind(meanings_json)
+                    .push_bind(stats_json);
+            });
+
+            query_builder.build().execute(&mut *self.tx).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn insert_kanji_metas(
+        &mut self,
+        metas: &[DictionaryKanjiMetaBankV3Row],
+    ) -> anyhow::Result<()> {
+        for chunk in metas.chunks(CHUNK_SIZE) {
+            let mut query_builder = sqlx::QueryBuilder::new(
+                r#"-- sql
+                INSERT INTO kanji_meta (dictionary_id, character, mode, frequency, data)"#,
+            );
+
+            query_builder.push_values(chunk, |mut b, meta| {
+                let data_json = serde_json::to_string(&meta.2).unwrap();
+                b.push_bind(self.dictionary_id)
+                    .push_bind(&meta.0)
+                    .push_bind(meta.1.as_str())
+                    .push_bind(meta.2.value())
+                    .push_bind(data_json);
+            });
+
+            query_builder.build().execute(&mut *self.tx).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn insert_tags(&mut self, tags: &[DictionaryTagBankV3Row]) -> anyhow::Result<()> {
+        for chunk in tags.chunks(CHUNK_SIZE) {
+            let mut query_builder = sqlx::QueryBuilder::new(
+                r#"-- sql
+                INSERT INTO definition_tag (dictionary_id, name, category, "order", notes, score)"#,
+            );
+
+            query_builder.push_values(chunk, |mut b, tag| {
+                b.push_bind(self.dictionary_id)
+                    .push_bind(&tag.0)
+                    .push_bind(&tag.1)
+                    .push_bind(tag.2)
+                    .push_bind(&tag.3)
+                    .push_bind(tag.4);
+            });
+
+            query_builder.build().execute(&mut *self.tx).await?;
+        }
+        Ok(())
+    }
+
+    /// Commits every bank written so far and returns the id of the dictionary.
+    pub async fn commit(mut self) -> anyhow::Result<i32> {
+        let compressor = self.compressor.as_ref();
+        let trained = compressor.and_then(|compressor| compressor.trained());
+        sqlx::query(
+            r#"--sql
+            INSERT INTO definition_compression (
+                dictionary_id, trained_id, trained, raw_size, stored_size
+            ) VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(self.dictionary_id)
+        .bind(trained.map(|(trained_id, _)| trained_id as i64))
+        .bind(trained.map(|(_, trained)| trained))
+        .bind(compressor.map_or(0, |compressor| compressor.raw_size))
+        .bind(compressor.map_or(0, |compressor| compressor.stored_size))
+        .execute(&mut *self.tx)
+        .await?;
+
+        self.tx.commit().await?;
+        Ok(self.dictionary_id)
+    }
+}
+
+/// Plain text of definitions for the full-text index, one line per text node: plain strings,
+/// "text" of text definitions, "content" of structured content and the text of HTML
+/// definitions. Deinflections are arrays nested directly in the definitions and are skipped.
+pub fn glossary(definitions: &Value) -> String {
+    fn push_text(value: &Value, is_text: bool, lines: &mut Vec<String>) {
+        match value {
+            Value::String(text) if is_text => lines.push(text.clone()),
+            Value::Array(items) => {
+                for item in items {
+                    push_text(item, true, lines);
+                }
+            }
+            Value::Object(map) if map.get("type").and_then(Value::as_str) == Some("html") => {
+                if let Some(html) = map.get("html").and_then(Value::as_str) {
+                    lines.extend(html_text(html));
+                }
+            }
+            Value::Object(map) => {
+                for (key, item) in map {
+                    push_text(item, key == "text" || key == "content", lines);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut lines = Vec::new();
+    if let Value::Array(definitions) = definitions {
+        for definition in definitions
+            .iter()
+            .filter(|definition| !definition.is_array())
+        {
+            push_text(definition, true, &mut lines);
+        }
+    }
+    lines.join("\n")
+}
+
+/// Tags that start a new line of text.
+const BREAK_TAGS: [&str; 14] = [
+    "br", "p", "div", "li", "tr", "dt", "dd", "h1", "h2", "h3", "h4", "h5", "h6", "hr",
+];
+
+/// Lines of text of an HTML fragment, with whitespace collapsed. Scripts and styles are left
+/// out and the common character references are decoded. Only meant for indexing and exports,
+/// the HTML shown to users goes through the sanitizer instead.
+pub fn html_text(html: &str) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        line.push_str(&decode_references(&rest[..start]));
+        let is_tag = rest[start + 1..]
+            .starts_with(|c: char| c.is_ascii_alphabetic() || c == '/' || c == '!');
+        if !is_tag {
+            line.push('<');
+            rest = &rest[start + 1..];
+            continue;
+        }
+        let end = rest[start..]
+            .find('>')
+            .map_or(rest.len(), |end| start + end + 1);
+        let tag = rest[start + 1..end].trim_end_matches('>');
+        rest = &rest[end..];
+
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_ascii_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if (name == "script" || name == "style") && !tag.starts_with('/') {
+            let close = format!("</{}", name);
+            rest = rest
+                .to_ascii_lowercase()
+                .find(&close)
+                .map_or("", |position| &rest[position..]);
+        } else if BREAK_TAGS.contains(&name.as_str()) {
+            lines.push(mem::take(&mut line));
+        }
+    }
+    line.push_str(&decode_references(rest));
+    lines.push(line);
+
+    lines
+        .iter()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// Decodes numeric character references and the named ones common in dictionaries, leaving
+/// the others as they are.
+fn decode_references(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let reference = rest
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| Some((decode_reference(&rest[1..end])?, end)));
+        match reference {
+            Some((c, end)) => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn decode_reference(name: &str) -> Option<char> {
+    let code = match name {
+        "amp" => '&' as u32,
+        "lt" => '<' as u32,
+        "gt" => '>' as u32,
+        "quot" => '"' as u32,
+        "apos" => '\'' as u32,
+        "nbsp" => ' ' as u32,
+        _ => match name.strip_prefix('#')? {
+            hex if hex.starts_with(['x', 'X']) => u32::from_str_radix(&hex[1..], 16).ok()?,
+            decimal => decimal.parse().ok()?,
+        },
+    };
+    char::from_u32(code)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::query::{DEFAULT_PROFILE_ID, FulltextField, FulltextMode};
+    use crate::test_util::temp_db;
+    use serde_json::json;
+
+    #[test]
+    fn should_extract_glossary() {
+        let definitions = json!([
+            "plain",
+            { "type": "text", "text": "detailed" },
+            { "type": "image", "path": "a.png", "title": "skipped" },
+            {
+                "type": "structured-content",
+                "content": [
+                    "first",
+                    { "tag": "span", "title": "skipped", "content": "second" },
+                    { "tag": "ul", "content": [{ "tag": "li", "content": "third" }] }
+                ]
+            },
+            ["deinflected", ["past"]],
+            { "type": "html", "html": "<b>fourth</b><br>fifth &amp; <i>sixth</i>" }
+        ]);
+        assert_eq!(
+            glossary(&definitions),
+            "plain\ndetailed\nfirst\nsecond\nthird\nfourth\nfifth & sixth"
+        );
+    }
+
+    #[test]
+    fn should_extract_html_text() {
+        assert_eq!(
+            html_text("<div>a  <span class=\"x\">b</span></div>\n<p>c&#x3042;&#12354;</p>"),
+            ["a b", "cああ"]
+        );
+        assert_eq!(
+            html_text("<style>p { color: red }</style>d<SCRIPT>e</SCRIPT>&unknown; f"),
+            ["d&unknown; f"]
+        );
+        assert_eq!(html_text("g < h <!-- i -->"), ["g < h"]);
+    }
+
+    #[tokio::test]
+    async fn should_index_terms_under_their_ids() {
+        let (_config, db) = temp_db("writer-test").await;
+        let index: DictionaryIndex =
+            serde_json::from_str(r#"{"title":"Test","revision":"1","format":3}"#).unwrap();
+        let terms: Vec<DictionaryTermBankV3Row> = (0..CHUNK_SIZE + 2)
+            .map(|i| {
+                serde_json::from_value(json!([
+                    format!("語{}", i),
+                    "",
+                    "",
+                    "",
+                    0,
+                    [format!("gloss {}", i)],
+                    0,
+                    ""
+                ]))
+                .unwrap()
+            })
+            .collect();
+        let mut writer = db.begin_dictionary(&index).await.unwrap();
+        writer.insert_terms(&terms[..CHUNK_SIZE + 1]).await.unwrap();
+        writer.insert_terms(&terms[CHUNK_SIZE + 1..]).await.unwrap();
+        writer.commit().await.unwrap();
+
+        for i in [0, CHUNK_SIZE - 1, CHUNK_SIZE, CHUNK_SIZE + 1] {
+            let result = db
+                .search_dictionary_entry_fulltext(
+                    &format!("gloss {}", i),
+                    FulltextMode::Prefix,
+                    FulltextField::Glossary,
+                    1,
+                    10,
+                    DEFAULT_PROFILE_ID,
+                )
+                .await
+                .unwrap();
+            let expressions: Vec<&str> = result
+                .entries
+                .iter()
+                .map(|entry| entry.expression.as_str())
+                .collect();
+            assert_eq!(expressions, [format!("語{}", i)]);
+        }
+    }
+}
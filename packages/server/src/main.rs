@@ -3,6 +3,8 @@ mod db;
 mod routes;
 mod schemas;
 mod server;
+#[cfg(test)]
+mod test_util;
 mod util;
 
 #[tokio::main]
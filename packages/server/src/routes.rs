@@ -1,8 +1,9 @@
 use crate::util::state::AppState;
 use axum::{
     Router,
+    extract::DefaultBodyLimit,
     http::HeaderValue,
-    routing::{delete, get},
+    routing::{delete, get, patch, post},
 };
 use tower_http::{
     catch_panic::CatchPanicLayer,
@@ -14,7 +15,11 @@ mod dictionaries;
 mod dictionary_entries;
 mod health;
 mod index;
+mod jobs;
+mod kanji;
 mod media;
+mod profiles;
+mod term_meta;
 mod tokenize;
 
 #[rustfmt::skip]
@@ -27,10 +32,32 @@ pub fn create_routes(state: AppState) -> Router {
         .route("/", get(index::root))
         .route("/health", get(health::status))
         .route("/dictionary_entries/search", get(dictionary_entries::search))
+        .route("/dictionary_entries/scan", get(dictionary_entries::scan))
+        .route("/dictionary_entries/batch", post(dictionary_entries::batch))
+        .route(
+            "/dictionary_entries/fulltext",
+            get(dictionary_entries::fulltext),
+        )
         .route("/definition_tags/search", get(definition_tags::search))
+        .route("/term_meta/search", get(term_meta::search))
+        .route("/kanji/search", get(kanji::search))
+        .route("/kanji/readings", get(kanji::search_reading))
         .route("/dictionaries", get(dictionaries::index))
+        .route("/dictionaries", post(dictionaries::create).layer(DefaultBodyLimit::max(dictionaries::MAX_UPLOAD_SIZE)))
         .route("/dictionaries/{dictionary_id}", get(dictionaries::show))
+        .route("/dictionaries/{dictionary_id}", patch(dictionaries::update_settings))
         .route("/dictionaries/{dictionary_id}", delete(dictionaries::destroy))
+        .route("/dictionaries/{dictionary_id}/export", get(dictionaries::export))
+        .route("/dictionaries/{dictionary_id}/update", get(dictionaries::check_update))
+        .route("/dictionaries/{dictionary_id}/update", post(dictionaries::update))
+        .route("/profiles", get(profiles::index))
+        .route("/profiles", post(profiles::create))
+        .route("/profiles/{name}", patch(profiles::update))
+        .route("/profiles/{name}", delete(profiles::destroy))
+        .route("/jobs", get(jobs::index))
+        .route("/jobs/{job_id}", get(jobs::show))
+        .route("/jobs/{job_id}/events", get(jobs::events))
+        .route("/jobs/{job_id}/cancel", post(jobs::cancel))
         .route("/tokenize", get(tokenize::handle))
         .route("/media/{dictionary_id}/{relative_path}", get(media::serve))
         .with_state(state)
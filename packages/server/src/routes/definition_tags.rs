@@ -1,6 +1,7 @@
 use crate::{
     db::tables::DefinitionTag,
     util::{
+        profile::SelectedProfile,
         response::{HandlerResult, RejectionResponse, success},
         state::AppState,
     },
@@ -18,11 +19,12 @@ pub struct SearchQueryParams {
 
 pub async fn search(
     State(state): State<AppState>,
+    SelectedProfile(profile): SelectedProfile,
     WithRejection(Query(params), _): WithRejection<Query<SearchQueryParams>, RejectionResponse>,
 ) -> HandlerResult<Vec<DefinitionTag>> {
     params.validate()?;
     let name = params.name;
 
-    let tags = state.db.query_definition_tag_by(name).await?;
+    let tags = state.db.query_definition_tag_by(name, profile.id).await?;
     success(tags)
 }
@@ -1,26 +1,126 @@
 use crate::{
-    db::tables::Dictionary,
+    db::{
+        query::{DEFAULT_PROFILE_ID, DictionarySettingsUpdate},
+        tables::Dictionary,
+    },
     util::{
-        response::{HandlerResult, RejectionResponse, fail, success},
+        dict::{Dict, OnConflict},
+        jobs::JobState,
+        profile::SelectedProfile,
+        response::{ErrorResponse, HandlerResult, RejectionResponse, fail, success},
         state::AppState,
+        updater::UpdateStatus,
     },
 };
+use anyhow::Context;
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    Json,
+    body::Body,
+    extract::{Multipart, Path, Query, State, multipart::Field},
+    http::{Response, StatusCode, header},
 };
 use axum_extra::extract::WithRejection;
+use futures_util::stream;
+use serde::Deserialize;
+use std::path::Path as FsPath;
+use tokio::{
+    fs,
+    io::{AsyncReadExt, AsyncWriteExt},
+};
+use validator::Validate;
 
-pub async fn index(State(state): State<AppState>) -> HandlerResult<Vec<Dictionary>> {
-    let dictionaries = state.db.query_dictionaries().await?;
+pub async fn index(
+    State(state): State<AppState>,
+    SelectedProfile(profile): SelectedProfile,
+) -> HandlerResult<Vec<Dictionary>> {
+    let dictionaries = state.db.query_dictionaries(profile.id).await?;
     success(dictionaries)
 }
 
+/// Largest dictionary archive accepted by an upload, in bytes.
+pub const MAX_UPLOAD_SIZE: usize = 2 * 1024 * 1024 * 1024;
+
+#[derive(Deserialize, Validate)]
+pub struct CreateQueryParams {
+    #[serde(default)]
+    pub on_conflict: OnConflict,
+}
+
+/// Takes the archive from the `dictionary` field of a multipart upload and queues its import.
+pub async fn create(
+    State(state): State<AppState>,
+    WithRejection(Query(params), _): WithRejection<Query<CreateQueryParams>, RejectionResponse>,
+    WithRejection(mut multipart, _): WithRejection<Multipart, RejectionResponse>,
+) -> HandlerResult<JobState> {
+    params.validate()?;
+
+    while let Some(field) = multipart.next_field().await? {
+        if field.name() != Some("dictionary") {
+            continue;
+        }
+        // Removed once dropped, which the job does after importing it
+        let archive = tempfile::Builder::new()
+            .prefix("upload-")
+            .suffix(".zip")
+            .tempfile_in(&state.config.dir.temp)
+            .context("Failed to create upload file")?
+            .into_temp_path();
+        save_upload(field, &archive).await?;
+
+        let job = state.jobs.import(
+            state.config.clone(),
+            state.db.clone(),
+            archive,
+            params.on_conflict,
+        );
+        return success(job.state());
+    }
+
+    fail(
+        "Missing dictionary field".to_string(),
+        StatusCode::BAD_REQUEST,
+    )
+}
+
+async fn save_upload(mut field: Field<'_>, path: &FsPath) -> Result<(), ErrorResponse> {
+    let mut file = fs::File::create(path)
+        .await
+        .context("Failed to create upload file")?;
+    while let Some(chunk) = field.chunk().await? {
+        file.write_all(&chunk)
+            .await
+            .context("Failed to write upload file")?;
+    }
+    file.flush().await.context("Failed to write upload file")?;
+    Ok(())
+}
+
 pub async fn show(
     State(state): State<AppState>,
+    SelectedProfile(profile): SelectedProfile,
+    WithRejection(Path(dictionary_id), _): WithRejection<Path<i32>, RejectionResponse>,
+) -> HandlerResult<Dictionary> {
+    let dictionary = state.db.query_dictionary(dictionary_id, profile.id).await?;
+    match dictionary {
+        Some(dictionary) => success(dictionary),
+        None => fail("Dictionary not found".to_string(), StatusCode::NOT_FOUND),
+    }
+}
+
+/// Changes the settings given in the body and returns the dictionary afterwards.
+pub async fn update_settings(
+    State(state): State<AppState>,
+    SelectedProfile(profile): SelectedProfile,
     WithRejection(Path(dictionary_id), _): WithRejection<Path<i32>, RejectionResponse>,
+    WithRejection(Json(update), _): WithRejection<
+        Json<DictionarySettingsUpdate>,
+        RejectionResponse,
+    >,
 ) -> HandlerResult<Dictionary> {
-    let dictionary = state.db.query_dictionary(dictionary_id).await?;
+    let dictionary = state
+        .db
+        .query_update_dictionary_settings(dictionary_id, profile.id, &update)
+        .await?;
     match dictionary {
         Some(dictionary) => success(dictionary),
         None => fail("Dictionary not found".to_string(), StatusCode::NOT_FOUND),
@@ -31,9 +131,115 @@ pub async fn destroy(
     State(state): State<AppState>,
     WithRejection(Path(dictionary_id), _): WithRejection<Path<i32>, RejectionResponse>,
 ) -> HandlerResult<Dictionary> {
-    let dictionary = state.db.query_delete_dictionary(dictionary_id).await?;
+    let dict = Dict::new(state.config.clone());
+    let dictionary = dict.delete_dict(&state.db, dictionary_id).await?;
     match dictionary {
         Some(dictionary) => success(dictionary),
         None => fail("Dictionary not found".to_string(), StatusCode::NOT_FOUND),
     }
 }
+
+pub async fn check_update(
+    State(state): State<AppState>,
+    WithRejection(Path(dictionary_id), _): WithRejection<Path<i32>, RejectionResponse>,
+) -> HandlerResult<UpdateStatus> {
+    let dictionary = state
+        .db
+        .query_dictionary(dictionary_id, DEFAULT_PROFILE_ID)
+        .await?;
+    let Some(dictionary) = dictionary else {
+        return fail("Dictionary not found".to_string(), StatusCode::NOT_FOUND);
+    };
+    if !dictionary.is_updatable {
+        return fail(
+            "Dictionary is not updatable".to_string(),
+            StatusCode::UNPROCESSABLE_ENTITY,
+        );
+    }
+
+    let status = state.updater.check(&dictionary).await?;
+    success(status)
+}
+
+/// Queues the install of the latest revision, if there is one, as a job.
+pub async fn update(
+    State(state): State<AppState>,
+    SelectedProfile(profile): SelectedProfile,
+    WithRejection(Path(dictionary_id), _): WithRejection<Path<i32>, RejectionResponse>,
+) -> HandlerResult<JobState> {
+    let dictionary = state.db.query_dictionary(dictionary_id, profile.id).await?;
+    let Some(dictionary) = dictionary else {
+        return fail("Dictionary not found".to_string(), StatusCode::NOT_FOUND);
+    };
+    if !dictionary.is_updatable {
+        return fail(
+            "Dictionary is not updatable".to_string(),
+            StatusCode::UNPROCESSABLE_ENTITY,
+        );
+    }
+
+    let job = state.jobs.update(
+        state.config.clone(),
+        state.db.clone(),
+        state.updater.clone(),
+        dictionary,
+    );
+    success(job.state())
+}
+
+/// Bytes read from an exported archive per chunk of the response.
+const EXPORT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Exports the dictionary as a Yomitan archive and streams it back.
+pub async fn export(
+    State(state): State<AppState>,
+    WithRejection(Path(dictionary_id), _): WithRejection<Path<i32>, RejectionResponse>,
+) -> Result<Response<Body>, ErrorResponse> {
+    let dictionary = state
+        .db
+        .query_dictionary(dictionary_id, DEFAULT_PROFILE_ID)
+        .await?;
+    if dictionary.is_none() {
+        return Err(ErrorResponse {
+            error: anyhow::anyhow!("Dictionary not found"),
+            status_code: StatusCode::NOT_FOUND,
+        });
+    }
+
+    let archive = state
+        .config
+        .dir
+        .temp
+        .join(format!("export-{:016x}.zip", rand::random::<u64>()));
+    let dict = Dict::new(state.config.clone());
+    dict.export_dict(&state.db, dictionary_id, &archive).await?;
+    let file = fs::File::open(&archive)
+        .await
+        .context("Failed to open exported archive")?;
+    // The open file can still be read, so nothing is left behind once it is sent
+    let _ = fs::remove_file(&archive).await;
+
+    let chunks = stream::unfold(Some(file), |file| async move {
+        let mut file = file?;
+        let mut chunk = vec![0; EXPORT_CHUNK_SIZE];
+        match file.read(&mut chunk).await {
+            Ok(0) => None,
+            Ok(read) => {
+                chunk.truncate(read);
+                Some((Ok(chunk), Some(file)))
+            }
+            Err(e) => Some((Err(e), None)),
+        }
+    });
+
+    let response = Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/zip")
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"dictionary-{}.zip\"", dictionary_id),
+        )
+        .body(Body::from_stream(chunks))
+        .context("Failed to create response")?;
+    Ok(response)
+}
@@ -1,31 +1,232 @@
-use crate::{
-    db::tables::DictionaryEntry,
-    util::{
-        response::{HandlerResult, RejectionResponse, success},
-        state::AppState,
-    },
+use crate::db::query::{
+    DEFAULT_PAGE_SIZE, FulltextField, FulltextMode, FulltextResult, MAX_PAGE_SIZE, SearchField,
 };
+use crate::util::{
+    html::{DefinitionFormat, RenderHtml},
+    merge::ResultMode,
+    profile::SelectedProfile,
+    ranking::Ranking,
+    response::{HandlerResult, RejectionResponse, success},
+    state::AppState,
+    translator::{BatchOptions, MAX_SCAN_LENGTH, ScanResult, SearchResult},
+};
+use axum::Json;
 use axum::extract::{Query, State};
 use axum_extra::extract::WithRejection;
 use serde::Deserialize;
-use validator::Validate;
+use std::collections::BTreeMap;
+use validator::{Validate, ValidateLength, ValidationError};
 
 #[derive(Deserialize, Validate)]
 pub struct SearchQueryParams {
     #[validate(length(min = 1))]
     pub expression: String,
+    /// Defaults to the search field of the profile.
+    pub field: Option<SearchField>,
+    #[serde(default)]
+    pub mode: ResultMode,
+    /// Comma-separated sort keys, see [`Ranking`].
+    #[serde(default)]
+    pub sort: Ranking,
+    /// Whether to return the tags of the entries with their definitions.
+    #[serde(default)]
+    pub resolve_tags: bool,
+    /// Whether to render the definitions as HTML.
+    #[serde(default)]
+    pub format: DefinitionFormat,
 }
 
 pub async fn search(
     State(state): State<AppState>,
+    SelectedProfile(profile): SelectedProfile,
     WithRejection(Query(params), _): WithRejection<Query<SearchQueryParams>, RejectionResponse>,
-) -> HandlerResult<Vec<DictionaryEntry>> {
+) -> HandlerResult<SearchResult> {
     params.validate()?;
     let expression = params.expression;
+    let field = params.field.unwrap_or(profile.search_field);
+
+    let mut result = match params.mode {
+        ResultMode::Flat => SearchResult::Flat(
+            state
+                .translator
+                .find_terms(
+                    &state.db,
+                    &expression,
+                    field,
+                    &params.sort,
+                    params.resolve_tags,
+                    &profile,
+                )
+                .await?,
+        ),
+        ResultMode::Merged => SearchResult::Merged(
+            state
+                .translator
+                .find_terms_merged(
+                    &state.db,
+                    &expression,
+                    field,
+                    &params.sort,
+                    params.resolve_tags,
+                    &profile,
+                )
+                .await?,
+        ),
+    };
+    if params.format == DefinitionFormat::Html {
+        result.render_html();
+    }
+    success(result)
+}
+
+/// Body of a batch search, the options apply to every expression.
+#[derive(Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchSearchBody {
+    /// Expressions or readings to look up, the results are keyed by them.
+    #[validate(length(min = 1, max = 500), custom(function = "validate_expressions"))]
+    pub expressions: Vec<String>,
+    /// Defaults to the search field of the profile.
+    pub field: Option<SearchField>,
+    #[serde(default)]
+    pub mode: ResultMode,
+    /// Comma-separated sort keys, see [`Ranking`].
+    #[serde(default)]
+    pub sort: Ranking,
+    /// Results per expression, defaults to the limit of the profile.
+    #[validate(range(min = 1))]
+    pub limit: Option<i32>,
+    #[serde(default)]
+    pub resolve_tags: bool,
+    #[serde(default)]
+    pub format: DefinitionFormat,
+}
+
+/// Longest expression of a batch search, in characters.
+const MAX_EXPRESSION_LENGTH: u64 = 64;
+
+fn validate_expressions(expressions: &[String]) -> Result<(), ValidationError> {
+    if expressions
+        .iter()
+        .all(|expression| expression.validate_length(Some(1), Some(MAX_EXPRESSION_LENGTH), None))
+    {
+        return Ok(());
+    }
+    let mut error = ValidationError::new("length");
+    error.add_param("min".into(), &1);
+    error.add_param("max".into(), &MAX_EXPRESSION_LENGTH);
+    Err(error)
+}
+
+/// Looks up many expressions at once, with as few queries as the candidates allow.
+pub async fn batch(
+    State(state): State<AppState>,
+    SelectedProfile(profile): SelectedProfile,
+    WithRejection(Json(body), _): WithRejection<Json<BatchSearchBody>, RejectionResponse>,
+) -> HandlerResult<BTreeMap<String, SearchResult>> {
+    body.validate()?;
+    let options = BatchOptions {
+        field: body.field.unwrap_or(profile.search_field),
+        mode: body.mode,
+        ranking: body.sort,
+        limit: body.limit,
+        resolve_tags: body.resolve_tags,
+    };
+
+    let mut result = state
+        .translator
+        .find_terms_batch(&state.db, &body.expressions, &options, &profile)
+        .await?;
+    if body.format == DefinitionFormat::Html {
+        result.render_html();
+    }
+    success(result)
+}
+
+#[derive(Deserialize, Validate)]
+pub struct ScanQueryParams {
+    #[validate(length(min = 1))]
+    pub text: String,
+    #[validate(range(min = 1, max = MAX_SCAN_LENGTH))]
+    pub max_length: Option<usize>,
+    /// Comma-separated sort keys, see [`Ranking`].
+    #[serde(default)]
+    pub sort: Ranking,
+    /// Whether to return the tags of the entries with their definitions.
+    #[serde(default)]
+    pub resolve_tags: bool,
+    /// Whether to render the definitions as HTML.
+    #[serde(default)]
+    pub format: DefinitionFormat,
+}
+
+pub async fn scan(
+    State(state): State<AppState>,
+    SelectedProfile(profile): SelectedProfile,
+    WithRejection(Query(params), _): WithRejection<Query<ScanQueryParams>, RejectionResponse>,
+) -> HandlerResult<ScanResult> {
+    params.validate()?;
+    let text = params.text;
+    let max_length = params
+        .max_length
+        .unwrap_or(profile.scan_length.max(1) as usize);
+
+    let mut result = state
+        .translator
+        .scan(
+            &state.db,
+            &text,
+            max_length,
+            &params.sort,
+            params.resolve_tags,
+            &profile,
+        )
+        .await?;
+    if params.format == DefinitionFormat::Html {
+        result.render_html();
+    }
+    success(result)
+}
+
+#[derive(Deserialize, Validate)]
+pub struct FulltextQueryParams {
+    #[validate(length(min = 1))]
+    pub text: String,
+    #[serde(default)]
+    pub mode: FulltextMode,
+    #[serde(default)]
+    pub field: FulltextField,
+    #[validate(range(min = 1))]
+    pub page: Option<u32>,
+    #[validate(range(min = 1, max = MAX_PAGE_SIZE))]
+    pub per_page: Option<u32>,
+    /// Whether to render the definitions as HTML.
+    #[serde(default)]
+    pub format: DefinitionFormat,
+}
+
+pub async fn fulltext(
+    State(state): State<AppState>,
+    SelectedProfile(profile): SelectedProfile,
+    WithRejection(Query(params), _): WithRejection<Query<FulltextQueryParams>, RejectionResponse>,
+) -> HandlerResult<FulltextResult> {
+    params.validate()?;
+    let page = params.page.unwrap_or(1);
+    let per_page = params.per_page.unwrap_or(DEFAULT_PAGE_SIZE);
 
-    let definition = state
+    let mut result = state
         .db
-        .query_dictionary_entry_by(expression.clone())
+        .search_dictionary_entry_fulltext(
+            &params.text,
+            params.mode,
+            params.field,
+            page,
+            per_page,
+            profile.id,
+        )
         .await?;
-    success(definition)
+    if params.format == DefinitionFormat::Html {
+        result.render_html();
+    }
+    success(result)
 }
@@ -0,0 +1,74 @@
+use crate::util::{
+    jobs::JobState,
+    response::{HandlerResult, RejectionResponse, fail, success},
+    state::AppState,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+};
+use axum_extra::extract::WithRejection;
+use futures_util::stream;
+use std::convert::Infallible;
+
+pub async fn index(State(state): State<AppState>) -> HandlerResult<Vec<JobState>> {
+    success(state.jobs.list())
+}
+
+pub async fn show(
+    State(state): State<AppState>,
+    WithRejection(Path(job_id), _): WithRejection<Path<u32>, RejectionResponse>,
+) -> HandlerResult<JobState> {
+    match state.jobs.get(job_id) {
+        Some(job) => success(job.state()),
+        None => fail("Job not found".to_string(), StatusCode::NOT_FOUND),
+    }
+}
+
+/// Streams the state of the job as server-sent events, starting with the current state and
+/// ending after the job finishes.
+pub async fn events(
+    State(state): State<AppState>,
+    WithRejection(Path(job_id), _): WithRejection<Path<u32>, RejectionResponse>,
+) -> Response {
+    let Some(job) = state.jobs.get(job_id) else {
+        return fail::<()>("Job not found".to_string(), StatusCode::NOT_FOUND).into_response();
+    };
+
+    let mut rx = job.subscribe();
+    rx.mark_changed();
+    let events = stream::unfold((rx, false), |(mut rx, finished)| async move {
+        if finished || rx.changed().await.is_err() {
+            return None;
+        }
+        let job = rx.borrow_and_update().clone();
+        let event = Event::default().json_data(&job).unwrap_or_default();
+        Some((Ok::<_, Infallible>(event), (rx, job.status.is_finished())))
+    });
+
+    Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+pub async fn cancel(
+    State(state): State<AppState>,
+    WithRejection(Path(job_id), _): WithRejection<Path<u32>, RejectionResponse>,
+) -> HandlerResult<JobState> {
+    let Some(job) = state.jobs.get(job_id) else {
+        return fail("Job not found".to_string(), StatusCode::NOT_FOUND);
+    };
+    if job.state().status.is_finished() {
+        return fail(
+            "Job already finished".to_string(),
+            StatusCode::UNPROCESSABLE_ENTITY,
+        );
+    }
+
+    job.cancel();
+    success(job.state())
+}
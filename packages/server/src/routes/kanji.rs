@@ -0,0 +1,65 @@
+use crate::{
+    db::{
+        query::KanjiReadingField,
+        tables::{KanjiEntry, KanjiMeta},
+    },
+    util::{
+        profile::SelectedProfile,
+        response::{HandlerResult, RejectionResponse, success},
+        state::AppState,
+    },
+};
+use axum::extract::{Query, State};
+use axum_extra::extract::WithRejection;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Deserialize, Validate)]
+pub struct SearchQueryParams {
+    #[validate(length(min = 1))]
+    pub character: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    pub entries: Vec<KanjiEntry>,
+    pub meta: Vec<KanjiMeta>,
+}
+
+pub async fn search(
+    State(state): State<AppState>,
+    SelectedProfile(profile): SelectedProfile,
+    WithRejection(Query(params), _): WithRejection<Query<SearchQueryParams>, RejectionResponse>,
+) -> HandlerResult<SearchResult> {
+    params.validate()?;
+    let character = params.character;
+
+    let entries = state
+        .db
+        .query_kanji_entry_by(character.clone(), profile.id)
+        .await?;
+    let meta = state.db.query_kanji_meta_by(character, profile.id).await?;
+    success(SearchResult { entries, meta })
+}
+
+#[derive(Deserialize, Validate)]
+pub struct ReadingQueryParams {
+    #[validate(length(min = 1, max = 64))]
+    pub reading: String,
+    #[serde(default)]
+    pub field: KanjiReadingField,
+}
+
+pub async fn search_reading(
+    State(state): State<AppState>,
+    SelectedProfile(profile): SelectedProfile,
+    WithRejection(Query(params), _): WithRejection<Query<ReadingQueryParams>, RejectionResponse>,
+) -> HandlerResult<Vec<KanjiEntry>> {
+    params.validate()?;
+    let entries = state
+        .db
+        .query_kanji_entry_by_reading(&params.reading, params.field, profile.id)
+        .await?;
+    success(entries)
+}
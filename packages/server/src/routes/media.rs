@@ -2,7 +2,7 @@ use crate::util::{response::ErrorResponse, state::AppState};
 use axum::{
     body::Body,
     extract::{Path, State},
-    http::{header, Response, StatusCode},
+    http::{Response, StatusCode, header},
 };
 use tokio::fs::File;
 use tokio::io::AsyncReadExt;
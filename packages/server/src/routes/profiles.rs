@@ -0,0 +1,86 @@
+use crate::{
+    db::{
+        query::{DEFAULT_PROFILE_ID, ProfileUpdate},
+        tables::Profile,
+    },
+    util::{
+        response::{HandlerResult, RejectionResponse, fail, success},
+        state::AppState,
+    },
+};
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use axum_extra::extract::WithRejection;
+use validator::Validate;
+
+pub async fn index(State(state): State<AppState>) -> HandlerResult<Vec<Profile>> {
+    let profiles = state.db.query_profiles().await?;
+    success(profiles)
+}
+
+/// Creates the profile named in the body, every dictionary starts with the default settings.
+pub async fn create(
+    State(state): State<AppState>,
+    WithRejection(Json(update), _): WithRejection<Json<ProfileUpdate>, RejectionResponse>,
+) -> HandlerResult<Profile> {
+    update.validate()?;
+    let Some(name) = update.name.as_deref().filter(|name| !name.is_empty()) else {
+        return fail("Missing name".to_string(), StatusCode::BAD_REQUEST);
+    };
+    if state.db.query_profile_by_name(name).await?.is_some() {
+        return fail("Profile already exists".to_string(), StatusCode::CONFLICT);
+    }
+
+    let profile = state.db.query_create_profile(name, &update).await?;
+    success(profile)
+}
+
+/// Changes the options given in the body and returns the profile afterwards.
+pub async fn update(
+    State(state): State<AppState>,
+    WithRejection(Path(name), _): WithRejection<Path<String>, RejectionResponse>,
+    WithRejection(Json(update), _): WithRejection<Json<ProfileUpdate>, RejectionResponse>,
+) -> HandlerResult<Profile> {
+    update.validate()?;
+    let Some(profile) = state.db.query_profile_by_name(&name).await? else {
+        return fail("Profile not found".to_string(), StatusCode::NOT_FOUND);
+    };
+    if let Some(new_name) = update.name.as_deref().filter(|new_name| *new_name != name) {
+        if new_name.is_empty() {
+            return fail("Empty name".to_string(), StatusCode::BAD_REQUEST);
+        }
+        if state.db.query_profile_by_name(new_name).await?.is_some() {
+            return fail("Profile already exists".to_string(), StatusCode::CONFLICT);
+        }
+    }
+
+    let profile = state.db.query_update_profile(profile.id, &update).await?;
+    match profile {
+        Some(profile) => success(profile),
+        None => fail("Profile not found".to_string(), StatusCode::NOT_FOUND),
+    }
+}
+
+pub async fn destroy(
+    State(state): State<AppState>,
+    WithRejection(Path(name), _): WithRejection<Path<String>, RejectionResponse>,
+) -> HandlerResult<Profile> {
+    let Some(profile) = state.db.query_profile_by_name(&name).await? else {
+        return fail("Profile not found".to_string(), StatusCode::NOT_FOUND);
+    };
+    if profile.id == DEFAULT_PROFILE_ID {
+        return fail(
+            "The default profile can't be removed".to_string(),
+            StatusCode::UNPROCESSABLE_ENTITY,
+        );
+    }
+
+    let profile = state.db.query_delete_profile(profile.id).await?;
+    match profile {
+        Some(profile) => success(profile),
+        None => fail("Profile not found".to_string(), StatusCode::NOT_FOUND),
+    }
+}
@@ -0,0 +1,35 @@
+use crate::{
+    db::tables::TermMeta,
+    schemas::dictionary_term_meta_bank_v3::TermMetaMode,
+    util::{
+        profile::SelectedProfile,
+        response::{HandlerResult, RejectionResponse, success},
+        state::AppState,
+    },
+};
+use axum::extract::{Query, State};
+use axum_extra::extract::WithRejection;
+use serde::Deserialize;
+use validator::Validate;
+
+#[derive(Deserialize, Validate)]
+pub struct SearchQueryParams {
+    #[validate(length(min = 1))]
+    pub expression: String,
+    pub mode: Option<TermMetaMode>,
+}
+
+pub async fn search(
+    State(state): State<AppState>,
+    SelectedProfile(profile): SelectedProfile,
+    WithRejection(Query(params), _): WithRejection<Query<SearchQueryParams>, RejectionResponse>,
+) -> HandlerResult<Vec<TermMeta>> {
+    params.validate()?;
+    let expression = params.expression;
+
+    let metas = state
+        .db
+        .query_term_meta_by(expression, params.mode, profile.id)
+        .await?;
+    success(metas)
+}
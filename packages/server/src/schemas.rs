@@ -1,3 +1,9 @@
 pub mod dictionary_index;
+pub mod dictionary_kanji_bank_v1;
+pub mod dictionary_kanji_bank_v3;
+pub mod dictionary_kanji_meta_bank_v3;
 pub mod dictionary_tag_bank_v3;
+pub mod dictionary_term_bank_v1;
+pub mod dictionary_term_bank_v2;
 pub mod dictionary_term_bank_v3;
+pub mod dictionary_term_meta_bank_v3;
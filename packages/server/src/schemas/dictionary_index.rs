@@ -2,11 +2,9 @@ use serde::{Deserialize, Serialize};
 use serde_valid::Validate;
 use std::collections::HashMap;
 
-// TODO: serde_valie .validate needs to be invoked manually
-// TODO: anyOf and dependencies validation needs to be implemented
-
 #[derive(Deserialize, Serialize, Debug, Validate)]
 #[serde(rename_all = "camelCase")]
+#[validate(custom = |s| s.validate_dependencies())]
 pub struct DictionaryIndex {
     /// Title of the dictionary.
     pub title: String,
@@ -20,11 +18,11 @@ pub struct DictionaryIndex {
     pub sequenced: bool,
     /// Format of data found in the JSON data files.
     #[validate(enumerate = [1, 2, 3])]
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "format", skip_serializing_if = "Option::is_none")]
     pub format: Option<u8>,
     /// Alias for format.
     #[validate(enumerate = [1, 2, 3])]
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "version", skip_serializing_if = "Option::is_none")]
     pub version: Option<u8>,
     /// Creator of the dictionary.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -49,18 +47,19 @@ pub struct DictionaryIndex {
     pub attribution: Option<String>,
     /// Language of the terms in the dictionary (ISO 639 code).
     #[validate(pattern = r"^[a-z]{2,3}$")]
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "sourceLanguage", skip_serializing_if = "Option::is_none")]
     pub source_language: Option<String>,
     /// Main language of the definitions (ISO 639 code).
     #[validate(pattern = r"^[a-z]{2,3}$")]
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "targetLanguage", skip_serializing_if = "Option::is_none")]
     pub target_language: Option<String>,
     /// Frequency mode for the dictionary.
     #[validate(enumerate = ["occurrence-based", "rank-based"])]
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "frequencyMode", skip_serializing_if = "Option::is_none")]
     pub frequency_mode: Option<String>,
     /// Tag information (Obsolete, but included for compatibility).
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[validate]
+    #[serde(rename = "tagMeta", skip_serializing_if = "Option::is_none")]
     pub tag_meta: Option<TagMeta>,
 }
 
@@ -1,4 +1,5 @@
 use super::*;
+use serde_valid::validation::Error;
 
 impl DictionaryIndex {
     pub fn get_format(&self) -> u8 {
@@ -9,3 +10,27 @@ impl DictionaryIndex {
         }
     }
 }
+
+impl DictionaryIndex {
+    /// Rules of the schema that involve more than one field: `format` or `version` is
+    /// required, and an updatable dictionary needs both urls.
+    pub(super) fn validate_dependencies(&self) -> Result<(), Vec<Error>> {
+        let mut errors = vec![];
+        if self.format.is_none() && self.version.is_none() {
+            errors.push(Error::Custom(
+                "Either `format` or `version` is required.".to_string(),
+            ));
+        }
+        if self.is_updatable.is_some() && (self.index_url.is_none() || self.download_url.is_none())
+        {
+            errors.push(Error::Custom(
+                "`isUpdatable` requires `indexUrl` and `downloadUrl`.".to_string(),
+            ));
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use crate::schemas::dictionary_kanji_bank_v3::{DictionaryKanjiBankV3Row, KanjiStats};
+use serde::Deserialize;
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde_valid::Validate;
+use std::fmt;
+
+/// Data file containing kanji information, used by format 1 dictionaries.
+pub type DictionaryKanjiBankV1 = Vec<DictionaryKanjiBankV1Row>;
+
+/// Information about a single kanji character. The meanings are the elements following the
+/// tags rather than an array.
+#[derive(Debug, Validate)]
+pub struct DictionaryKanjiBankV1Row(
+    /// Kanji character.
+    pub String,
+    /// String of space-separated onyomi readings for the kanji character. An empty string is treated as no readings.
+    pub String,
+    /// String of space-separated kunyomi readings for the kanji character. An empty string is treated as no readings.
+    pub String,
+    /// String of space-separated tags for the kanji character. An empty string is treated as no tags.
+    pub String,
+    /// Meanings for the kanji character.
+    pub Vec<String>,
+);
+
+impl<'de> Deserialize<'de> for DictionaryKanjiBankV1Row {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RowVisitor;
+
+        impl<'de> Visitor<'de> for RowVisitor {
+            type Value = DictionaryKanjiBankV1Row;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an array of at least 4 elements")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let character = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let onyomi = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                let kunyomi = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+                let tags = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(3, &self))?;
+                let mut meanings = vec![];
+                while let Some(meaning) = seq.next_element()? {
+                    meanings.push(meaning);
+                }
+                Ok(DictionaryKanjiBankV1Row(
+                    character, onyomi, kunyomi, tags, meanings,
+                ))
+            }
+        }
+
+        deserializer.deserialize_seq(RowVisitor)
+    }
+}
+
+impl From<DictionaryKanjiBankV1Row> for DictionaryKanjiBankV3Row {
+    /// Format 1 has no stats.
+    fn from(row: DictionaryKanjiBankV1Row) -> Self {
+        DictionaryKanjiBankV3Row(row.0, row.1, row.2, row.3, row.4, KanjiStats::new())
+    }
+}
+
+#[cfg(test)]
+mod test;
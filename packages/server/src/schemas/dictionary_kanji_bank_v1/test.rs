@@ -0,0 +1,15 @@
+use super::*;
+use serde_json::json;
+
+#[test]
+fn should_parse_kanji_bank_v1() {
+    let kanji = json!([["日", "ニチ ジツ", "ひ か", "jouyou", "day", "sun", "Japan"]]);
+    let result: DictionaryKanjiBankV1 = serde_json::from_value(kanji).unwrap();
+    let first = result.first().unwrap();
+    assert_eq!(first.0, "日");
+    assert_eq!(first.4, ["day", "sun", "Japan"]);
+
+    let row = DictionaryKanjiBankV3Row::from(result.into_iter().next().unwrap());
+    assert_eq!(row.4.len(), 3);
+    assert!(row.5.is_empty());
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+use serde_valid::Validate;
+use std::collections::HashMap;
+
+/// Data file containing kanji information.
+pub type DictionaryKanjiBankV3 = Vec<DictionaryKanjiBankV3Row>;
+
+#[derive(Deserialize, Serialize, Debug, Validate)]
+/// Information about a single kanji character.
+pub struct DictionaryKanjiBankV3Row(
+    /// Kanji character.
+    pub String,
+    /// String of space-separated onyomi readings for the kanji character. An empty string is treated as no readings.
+    pub String,
+    /// String of space-separated kunyomi readings for the kanji character. An empty string is treated as no readings.
+    pub String,
+    /// String of space-separated tags for the kanji character. An empty string is treated as no tags.
+    pub String,
+    /// Array of meanings for the kanji character.
+    pub Vec<String>,
+    /// Various stats for the kanji character.
+    pub KanjiStats,
+);
+
+/// Stats for the kanji character, keyed by the name of a tag.
+pub type KanjiStats = HashMap<String, String>;
+
+#[cfg(test)]
+mod test;
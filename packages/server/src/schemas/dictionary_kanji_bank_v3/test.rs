@@ -0,0 +1,44 @@
+use super::*;
+use serde_json::{Value, json};
+use std::fs;
+
+fn get_kanji_bank() -> Vec<Value> {
+    // Path is relative to the Cargo.toml of the package
+    let data = fs::read_to_string("src/fixtures/kanji_bank.json").unwrap();
+    let kanji_bank: Value = serde_json::from_str(&data).unwrap();
+    let kanji_bank = kanji_bank.as_array().unwrap();
+    kanji_bank.to_vec()
+}
+
+#[test]
+fn should_parse_kanji_bank_0() {
+    let kanji = json!([get_kanji_bank().first().unwrap()]);
+    let result: Result<DictionaryKanjiBankV3, _> = serde_json::from_value(kanji);
+    assert!(result.is_ok());
+    let result = result.unwrap();
+    let first = result.first().unwrap();
+    assert_eq!(first.0, "日");
+    assert_eq!(first.4.len(), 3);
+    assert_eq!(first.5.get("strokes").unwrap(), "4");
+}
+
+#[test]
+fn should_parse_kanji_bank_1() {
+    let kanji = json!([get_kanji_bank().get(1).unwrap()]);
+    let result: Result<DictionaryKanjiBankV3, _> = serde_json::from_value(kanji);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn should_not_parse_kanji_bank_2() {
+    let kanji = json!([get_kanji_bank().get(2).unwrap()]);
+    let result: Result<DictionaryKanjiBankV3, _> = serde_json::from_value(kanji);
+    assert!(result.is_err());
+}
+
+#[test]
+fn should_not_parse_kanji_bank_3() {
+    let kanji = json!([get_kanji_bank().get(3).unwrap()]);
+    let result: Result<DictionaryKanjiBankV3, _> = serde_json::from_value(kanji);
+    assert!(result.is_err());
+}
@@ -0,0 +1,34 @@
+use crate::schemas::dictionary_term_meta_bank_v3::Frequency;
+use serde::{Deserialize, Serialize};
+use serde_valid::Validate;
+
+/// Custom metadata for kanji characters.
+pub type DictionaryKanjiMetaBankV3 = Vec<DictionaryKanjiMetaBankV3Row>;
+
+#[derive(Deserialize, Serialize, Debug, Validate)]
+/// Metadata about a single kanji character.
+pub struct DictionaryKanjiMetaBankV3Row(
+    /// Kanji character.
+    pub String,
+    /// Type of data. "freq" corresponds to frequency information.
+    pub KanjiMetaMode,
+    /// Frequency information for the kanji character.
+    pub Frequency,
+);
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum KanjiMetaMode {
+    Freq,
+}
+
+impl KanjiMetaMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Freq => "freq",
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,58 @@
+use super::*;
+use crate::schemas::dictionary_term_meta_bank_v3::FrequencyValue;
+use serde_json::{Value, json};
+use std::fs;
+
+fn get_kanji_meta_bank() -> Vec<Value> {
+    // Path is relative to the Cargo.toml of the package
+    let data = fs::read_to_string("src/fixtures/kanji_meta_bank.json").unwrap();
+    let kanji_meta_bank: Value = serde_json::from_str(&data).unwrap();
+    let kanji_meta_bank = kanji_meta_bank.as_array().unwrap();
+    kanji_meta_bank.to_vec()
+}
+
+fn parse(index: usize) -> Result<DictionaryKanjiMetaBankV3, serde_json::Error> {
+    let kanji = json!([get_kanji_meta_bank().get(index).unwrap()]);
+    serde_json::from_value(kanji)
+}
+
+#[test]
+fn should_parse_kanji_meta_bank_0() {
+    let result = parse(0).unwrap();
+    let row = result.first().unwrap();
+    assert_eq!(row.0, "日");
+    assert_eq!(row.1, KanjiMetaMode::Freq);
+    assert_eq!(row.2.value(), Some(1.0));
+}
+
+#[test]
+fn should_parse_kanji_meta_bank_1() {
+    let result = parse(1).unwrap();
+    let row = result.first().unwrap();
+    if let Frequency::Object(FrequencyValue {
+        value,
+        display_value,
+    }) = &row.2
+    {
+        assert_eq!(*value, 10.0);
+        assert_eq!(display_value.as_deref(), Some("10★"));
+    } else {
+        panic!("Failed to parse kanji meta");
+    }
+}
+
+#[test]
+fn should_parse_kanji_meta_bank_2() {
+    let result = parse(2).unwrap();
+    assert_eq!(result.first().unwrap().2.value(), Some(25.0));
+}
+
+#[test]
+fn should_not_parse_kanji_meta_bank_3() {
+    assert!(parse(3).is_err());
+}
+
+#[test]
+fn should_not_parse_kanji_meta_bank_4() {
+    assert!(parse(4).is_err());
+}
@@ -0,0 +1,91 @@
+use crate::schemas::dictionary_term_bank_v3::{Definition, DictionaryTermBankV3Row};
+use serde::Deserialize;
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde_valid::Validate;
+use std::fmt;
+
+/// Data file containing term information, used by format 1 dictionaries.
+pub type DictionaryTermBankV1 = Vec<DictionaryTermBankV1Row>;
+
+/// Information about a single term. The definitions are the elements following the score
+/// rather than an array.
+#[derive(Debug, Validate)]
+pub struct DictionaryTermBankV1Row(
+    /// The text for the term.
+    pub String,
+    /// Reading of the term, or an empty string if the reading is the same as the term.
+    pub String,
+    /// String of space-separated tags for the definition. An empty string is treated as no tags.
+    pub Option<String>,
+    /// String of space-separated rule identifiers for the definition which is used to validate deinflection. An empty string should be used for words which aren't inflected.
+    pub String,
+    /// Score used to determine popularity. Negative values are more rare and positive values are more frequent. This score is also used to sort search results.
+    pub f32,
+    /// Single definitions for the term.
+    pub Vec<String>,
+);
+
+impl<'de> Deserialize<'de> for DictionaryTermBankV1Row {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RowVisitor;
+
+        impl<'de> Visitor<'de> for RowVisitor {
+            type Value = DictionaryTermBankV1Row;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an array of at least 5 elements")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let expression = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let reading = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                let definition_tags = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+                let rules = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(3, &self))?;
+                let score = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(4, &self))?;
+                let mut glossary = vec![];
+                while let Some(definition) = seq.next_element()? {
+                    glossary.push(definition);
+                }
+                Ok(DictionaryTermBankV1Row(
+                    expression,
+                    reading,
+                    definition_tags,
+                    rules,
+                    score,
+                    glossary,
+                ))
+            }
+        }
+
+        deserializer.deserialize_seq(RowVisitor)
+    }
+}
+
+impl From<DictionaryTermBankV1Row> for DictionaryTermBankV3Row {
+    /// Format 1 has no sequence numbers or term tags, terms get the sequence -1 like in Yomitan.
+    fn from(row: DictionaryTermBankV1Row) -> Self {
+        DictionaryTermBankV3Row(
+            row.0,
+            row.1,
+            row.2,
+            row.3,
+            row.4,
+            row.5.into_iter().map(Definition::Text).collect(),
+            -1,
+            String::new(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,26 @@
+use super::*;
+use serde_json::json;
+
+#[test]
+fn should_parse_term_bank_v1() {
+    let term = json!([["読む", "よむ", "v5", "v5", 10, "to read", "to recite"]]);
+    let result: DictionaryTermBankV1 = serde_json::from_value(term).unwrap();
+    let first = result.first().unwrap();
+    assert_eq!(first.0, "読む");
+    assert_eq!(first.5, ["to read", "to recite"]);
+
+    let row = DictionaryTermBankV3Row::from(result.into_iter().next().unwrap());
+    assert_eq!(row.5.len(), 2);
+    assert_eq!(row.6, -1);
+}
+
+#[test]
+fn should_not_parse_term_bank_v1() {
+    let term = json!([["読む", "よむ", "v5", "v5"]]);
+    let result: Result<DictionaryTermBankV1, _> = serde_json::from_value(term);
+    assert!(result.is_err());
+
+    let term = json!([["読む", "よむ", "v5", "v5", 10, ["to read"]]]);
+    let result: Result<DictionaryTermBankV1, _> = serde_json::from_value(term);
+    assert!(result.is_err());
+}
@@ -0,0 +1,42 @@
+use crate::schemas::dictionary_term_bank_v3::{Definition, DictionaryTermBankV3Row};
+use serde::{Deserialize, Serialize};
+use serde_valid::Validate;
+
+/// Data file containing term information, used by format 2 dictionaries.
+pub type DictionaryTermBankV2 = Vec<DictionaryTermBankV2Row>;
+
+#[derive(Deserialize, Serialize, Debug, Validate)]
+/// Information about a single term. Same as format 3 except that definitions can only be text.
+pub struct DictionaryTermBankV2Row(
+    /// The text for the term.
+    pub String,
+    /// Reading of the term, or an empty string if the reading is the same as the term.
+    pub String,
+    /// String of space-separated tags for the definition. An empty string is treated as no tags.
+    pub Option<String>,
+    /// String of space-separated rule identifiers for the definition which is used to validate deinflection. An empty string should be used for words which aren't inflected.
+    pub String,
+    /// Score used to determine popularity. Negative values are more rare and positive values are more frequent. This score is also used to sort search results.
+    pub f32,
+    /// Array of definitions for the term.
+    pub Vec<String>,
+    /// Sequence number for the term. Terms with the same sequence number can be shown together when the "resultOutputMode" option is set to "merge".
+    pub i32,
+    /// String of space-separated tags for the term. An empty string is treated as no tags.
+    pub String,
+);
+
+impl From<DictionaryTermBankV2Row> for DictionaryTermBankV3Row {
+    fn from(row: DictionaryTermBankV2Row) -> Self {
+        DictionaryTermBankV3Row(
+            row.0,
+            row.1,
+            row.2,
+            row.3,
+            row.4,
+            row.5.into_iter().map(Definition::Text).collect(),
+            row.6,
+            row.7,
+        )
+    }
+}
@@ -2,11 +2,12 @@ use serde::{Deserialize, Serialize};
 use serde_valid::Validate;
 use std::collections::HashMap;
 
-// TODO: serde_valie .validate needs to be invoked manually
+// serde_valid names fields in its errors after the first string of their serde attributes and
+// ignores rename_all, so validated fields that have one start with an explicit rename.
 
 pub type DictionaryTermBankV3 = Vec<DictionaryTermBankV3Row>;
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, Validate)]
 /// Information about a single term.
 pub struct DictionaryTermBankV3Row(
     /// The text for the term.
@@ -20,6 +21,8 @@ pub struct DictionaryTermBankV3Row(
     /// Score used to determine popularity. Negative values are more rare and positive values are more frequent. This score is also used to sort search results.
     pub f32,
     /// Array of definitions for the term.
+    #[validate]
+    #[serde(deserialize_with = "deserialize_definitions")]
     pub Vec<Definition>,
     /// Sequence number for the term. Terms with the same sequence number can be shown together when the "resultOutputMode" option is set to "merge".
     pub i32,
@@ -27,26 +30,29 @@ pub struct DictionaryTermBankV3Row(
     pub String,
 );
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, Validate)]
 #[serde(untagged)]
 pub enum Definition {
     /// Single definition for the term.
     Text(String),
     /// Single detailed definition for the term.
-    Detailed(Box<DetailedDefinition>),
+    Detailed(#[validate] Box<DetailedDefinition>),
     /// Deinflection of the term to an uninflected term.
     Deinflection(Deinflection),
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, Validate)]
 #[serde(tag = "type", rename_all = "kebab-case")]
 pub enum DetailedDefinition {
     Text(TextDefinition),
-    Image(ImageDefinition),
-    StructuredContent(StructuredContentDefinition),
+    Image(#[validate] ImageDefinition),
+    StructuredContent(#[validate] StructuredContentDefinition),
+    /// Not part of the Yomitan schema, written by the StarDict and MDict importers. Refused in
+    /// the rows of a term bank, so it is only read back from the database.
+    Html(HtmlDefinition),
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Deinflection(
     /// The uninflected term.
     pub String,
@@ -57,24 +63,30 @@ pub struct Deinflection(
 /// A single inflection rule.
 pub type InflectedTerm = String;
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct TextDefinition {
     /// Single definition for the term.
-    text: String,
+    pub text: String,
 }
 
-#[derive(Deserialize, Serialize, Debug, Validate)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct HtmlDefinition {
+    /// Single definition for the term as an HTML fragment.
+    pub html: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Validate)]
 #[serde(rename_all = "camelCase")]
 pub struct ImageDefinition {
     /// Path to the image file in the archive.
     pub path: String,
     /// Preferred width of the image.
     #[validate(minimum = 1)]
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "width", skip_serializing_if = "Option::is_none")]
     pub width: Option<i32>,
     /// Preferred height of the image.
     #[validate(minimum = 1)]
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "height", skip_serializing_if = "Option::is_none")]
     pub height: Option<i32>,
     /// Hover text for the image.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -90,11 +102,11 @@ pub struct ImageDefinition {
     pub pixelated: bool,
     /// Controls how the image is rendered. The value of this field supersedes the pixelated field.
     #[validate(enumerate = ["auto", "pixelated", "crisp-edges"])]
-    #[serde(default = "default_auto")]
+    #[serde(rename = "imageRendering", default = "default_auto")]
     pub image_rendering: String,
     /// Controls the appearance of the image. The "monochrome" value will mask the opaque parts of the image using the current text color.
     #[validate(enumerate = ["auto", "monochrome"])]
-    #[serde(default = "default_auto")]
+    #[serde(rename = "appearance", default = "default_auto")]
     pub appearance: String,
     /// Whether or not a background color is displayed behind the image.
     #[serde(default = "default_true")]
@@ -107,55 +119,56 @@ pub struct ImageDefinition {
     pub collapsible: bool,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, Validate)]
 pub struct StructuredContentDefinition {
     /// Single definition for the term using a structured content object.
+    #[validate]
     pub content: Box<StructuredContent>,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, Validate)]
 #[serde(untagged)]
 pub enum StructuredContent {
     /// Represents a text node.
     Text(String),
     /// An array of child content.
-    Array(Vec<StructuredContent>),
-    Object(Box<StructuredContentObject>),
+    Array(#[validate] Vec<StructuredContent>),
+    Object(#[validate] Box<StructuredContentObject>),
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, Validate)]
 #[serde(tag = "tag", rename_all = "lowercase")]
 pub enum StructuredContentObject {
-    Br(BreakFields),
-
-    Ruby(ContainerFields),
-    Rt(ContainerFields),
-    Rp(ContainerFields),
-    Table(ContainerFields),
-    Thead(ContainerFields),
-    Tbody(ContainerFields),
-    Tfoot(ContainerFields),
-    Tr(ContainerFields),
-
-    Td(TableElementFields),
-    Th(TableElementFields),
-
-    Span(StyledContainerFields),
-    Div(StyledContainerFields),
-    Ol(StyledContainerFields),
-    Ul(StyledContainerFields),
-    Li(StyledContainerFields),
-    Details(StyledContainerFields),
-    Summary(StyledContainerFields),
-
-    Img(ImageFields),
-
-    A(LinkFields),
+    Br(#[validate] BreakFields),
+
+    Ruby(#[validate] ContainerFields),
+    Rt(#[validate] ContainerFields),
+    Rp(#[validate] ContainerFields),
+    Table(#[validate] ContainerFields),
+    Thead(#[validate] ContainerFields),
+    Tbody(#[validate] ContainerFields),
+    Tfoot(#[validate] ContainerFields),
+    Tr(#[validate] ContainerFields),
+
+    Td(#[validate] TableElementFields),
+    Th(#[validate] TableElementFields),
+
+    Span(#[validate] StyledContainerFields),
+    Div(#[validate] StyledContainerFields),
+    Ol(#[validate] StyledContainerFields),
+    Ul(#[validate] StyledContainerFields),
+    Li(#[validate] StyledContainerFields),
+    Details(#[validate] StyledContainerFields),
+    Summary(#[validate] StyledContainerFields),
+
+    Img(#[validate] ImageFields),
+
+    A(#[validate] LinkFields),
 }
 
 impl StructuredContentObject {
     /// Returns the tag name associated with this variant.
-    pub fn _tag(&self) -> &'static str {
+    pub fn tag(&self) -> &'static str {
         match self {
             Self::Br(_) => "br",
             Self::Ruby(_) => "ruby",
@@ -184,16 +197,17 @@ impl StructuredContentObject {
 // --- Data Structures for the Variants ---
 
 /// Empty tags.
-#[derive(Deserialize, Serialize, Debug, Validate)]
+#[derive(Deserialize, Serialize, Debug, Clone, Validate)]
 pub struct BreakFields {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<StructuredContentData>,
 }
 
 /// Generic container tags.
-#[derive(Deserialize, Serialize, Debug, Validate)]
+#[derive(Deserialize, Serialize, Debug, Clone, Validate)]
 pub struct ContainerFields {
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "content", skip_serializing_if = "Option::is_none")]
+    #[validate]
     pub content: Option<StructuredContent>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<StructuredContentData>,
@@ -203,20 +217,22 @@ pub struct ContainerFields {
 }
 
 /// Table tags.
-#[derive(Deserialize, Serialize, Debug, Validate)]
+#[derive(Deserialize, Serialize, Debug, Clone, Validate)]
 #[serde(rename_all = "camelCase")]
 pub struct TableElementFields {
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "content", skip_serializing_if = "Option::is_none")]
+    #[validate]
     pub content: Option<StructuredContent>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<StructuredContentData>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "colSpan", skip_serializing_if = "Option::is_none")]
     #[validate(minimum = 1)]
     pub col_span: Option<i32>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "rowSpan", skip_serializing_if = "Option::is_none")]
     #[validate(minimum = 1)]
     pub row_span: Option<i32>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "style", skip_serializing_if = "Option::is_none")]
+    #[validate]
     pub style: Option<StructuredContentStyle>,
     /// Defines the language of an element in the format defined by RFC 5646.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -224,13 +240,15 @@ pub struct TableElementFields {
 }
 
 /// Container tags supporting configurable styles.
-#[derive(Deserialize, Serialize, Debug, Validate)]
+#[derive(Deserialize, Serialize, Debug, Clone, Validate)]
 pub struct StyledContainerFields {
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "content", skip_serializing_if = "Option::is_none")]
+    #[validate]
     pub content: Option<StructuredContent>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<StructuredContentData>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "style", skip_serializing_if = "Option::is_none")]
+    #[validate]
     pub style: Option<StructuredContentStyle>,
     /// Hover text for the element.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -244,7 +262,7 @@ pub struct StyledContainerFields {
 }
 
 /// Image tag.
-#[derive(Deserialize, Serialize, Debug, Validate)]
+#[derive(Deserialize, Serialize, Debug, Clone, Validate)]
 #[serde(rename_all = "camelCase")]
 pub struct ImageFields {
     /// Path to the image file in the archive.
@@ -252,11 +270,11 @@ pub struct ImageFields {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<Box<StructuredContentData>>,
     /// Preferred width of the image.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "width", skip_serializing_if = "Option::is_none")]
     #[validate(minimum = 0.0)]
     pub width: Option<f32>,
     /// Preferred height of the image.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "height", skip_serializing_if = "Option::is_none")]
     #[validate(minimum = 0.0)]
     pub height: Option<f32>,
     /// Hover text for the image.
@@ -272,11 +290,11 @@ pub struct ImageFields {
     #[serde(default)]
     pub pixelated: bool,
     /// Controls how the image is rendered. The value of this field supersedes the pixelated field.
-    #[serde(default = "default_auto")]
+    #[serde(rename = "imageRendering", default = "default_auto")]
     #[validate(enumerate = ["auto", "pixelated", "crisp-edges"])]
     pub image_rendering: String,
     /// Controls the appearance of the image. The "monochrome" value will mask the opaque parts of the image using the current text color.
-    #[serde(default = "default_auto")]
+    #[serde(rename = "appearance", default = "default_auto")]
     #[validate(enumerate = ["auto", "monochrome"])]
     pub appearance: String,
     /// Whether or not a background color is displayed behind the image.
@@ -289,7 +307,7 @@ pub struct ImageFields {
     #[serde(default)]
     pub collapsible: bool,
     /// The vertical alignment of the image.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "verticalAlign", skip_serializing_if = "Option::is_none")]
     #[validate(enumerate = ["baseline", "sub", "super", "text-top", "text-bottom", "middle", "top", "bottom"])]
     pub vertical_align: Option<String>,
     /// Shorthand for border width, style, and color.
@@ -299,17 +317,18 @@ pub struct ImageFields {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub border_radius: Option<String>,
     /// The units for the width and height.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "sizeUnits", skip_serializing_if = "Option::is_none")]
     #[validate(enumerate = ["px", "em"])]
     pub size_units: Option<String>,
 }
 
 /// Link tag.
-#[derive(Deserialize, Serialize, Debug, Validate)]
+#[derive(Deserialize, Serialize, Debug, Clone, Validate)]
 pub struct LinkFields {
     #[validate(pattern = r"^(?:https?:|\?)[\w\W]*")]
     pub href: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "content", skip_serializing_if = "Option::is_none")]
+    #[validate]
     pub content: Option<StructuredContent>,
     /// Defines the language of an element in the format defined by RFC 5646.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -317,16 +336,16 @@ pub struct LinkFields {
 }
 
 /// Generic data attributes that should be added to the element.
-type StructuredContentData = HashMap<String, String>;
+pub type StructuredContentData = HashMap<String, String>;
 
-#[derive(Deserialize, Serialize, Debug, Validate)]
+#[derive(Deserialize, Serialize, Debug, Clone, Validate)]
 #[serde(rename_all = "camelCase")]
 pub struct StructuredContentStyle {
-    #[serde(default = "default_normal")]
+    #[serde(rename = "fontStyle", default = "default_normal")]
     #[validate(enumerate = ["normal", "italic"])]
     pub font_style: String,
 
-    #[serde(default = "default_normal")]
+    #[serde(rename = "fontWeight", default = "default_normal")]
     #[validate(enumerate = ["normal", "bold"])]
     pub font_weight: String,
 
@@ -345,7 +364,7 @@ pub struct StructuredContentStyle {
     #[serde(default)]
     pub text_decoration_line: TextDecorationLine,
 
-    #[serde(default = "default_solid")]
+    #[serde(rename = "textDecorationStyle", default = "default_solid")]
     #[validate(enumerate = ["solid", "double", "dotted", "dashed", "wavy"])]
     pub text_decoration_style: String,
 
@@ -367,11 +386,11 @@ pub struct StructuredContentStyle {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub clip_path: Option<String>,
 
-    #[serde(default = "default_baseline")]
+    #[serde(rename = "verticalAlign", default = "default_baseline")]
     #[validate(enumerate = ["baseline", "sub", "super", "text-top", "text-bottom", "middle", "top", "bottom"])]
     pub vertical_align: String,
 
-    #[serde(default = "default_start")]
+    #[serde(rename = "textAlign", default = "default_start")]
     #[validate(enumerate = ["start", "end", "left", "right", "center", "justify", "justify-all", "match-parent"])]
     pub text_align: String,
 
@@ -411,7 +430,7 @@ pub struct StructuredContentStyle {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub padding_bottom: Option<String>,
 
-    #[serde(default = "default_normal")]
+    #[serde(rename = "wordBreak", default = "default_normal")]
     #[validate(enumerate = ["normal", "break-all", "keep-all"])]
     pub word_break: String,
 
@@ -425,7 +444,7 @@ pub struct StructuredContentStyle {
     pub list_style_type: String,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(untagged)]
 pub enum TextDecorationLine {
     Single(String),
@@ -438,7 +457,7 @@ impl Default for TextDecorationLine {
     }
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(untagged)]
 pub enum NumberOrString {
     Number(f32),
@@ -476,6 +495,24 @@ fn default_true() -> bool {
     true
 }
 
+/// Definitions of a term bank row, which can't hold the HTML written by other importers.
+fn deserialize_definitions<'de, D>(deserializer: D) -> Result<Vec<Definition>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let definitions = Vec::<Definition>::deserialize(deserializer)?;
+    let html = definitions.iter().any(|definition| match definition {
+        Definition::Detailed(detailed) => matches!(**detailed, DetailedDefinition::Html(_)),
+        _ => false,
+    });
+    if html {
+        return Err(serde::de::Error::custom(
+            "html definitions are not part of the Yomitan schema",
+        ));
+    }
+    Ok(definitions)
+}
+
 mod r#impl;
 
 #[cfg(test)]
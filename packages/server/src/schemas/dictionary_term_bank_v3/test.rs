@@ -143,3 +143,15 @@ fn should_parse_term_bank_14() {
         panic!("Failed to parse term");
     }
 }
+
+#[test]
+fn should_not_parse_html_definitions() {
+    let html = json!({ "type": "html", "html": "<b>raw</b>" });
+    let term = json!([["a", "", "", "", 0, [html], 0, ""]]);
+    let result: Result<DictionaryTermBankV3, _> = serde_json::from_value(term);
+    assert!(result.is_err());
+
+    // Definitions written by other importers are still read back from the database
+    let result: Result<Vec<Definition>, _> = serde_json::from_value(json!([html]));
+    assert!(result.is_ok());
+}
@@ -33,7 +33,8 @@ export type Definition =
 export type DetailedDefinition =
   | ({ type: "text" } & TextDefinition)
   | ({ type: "image" } & ImageDefinition)
-  | ({ type: "structured-content" } & StructuredContentDefinition);
+  | ({ type: "structured-content" } & StructuredContentDefinition)
+  | ({ type: "html" } & HtmlDefinition);
 
 /**
  * Text definition - simple string content
@@ -42,6 +43,13 @@ export type TextDefinition = {
   text: string;
 };
 
+/**
+ * HTML definition - not part of the Yomitan schema, written by the StarDict and MDict importers
+ */
+export type HtmlDefinition = {
+  html: string;
+};
+
 /**
  * Image definition with display and styling options
  */
@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+use serde_valid::Validate;
+
+/// Custom metadata for terms.
+pub type DictionaryTermMetaBankV3 = Vec<DictionaryTermMetaBankV3Row>;
+
+#[derive(Deserialize, Serialize, Debug, Validate)]
+#[validate(custom = |s| s.validate_mode())]
+/// Metadata about a single term.
+pub struct DictionaryTermMetaBankV3Row(
+    /// The text for the term.
+    pub String,
+    /// Type of data. "freq" corresponds to frequency information; "pitch" corresponds to pitch information; "ipa" corresponds to IPA transcription.
+    pub TermMetaMode,
+    /// Data for the term. The shape depends on the mode.
+    #[validate]
+    pub TermMetaData,
+);
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TermMetaMode {
+    Freq,
+    Pitch,
+    Ipa,
+}
+
+#[derive(Deserialize, Serialize, Debug, Validate)]
+#[serde(untagged)]
+pub enum TermMetaData {
+    /// Pitch accent information for the term.
+    Pitch(#[validate] PitchData),
+    /// IPA transcription information for the term.
+    Ipa(#[validate] IpaData),
+    /// Frequency information for the term.
+    Frequency(FrequencyData),
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(untagged)]
+pub enum FrequencyData {
+    /// Frequency information for the term with a specific reading.
+    WithReading(FrequencyWithReading),
+    /// Frequency information for the term.
+    Frequency(Frequency),
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct FrequencyWithReading {
+    /// Reading for the term.
+    pub reading: String,
+    /// Frequency information for the term.
+    pub frequency: Frequency,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(untagged)]
+pub enum Frequency {
+    /// Frequency value for the term.
+    Number(f64),
+    /// Frequency value for the term, as a string.
+    String(String),
+    /// Frequency value with an optional display value.
+    Object(FrequencyValue),
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FrequencyValue {
+    /// Frequency value for the term.
+    pub value: f64,
+    /// String of the frequency value which is displayed instead of the numerical value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_value: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Validate)]
+pub struct PitchData {
+    /// Reading for the term.
+    pub reading: String,
+    /// List of different pitch accent information for the term and reading combination.
+    #[validate]
+    pub pitches: Vec<Pitch>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Validate)]
+pub struct Pitch {
+    /// Mora position of the pitch accent downstep. A value of 0 indicates that the word does not have a downstep (heiban).
+    #[validate(minimum = 0)]
+    pub position: i32,
+    /// Positions of morae with a nasal sound.
+    #[validate(custom = r#impl::validate_positions)]
+    #[serde(rename = "nasal", skip_serializing_if = "Option::is_none")]
+    pub nasal: Option<OneOrMany<i32>>,
+    /// Positions of morae with a devoiced sound.
+    #[validate(custom = r#impl::validate_positions)]
+    #[serde(rename = "devoice", skip_serializing_if = "Option::is_none")]
+    pub devoice: Option<OneOrMany<i32>>,
+    /// List of tags for this pitch accent. This typically corresponds to a certain type of part of speech.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Validate)]
+pub struct IpaData {
+    /// Reading for the term.
+    pub reading: String,
+    /// List of different IPA transcription information for the term and reading combination.
+    pub transcriptions: Vec<IpaTranscription>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Validate)]
+pub struct IpaTranscription {
+    /// IPA transcription for the term.
+    pub ipa: String,
+    /// List of tags for this IPA transcription.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(untagged)]
+pub enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+mod r#impl;
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,88 @@
+use super::*;
+use serde_valid::validation::Error;
+
+impl DictionaryTermMetaBankV3Row {
+    pub(super) fn validate_mode(&self) -> Result<(), Error> {
+        if self.2.matches_mode(self.1) {
+            Ok(())
+        } else {
+            Err(Error::Custom(format!(
+                "The data does not match mode `{}`.",
+                self.1.as_str()
+            )))
+        }
+    }
+}
+
+/// Mora positions start from 0.
+pub(super) fn validate_positions(positions: &Option<OneOrMany<i32>>) -> Result<(), Error> {
+    let invalid = match positions {
+        Some(OneOrMany::One(position)) => *position < 0,
+        Some(OneOrMany::Many(positions)) => positions.iter().any(|p| *p < 0),
+        None => false,
+    };
+    if invalid {
+        return Err(Error::Custom("Positions must be `>= 0`.".to_string()));
+    }
+    Ok(())
+}
+
+impl TermMetaMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Freq => "freq",
+            Self::Pitch => "pitch",
+            Self::Ipa => "ipa",
+        }
+    }
+}
+
+impl TermMetaData {
+    /// Returns the reading this metadata is restricted to, if any.
+    pub fn reading(&self) -> Option<&str> {
+        match self {
+            Self::Pitch(pitch) => Some(&pitch.reading),
+            Self::Ipa(ipa) => Some(&ipa.reading),
+            Self::Frequency(FrequencyData::WithReading(freq)) => Some(&freq.reading),
+            Self::Frequency(FrequencyData::Frequency(_)) => None,
+        }
+    }
+
+    /// Returns the numeric frequency value for frequency data.
+    pub fn frequency(&self) -> Option<f64> {
+        match self {
+            Self::Frequency(freq) => freq.frequency().value(),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the shape of the data agrees with the declared mode.
+    pub fn matches_mode(&self, mode: TermMetaMode) -> bool {
+        matches!(
+            (self, mode),
+            (Self::Frequency(_), TermMetaMode::Freq)
+                | (Self::Pitch(_), TermMetaMode::Pitch)
+                | (Self::Ipa(_), TermMetaMode::Ipa)
+        )
+    }
+}
+
+impl FrequencyData {
+    pub fn frequency(&self) -> &Frequency {
+        match self {
+            Self::WithReading(freq) => &freq.frequency,
+            Self::Frequency(freq) => freq,
+        }
+    }
+}
+
+impl Frequency {
+    /// Numerical value of the frequency, parsing string values when possible.
+    pub fn value(&self) -> Option<f64> {
+        match self {
+            Self::Number(value) => Some(*value),
+            Self::String(value) => value.trim().parse().ok(),
+            Self::Object(value) => Some(value.value),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use super::*;
+use serde_json::{Value, json};
+use std::fs;
+
+fn get_term_meta_bank() -> Vec<Value> {
+    // Path is relative to the Cargo.toml of the package
+    let data = fs::read_to_string("src/fixtures/term_meta_bank.json").unwrap();
+    let term_meta_bank: Value = serde_json::from_str(&data).unwrap();
+    let term_meta_bank = term_meta_bank.as_array().unwrap();
+    term_meta_bank.to_vec()
+}
+
+fn parse(index: usize) -> Result<DictionaryTermMetaBankV3, serde_json::Error> {
+    let term = json!([get_term_meta_bank().get(index).unwrap()]);
+    serde_json::from_value(term)
+}
+
+#[test]
+fn should_parse_term_meta_bank_0() {
+    let result = parse(0).unwrap();
+    let row = result.first().unwrap();
+    assert_eq!(row.1, TermMetaMode::Freq);
+    assert!(row.2.matches_mode(TermMetaMode::Freq));
+    assert!(row.2.reading().is_none());
+    if let TermMetaData::Frequency(freq) = &row.2 {
+        assert_eq!(freq.frequency().value(), Some(157.0));
+    } else {
+        panic!("Failed to parse term meta");
+    }
+}
+
+#[test]
+fn should_parse_term_meta_bank_1() {
+    let result = parse(1).unwrap();
+    let row = result.first().unwrap();
+    if let TermMetaData::Frequency(FrequencyData::Frequency(Frequency::Object(freq))) = &row.2 {
+        assert_eq!(freq.value, 157.0);
+        assert_eq!(freq.display_value.as_deref(), Some("157㋕"));
+    } else {
+        panic!("Failed to parse term meta");
+    }
+}
+
+#[test]
+fn should_parse_term_meta_bank_2() {
+    let result = parse(2).unwrap();
+    let row = result.first().unwrap();
+    assert_eq!(row.2.reading(), Some("にほん"));
+}
+
+#[test]
+fn should_parse_term_meta_bank_3() {
+    let result = parse(3).unwrap();
+    let row = result.first().unwrap();
+    assert_eq!(row.2.reading(), Some("にっぽん"));
+    if let TermMetaData::Frequency(freq) = &row.2 {
+        assert_eq!(freq.frequency().value(), Some(4620.0));
+    } else {
+        panic!("Failed to parse term meta");
+    }
+}
+
+#[test]
+fn should_parse_term_meta_bank_4() {
+    let result = parse(4).unwrap();
+    let row = result.first().unwrap();
+    assert_eq!(row.1, TermMetaMode::Pitch);
+    assert!(row.2.matches_mode(TermMetaMode::Pitch));
+    assert!(!row.2.matches_mode(TermMetaMode::Freq));
+}
+
+#[test]
+fn should_parse_term_meta_bank_5() {
+    let result = parse(5).unwrap();
+    let row = result.first().unwrap();
+    if let TermMetaData::Pitch(pitch) = &row.2 {
+        let first = pitch.pitches.first().unwrap();
+        assert_eq!(first.position, 1);
+        assert!(matches!(first.nasal, Some(OneOrMany::One(2))));
+        assert!(matches!(first.devoice, Some(OneOrMany::Many(_))));
+        assert!(pitch.validate().is_ok());
+    } else {
+        panic!("Failed to parse term meta");
+    }
+}
+
+#[test]
+fn should_parse_term_meta_bank_6() {
+    let result = parse(6).unwrap();
+    let row = result.first().unwrap();
+    assert_eq!(row.1, TermMetaMode::Ipa);
+    assert!(row.2.matches_mode(TermMetaMode::Ipa));
+    assert_eq!(row.2.reading(), Some("ねこ"));
+}
+
+#[test]
+fn should_not_parse_term_meta_bank_7() {
+    assert!(parse(7).is_err());
+}
+
+#[test]
+fn should_not_parse_term_meta_bank_8() {
+    assert!(parse(8).is_err());
+}
+
+#[test]
+fn should_not_parse_term_meta_bank_9() {
+    assert!(parse(9).is_err());
+}
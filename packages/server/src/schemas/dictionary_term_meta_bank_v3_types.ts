@@ -0,0 +1,71 @@
+/**
+ * Dictionary term meta bank V3 types
+ * Represents frequency, pitch accent and IPA metadata for terms
+ */
+
+export type DictionaryTermMetaBankV3 = Array<DictionaryTermMetaBankV3Row>;
+
+/**
+ * Term metadata row - represents a tuple structure
+ */
+export type DictionaryTermMetaBankV3Row =
+  | [string, "freq", FrequencyData] // Frequency information for the term
+  | [string, "pitch", PitchData] // Pitch accent information for the term
+  | [string, "ipa", IpaData]; // IPA transcription for the term
+
+export type TermMetaMode = "freq" | "pitch" | "ipa";
+
+export type TermMetaData = FrequencyData | PitchData | IpaData;
+
+/**
+ * Frequency value, with or without a display value
+ */
+export type Frequency =
+  | number
+  | string
+  | {
+      value: number;
+      displayValue?: string;
+    };
+
+/**
+ * Frequency information, optionally restricted to a reading
+ */
+export type FrequencyData =
+  | Frequency
+  | {
+      reading: string;
+      frequency: Frequency;
+    };
+
+/**
+ * Pitch accent information for a term and reading combination
+ */
+export type PitchData = {
+  reading: string;
+  pitches: Pitch[];
+};
+
+export type Pitch = {
+  /** Mora position of the downstep, 0 means heiban */
+  position: number;
+  /** Positions of morae with a nasal sound */
+  nasal?: number | number[];
+  /** Positions of morae with a devoiced sound */
+  devoice?: number | number[];
+  /** Tags for this pitch accent, typically a part of speech */
+  tags?: string[];
+};
+
+/**
+ * IPA transcription information for a term and reading combination
+ */
+export type IpaData = {
+  reading: string;
+  transcriptions: IpaTranscription[];
+};
+
+export type IpaTranscription = {
+  ipa: string;
+  tags?: string[];
+};
@@ -1,7 +1,14 @@
 use crate::{
     db::Db,
     routes::create_routes,
-    util::{config::Config, lexer, state::AppState},
+    util::{
+        config::Config,
+        jobs::Jobs,
+        lexer,
+        state::AppState,
+        translator::Translator,
+        updater::{HttpFetcher, Updater},
+    },
 };
 use anyhow::Context;
 use std::sync::Arc;
@@ -11,10 +18,17 @@ pub async fn serve(config: Arc<Config>) -> anyhow::Result<()> {
     let db = Arc::new(db);
     let lexer = lexer::Lexer::new()?;
     let lexer = Arc::new(lexer);
+    let translator = Translator::new()?;
+    let translator = Arc::new(translator);
+    let updater = Updater::new(HttpFetcher::default());
+    let updater = Arc::new(updater);
     let state = AppState {
         db: db.clone(),
         lexer: lexer.clone(),
         config: config.clone(),
+        translator: translator.clone(),
+        updater: updater.clone(),
+        jobs: Arc::new(Jobs::default()),
     };
 
     let app = create_routes(state);
@@ -0,0 +1,66 @@
+use crate::db::Db;
+use crate::db::tables::Dictionary;
+use crate::util::config::Config;
+use crate::util::progress::{ImportPhase, ImportProgress};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use zip::ZipWriter;
+use zip::write::SimpleFileOptions;
+
+/// Creates an empty directory under the temp dir, named after `name` and a random suffix.
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("{}-{:016x}", name, rand::random::<u64>()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Opens a new database in a workdir of its own.
+pub async fn temp_db(name: &str) -> (Arc<Config>, Db) {
+    let workdir = temp_dir(name).to_string_lossy().to_string();
+    let config = Arc::new(Config::new(Some(workdir), String::new(), 0).unwrap());
+    let db = Db::new(config.clone()).await.unwrap();
+    (config, db)
+}
+
+/// An unsequenced dictionary with the default settings, as read from the database.
+pub fn dictionary(id: i32) -> Dictionary {
+    serde_json::from_value(serde_json::json!({
+        "id": id,
+        "createdAt": "2026-01-01T00:00:00Z",
+        "updatedAt": "2026-01-01T00:00:00Z",
+        "title": format!("Dictionary {}", id),
+        "revision": "1",
+        "format": 3,
+        "sequenced": false,
+        "isUpdatable": false,
+        "settings": {
+            "priority": 0,
+            "enabled": true,
+            "collapsed": false,
+            "mergeMode": "merge",
+        },
+    }))
+    .unwrap()
+}
+
+/// Writes a zip archive holding `files` as name and content.
+pub fn write_zip(path: &Path, files: &[(&str, &str)]) {
+    let mut zip = ZipWriter::new(File::create(path).unwrap());
+    for (name, content) in files {
+        zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+        zip.write_all(content.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+/// Ignores the progress of an import.
+pub struct NoProgress;
+
+impl ImportProgress for NoProgress {
+    fn start(&self, _phase: ImportPhase, _length: u64) {}
+    fn set_message(&self, _message: &str) {}
+    fn inc(&self, _phase: ImportPhase, _delta: u64) {}
+    fn finish(&self) {}
+}
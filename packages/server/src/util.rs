@@ -1,7 +1,23 @@
 pub mod config;
+pub mod deinflector;
 pub mod dict;
+pub mod export;
+pub mod gc;
+pub mod html;
+pub mod jmdict;
+pub mod jobs;
+pub mod kana;
 pub mod lexer;
+pub mod merge;
+pub mod mdict;
+pub mod profile;
 pub mod progress;
+pub mod ranking;
 pub mod response;
+pub mod stardict;
 pub mod state;
+pub mod tags;
+pub mod translator;
+pub mod updater;
+pub mod validation;
 pub mod ve;
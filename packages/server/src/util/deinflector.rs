@@ -0,0 +1,234 @@
+// A port of Yomitan's LanguageTransformer
+// https://github.com/yomidevs/yomitan/blob/master/ext/js/language/language-transformer.js
+
+pub mod japanese;
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Describes the conditions and transforms of a language.
+pub struct LanguageTransformDescriptor {
+    pub conditions: Vec<Condition>,
+    pub transforms: Vec<Transform>,
+}
+
+pub struct Condition {
+    /// Identifier used by rules and by the `rules` column of dictionary entries.
+    pub name: &'static str,
+    /// Whether this condition can be matched against a dictionary entry.
+    pub is_dictionary_form: bool,
+    /// Conditions that are implied by this condition.
+    pub sub_conditions: &'static [&'static str],
+}
+
+pub struct Transform {
+    /// User facing name of the inflection, e.g. "past" or "-te".
+    pub name: &'static str,
+    pub rules: Vec<SuffixRule>,
+}
+
+pub struct SuffixRule {
+    pub inflected_suffix: &'static str,
+    pub deinflected_suffix: &'static str,
+    pub conditions_in: &'static [&'static str],
+    pub conditions_out: &'static [&'static str],
+}
+
+/// Shorthand for building a [`SuffixRule`].
+pub const fn suffix(
+    inflected_suffix: &'static str,
+    deinflected_suffix: &'static str,
+    conditions_in: &'static [&'static str],
+    conditions_out: &'static [&'static str],
+) -> SuffixRule {
+    SuffixRule {
+        inflected_suffix,
+        deinflected_suffix,
+        conditions_in,
+        conditions_out,
+    }
+}
+
+/// A candidate dictionary form produced from an inflected text.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeinflectionCandidate {
+    /// The candidate text.
+    pub term: String,
+    /// Condition flags the dictionary entry must satisfy, 0 matches any entry.
+    pub conditions: u32,
+    /// Inflections applied to the candidate to produce the source text, in order of application.
+    pub inflection_rules: Vec<String>,
+}
+
+struct CompiledRule {
+    inflected_suffix: &'static str,
+    deinflected_suffix: &'static str,
+    conditions_in: u32,
+    conditions_out: u32,
+}
+
+struct CompiledTransform {
+    name: &'static str,
+    rules: Vec<CompiledRule>,
+}
+
+struct TraceFrame {
+    transform: usize,
+    text: String,
+}
+
+pub struct Deinflector {
+    transforms: Vec<CompiledTransform>,
+    dictionary_form_flags: HashMap<&'static str, u32>,
+}
+
+impl Deinflector {
+    pub fn new(descriptor: LanguageTransformDescriptor) -> anyhow::Result<Self> {
+        if descriptor.conditions.len() > u32::BITS as usize {
+            bail!("Too many conditions: {}", descriptor.conditions.len());
+        }
+
+        let mut condition_flags = HashMap::new();
+        for (i, condition) in descriptor.conditions.iter().enumerate() {
+            condition_flags.insert(condition.name, 1u32 << i);
+        }
+        // Sub conditions may be nested, so resolve them until nothing changes
+        loop {
+            let mut changed = false;
+            for condition in &descriptor.conditions {
+                let mut flags = condition_flags[condition.name];
+                for sub_condition in condition.sub_conditions {
+                    match condition_flags.get(sub_condition) {
+                        Some(sub_flags) => flags |= sub_flags,
+                        None => bail!("Unknown sub condition: {}", sub_condition),
+                    }
+                }
+                if flags != condition_flags[condition.name] {
+                    condition_flags.insert(condition.name, flags);
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        let dictionary_form_flags = descriptor
+            .conditions
+            .iter()
+            .filter(|c| c.is_dictionary_form)
+            .map(|c| (c.name, condition_flags[c.name]))
+            .collect();
+
+        let get_flags = |names: &[&str]| -> anyhow::Result<u32> {
+            let mut flags = 0;
+            for name in names {
+                match condition_flags.get(name) {
+                    Some(f) => flags |= f,
+                    None => bail!("Unknown condition: {}", name),
+                }
+            }
+            Ok(flags)
+        };
+
+        let mut transforms = Vec::with_capacity(descriptor.transforms.len());
+        for transform in descriptor.transforms {
+            let mut rules = Vec::with_capacity(transform.rules.len());
+            for rule in transform.rules {
+                rules.push(CompiledRule {
+                    inflected_suffix: rule.inflected_suffix,
+                    deinflected_suffix: rule.deinflected_suffix,
+                    conditions_in: get_flags(rule.conditions_in)?,
+                    conditions_out: get_flags(rule.conditions_out)?,
+                });
+            }
+            transforms.push(CompiledTransform {
+                name: transform.name,
+                rules,
+            });
+        }
+
+        Ok(Self {
+            transforms,
+            dictionary_form_flags,
+        })
+    }
+
+    /// Produces every candidate dictionary form of `source`, including `source` itself.
+    pub fn deinflect(&self, source: &str) -> Vec<DeinflectionCandidate> {
+        let mut results: Vec<(String, u32, Vec<TraceFrame>)> =
+            vec![(source.to_string(), 0, Vec::new())];
+
+        let mut i = 0;
+        while i < results.len() {
+            let mut next = Vec::new();
+            let (text, conditions, trace) = &results[i];
+            for (transform_index, transform) in self.transforms.iter().enumerate() {
+                for rule in &transform.rules {
+                    if !Self::conditions_match(*conditions, rule.conditions_in)
+                        || !text.ends_with(rule.inflected_suffix)
+                    {
+                        continue;
+                    }
+                    let is_cycle = trace
+                        .iter()
+                        .any(|frame| frame.transform == transform_index && frame.text == *text);
+                    if is_cycle {
+                        continue;
+                    }
+
+                    let stem = &text[..text.len() - rule.inflected_suffix.len()];
+                    let new_text = format!("{}{}", stem, rule.deinflected_suffix);
+                    let mut new_trace = vec![TraceFrame {
+                        transform: transform_index,
+                        text: text.clone(),
+                    }];
+                    new_trace.extend(trace.iter().map(|frame| TraceFrame {
+                        transform: frame.transform,
+                        text: frame.text.clone(),
+                    }));
+                    next.push((new_text, rule.conditions_out, new_trace));
+                }
+            }
+            results.append(&mut next);
+            i += 1;
+        }
+
+        results
+            .into_iter()
+            .map(|(term, conditions, trace)| DeinflectionCandidate {
+                term,
+                conditions,
+                inflection_rules: trace
+                    .iter()
+                    .map(|frame| self.transforms[frame.transform].name.to_string())
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Resolves the space-separated `rules` of a dictionary entry into condition flags.
+    pub fn get_condition_flags_from_rules(&self, rules: &str) -> u32 {
+        rules
+            .split_whitespace()
+            .filter_map(|rule| self.dictionary_form_flags.get(rule))
+            .fold(0, |flags, f| flags | f)
+    }
+
+    /// Returns whether an entry with `rules` can be the dictionary form of `candidate`.
+    pub fn candidate_matches_rules(&self, candidate: &DeinflectionCandidate, rules: &str) -> bool {
+        Self::conditions_match(
+            candidate.conditions,
+            self.get_condition_flags_from_rules(rules),
+        )
+    }
+
+    pub fn conditions_match(current_conditions: u32, next_conditions: u32) -> bool {
+        current_conditions == 0 || (current_conditions & next_conditions) != 0
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,690 @@
+// Japanese transforms, based on Yomitan's japanese-transforms.js
+// https://github.com/yomidevs/yomitan/blob/master/ext/js/language/ja/japanese-transforms.js
+
+use super::*;
+
+pub fn descriptor() -> LanguageTransformDescriptor {
+    LanguageTransformDescriptor {
+        conditions: conditions(),
+        transforms: transforms(),
+    }
+}
+
+#[rustfmt::skip]
+fn conditions() -> Vec<Condition> {
+    let condition = |name, is_dictionary_form, sub_conditions| Condition { name, is_dictionary_form, sub_conditions };
+    vec![
+        condition("v", false, &["v1", "v5", "vk", "vs", "vz"]),
+        condition("v1", true, &["v1d", "v1p"]),
+        condition("v1d", false, &[]),
+        condition("v1p", false, &[]),
+        condition("v5", true, &["v5d", "v5s"]),
+        condition("v5d", false, &[]),
+        condition("v5s", false, &["v5ss", "v5sp"]),
+        condition("v5ss", false, &[]),
+        condition("v5sp", false, &[]),
+        condition("vk", true, &[]),
+        condition("vs", true, &[]),
+        condition("vz", true, &[]),
+        condition("adj-i", true, &[]),
+        condition("-ます", false, &[]),
+        condition("-ません", false, &[]),
+        condition("-て", false, &[]),
+        condition("-ば", false, &[]),
+        condition("-く", false, &[]),
+        condition("-た", false, &[]),
+        condition("-ん", false, &[]),
+        condition("-なさい", false, &[]),
+        condition("-ゃ", false, &[]),
+    ]
+}
+
+#[rustfmt::skip]
+fn transforms() -> Vec<Transform> {
+    vec![
+        Transform {
+            name: "-ば",
+            rules: vec![
+                suffix("ければ", "い", &["-ば"], &["adj-i"]),
+                suffix("えば", "う", &["-ば"], &["v5"]),
+                suffix("けば", "く", &["-ば"], &["v5"]),
+                suffix("げば", "ぐ", &["-ば"], &["v5"]),
+                suffix("せば", "す", &["-ば"], &["v5"]),
+                suffix("てば", "つ", &["-ば"], &["v5"]),
+                suffix("ねば", "ぬ", &["-ば"], &["v5"]),
+                suffix("べば", "ぶ", &["-ば"], &["v5"]),
+                suffix("めば", "む", &["-ば"], &["v5"]),
+                suffix("れば", "る", &["-ば"], &["v1", "v5", "vk", "vs", "vz"]),
+            ],
+        },
+        Transform {
+            name: "-ゃ",
+            rules: vec![
+                suffix("けりゃ", "ければ", &["-ゃ"], &["-ば"]),
+                suffix("きゃ", "ければ", &["-ゃ"], &["-ば"]),
+                suffix("や", "えば", &["-ゃ"], &["-ば"]),
+                suffix("きゃ", "けば", &["-ゃ"], &["-ば"]),
+                suffix("ぎゃ", "げば", &["-ゃ"], &["-ば"]),
+                suffix("しゃ", "せば", &["-ゃ"], &["-ば"]),
+                suffix("ちゃ", "てば", &["-ゃ"], &["-ば"]),
+                suffix("にゃ", "ねば", &["-ゃ"], &["-ば"]),
+                suffix("びゃ", "べば", &["-ゃ"], &["-ば"]),
+                suffix("みゃ", "めば", &["-ゃ"], &["-ば"]),
+                suffix("りゃ", "れば", &["-ゃ"], &["-ば"]),
+            ],
+        },
+        Transform {
+            name: "-ちゃ",
+            rules: vec![
+                suffix("ちゃ", "る", &[], &["v1"]),
+                suffix("いじゃ", "ぐ", &[], &["v5"]),
+                suffix("いちゃ", "く", &[], &["v5"]),
+                suffix("しちゃ", "す", &[], &["v5"]),
+                suffix("っちゃ", "う", &[], &["v5"]),
+                suffix("っちゃ", "く", &[], &["v5"]),
+                suffix("っちゃ", "つ", &[], &["v5"]),
+                suffix("っちゃ", "る", &[], &["v5"]),
+                suffix("んじゃ", "ぬ", &[], &["v5"]),
+                suffix("んじゃ", "ぶ", &[], &["v5"]),
+                suffix("んじゃ", "む", &[], &["v5"]),
+                suffix("じちゃ", "ずる", &[], &["vz"]),
+                suffix("しちゃ", "する", &[], &["vs"]),
+                suffix("為ちゃ", "為る", &[], &["vs"]),
+                suffix("きちゃ", "くる", &[], &["vk"]),
+                suffix("来ちゃ", "来る", &[], &["vk"]),
+                suffix("來ちゃ", "來る", &[], &["vk"]),
+            ],
+        },
+        Transform {
+            name: "-ちゃう",
+            rules: vec![
+                suffix("ちゃう", "る", &["v5"], &["v1"]),
+                suffix("いじゃう", "ぐ", &["v5"], &["v5"]),
+                suffix("いちゃう", "く", &["v5"], &["v5"]),
+                suffix("しちゃう", "す", &["v5"], &["v5"]),
+                suffix("っちゃう", "う", &["v5"], &["v5"]),
+                suffix("っちゃう", "く", &["v5"], &["v5"]),
+                suffix("っちゃう", "つ", &["v5"], &["v5"]),
+                suffix("っちゃう", "る", &["v5"], &["v5"]),
+                suffix("んじゃう", "ぬ", &["v5"], &["v5"]),
+                suffix("んじゃう", "ぶ", &["v5"], &["v5"]),
+                suffix("んじゃう", "む", &["v5"], &["v5"]),
+                suffix("じちゃう", "ずる", &["v5"], &["vz"]),
+                suffix("しちゃう", "する", &["v5"], &["vs"]),
+                suffix("為ちゃう", "為る", &["v5"], &["vs"]),
+                suffix("きちゃう", "くる", &["v5"], &["vk"]),
+                suffix("来ちゃう", "来る", &["v5"], &["vk"]),
+                suffix("來ちゃう", "來る", &["v5"], &["vk"]),
+            ],
+        },
+        Transform {
+            name: "-ちまう",
+            rules: vec![
+                suffix("ちまう", "る", &["v5"], &["v1"]),
+                suffix("いじまう", "ぐ", &["v5"], &["v5"]),
+                suffix("いちまう", "く", &["v5"], &["v5"]),
+                suffix("しちまう", "す", &["v5"], &["v5"]),
+                suffix("っちまう", "う", &["v5"], &["v5"]),
+                suffix("っちまう", "く", &["v5"], &["v5"]),
+                suffix("っちまう", "つ", &["v5"], &["v5"]),
+                suffix("っちまう", "る", &["v5"], &["v5"]),
+                suffix("んじまう", "ぬ", &["v5"], &["v5"]),
+                suffix("んじまう", "ぶ", &["v5"], &["v5"]),
+                suffix("んじまう", "む", &["v5"], &["v5"]),
+                suffix("じちまう", "ずる", &["v5"], &["vz"]),
+                suffix("しちまう", "する", &["v5"], &["vs"]),
+                suffix("為ちまう", "為る", &["v5"], &["vs"]),
+                suffix("きちまう", "くる", &["v5"], &["vk"]),
+                suffix("来ちまう", "来る", &["v5"], &["vk"]),
+                suffix("來ちまう", "來る", &["v5"], &["vk"]),
+            ],
+        },
+        Transform {
+            name: "-しまう",
+            rules: vec![
+                suffix("てしまう", "て", &["v5"], &["-て"]),
+                suffix("でしまう", "で", &["v5"], &["-て"]),
+            ],
+        },
+        Transform {
+            name: "-なさい",
+            rules: vec![
+                suffix("なさい", "る", &["-なさい"], &["v1"]),
+                suffix("いなさい", "う", &["-なさい"], &["v5"]),
+                suffix("きなさい", "く", &["-なさい"], &["v5"]),
+                suffix("ぎなさい", "ぐ", &["-なさい"], &["v5"]),
+                suffix("しなさい", "す", &["-なさい"], &["v5"]),
+                suffix("ちなさい", "つ", &["-なさい"], &["v5"]),
+                suffix("になさい", "ぬ", &["-なさい"], &["v5"]),
+                suffix("びなさい", "ぶ", &["-なさい"], &["v5"]),
+                suffix("みなさい", "む", &["-なさい"], &["v5"]),
+                suffix("りなさい", "る", &["-なさい"], &["v5"]),
+                suffix("じなさい", "ずる", &["-なさい"], &["vz"]),
+                suffix("しなさい", "する", &["-なさい"], &["vs"]),
+                suffix("為なさい", "為る", &["-なさい"], &["vs"]),
+                suffix("きなさい", "くる", &["-なさい"], &["vk"]),
+                suffix("来なさい", "来る", &["-なさい"], &["vk"]),
+                suffix("來なさい", "來る", &["-なさい"], &["vk"]),
+            ],
+        },
+        Transform {
+            name: "-そう",
+            rules: vec![
+                suffix("そう", "い", &[], &["adj-i"]),
+                suffix("そう", "る", &[], &["v1"]),
+                suffix("いそう", "う", &[], &["v5"]),
+                suffix("きそう", "く", &[], &["v5"]),
+                suffix("ぎそう", "ぐ", &[], &["v5"]),
+                suffix("しそう", "す", &[], &["v5"]),
+                suffix("ちそう", "つ", &[], &["v5"]),
+                suffix("にそう", "ぬ", &[], &["v5"]),
+                suffix("びそう", "ぶ", &[], &["v5"]),
+                suffix("みそう", "む", &[], &["v5"]),
+                suffix("りそう", "る", &[], &["v5"]),
+                suffix("じそう", "ずる", &[], &["vz"]),
+                suffix("しそう", "する", &[], &["vs"]),
+                suffix("為そう", "為る", &[], &["vs"]),
+                suffix("きそう", "くる", &[], &["vk"]),
+                suffix("来そう", "来る", &[], &["vk"]),
+                suffix("來そう", "來る", &[], &["vk"]),
+            ],
+        },
+        Transform {
+            name: "-すぎる",
+            rules: vec![
+                suffix("すぎる", "い", &["v1"], &["adj-i"]),
+                suffix("すぎる", "る", &["v1"], &["v1"]),
+                suffix("いすぎる", "う", &["v1"], &["v5"]),
+                suffix("きすぎる", "く", &["v1"], &["v5"]),
+                suffix("ぎすぎる", "ぐ", &["v1"], &["v5"]),
+                suffix("しすぎる", "す", &["v1"], &["v5"]),
+                suffix("ちすぎる", "つ", &["v1"], &["v5"]),
+                suffix("にすぎる", "ぬ", &["v1"], &["v5"]),
+                suffix("びすぎる", "ぶ", &["v1"], &["v5"]),
+                suffix("みすぎる", "む", &["v1"], &["v5"]),
+                suffix("りすぎる", "る", &["v1"], &["v5"]),
+                suffix("じすぎる", "ずる", &["v1"], &["vz"]),
+                suffix("しすぎる", "する", &["v1"], &["vs"]),
+                suffix("為すぎる", "為る", &["v1"], &["vs"]),
+                suffix("きすぎる", "くる", &["v1"], &["vk"]),
+                suffix("来すぎる", "来る", &["v1"], &["vk"]),
+                suffix("來すぎる", "來る", &["v1"], &["vk"]),
+            ],
+        },
+        Transform {
+            name: "-過ぎる",
+            rules: vec![
+                suffix("過ぎる", "い", &["v1"], &["adj-i"]),
+                suffix("過ぎる", "る", &["v1"], &["v1"]),
+                suffix("い過ぎる", "う", &["v1"], &["v5"]),
+                suffix("き過ぎる", "く", &["v1"], &["v5"]),
+                suffix("ぎ過ぎる", "ぐ", &["v1"], &["v5"]),
+                suffix("し過ぎる", "す", &["v1"], &["v5"]),
+                suffix("ち過ぎる", "つ", &["v1"], &["v5"]),
+                suffix("に過ぎる", "ぬ", &["v1"], &["v5"]),
+                suffix("び過ぎる", "ぶ", &["v1"], &["v5"]),
+                suffix("み過ぎる", "む", &["v1"], &["v5"]),
+                suffix("り過ぎる", "る", &["v1"], &["v5"]),
+                suffix("じ過ぎる", "ずる", &["v1"], &["vz"]),
+                suffix("し過ぎる", "する", &["v1"], &["vs"]),
+                suffix("為過ぎる", "為る", &["v1"], &["vs"]),
+                suffix("き過ぎる", "くる", &["v1"], &["vk"]),
+                suffix("来過ぎる", "来る", &["v1"], &["vk"]),
+                suffix("來過ぎる", "來る", &["v1"], &["vk"]),
+            ],
+        },
+        Transform {
+            name: "-たい",
+            rules: vec![
+                suffix("たい", "る", &["adj-i"], &["v1"]),
+                suffix("いたい", "う", &["adj-i"], &["v5"]),
+                suffix("きたい", "く", &["adj-i"], &["v5"]),
+                suffix("ぎたい", "ぐ", &["adj-i"], &["v5"]),
+                suffix("したい", "す", &["adj-i"], &["v5"]),
+                suffix("ちたい", "つ", &["adj-i"], &["v5"]),
+                suffix("にたい", "ぬ", &["adj-i"], &["v5"]),
+                suffix("びたい", "ぶ", &["adj-i"], &["v5"]),
+                suffix("みたい", "む", &["adj-i"], &["v5"]),
+                suffix("りたい", "る", &["adj-i"], &["v5"]),
+                suffix("じたい", "ずる", &["adj-i"], &["vz"]),
+                suffix("したい", "する", &["adj-i"], &["vs"]),
+                suffix("為たい", "為る", &["adj-i"], &["vs"]),
+                suffix("きたい", "くる", &["adj-i"], &["vk"]),
+                suffix("来たい", "来る", &["adj-i"], &["vk"]),
+                suffix("來たい", "來る", &["adj-i"], &["vk"]),
+            ],
+        },
+        Transform {
+            name: "-たら",
+            rules: vec![
+                suffix("かったら", "い", &[], &["adj-i"]),
+                suffix("たら", "る", &[], &["v1"]),
+                suffix("いたら", "く", &[], &["v5"]),
+                suffix("いだら", "ぐ", &[], &["v5"]),
+                suffix("したら", "す", &[], &["v5"]),
+                suffix("ったら", "う", &[], &["v5"]),
+                suffix("ったら", "つ", &[], &["v5"]),
+                suffix("ったら", "る", &[], &["v5"]),
+                suffix("んだら", "ぬ", &[], &["v5"]),
+                suffix("んだら", "ぶ", &[], &["v5"]),
+                suffix("んだら", "む", &[], &["v5"]),
+                suffix("じたら", "ずる", &[], &["vz"]),
+                suffix("したら", "する", &[], &["vs"]),
+                suffix("為たら", "為る", &[], &["vs"]),
+                suffix("きたら", "くる", &[], &["vk"]),
+                suffix("来たら", "来る", &[], &["vk"]),
+                suffix("來たら", "來る", &[], &["vk"]),
+                suffix("いったら", "いく", &[], &["v5"]),
+                suffix("行ったら", "行く", &[], &["v5"]),
+                suffix("ましたら", "ます", &[], &["-ます"]),
+            ],
+        },
+        Transform {
+            name: "-たり",
+            rules: vec![
+                suffix("かったり", "い", &[], &["adj-i"]),
+                suffix("たり", "る", &[], &["v1"]),
+                suffix("いたり", "く", &[], &["v5"]),
+                suffix("いだり", "ぐ", &[], &["v5"]),
+                suffix("したり", "す", &[], &["v5"]),
+                suffix("ったり", "う", &[], &["v5"]),
+                suffix("ったり", "つ", &[], &["v5"]),
+                suffix("ったり", "る", &[], &["v5"]),
+                suffix("んだり", "ぬ", &[], &["v5"]),
+                suffix("んだり", "ぶ", &[], &["v5"]),
+                suffix("んだり", "む", &[], &["v5"]),
+                suffix("じたり", "ずる", &[], &["vz"]),
+                suffix("したり", "する", &[], &["vs"]),
+                suffix("為たり", "為る", &[], &["vs"]),
+                suffix("きたり", "くる", &[], &["vk"]),
+                suffix("来たり", "来る", &[], &["vk"]),
+                suffix("來たり", "來る", &[], &["vk"]),
+                suffix("いったり", "いく", &[], &["v5"]),
+                suffix("行ったり", "行く", &[], &["v5"]),
+            ],
+        },
+        Transform {
+            name: "-て",
+            rules: vec![
+                suffix("くて", "い", &["-て"], &["adj-i"]),
+                suffix("て", "る", &["-て"], &["v1"]),
+                suffix("いて", "く", &["-て"], &["v5"]),
+                suffix("いで", "ぐ", &["-て"], &["v5"]),
+                suffix("して", "す", &["-て"], &["v5"]),
+                suffix("って", "う", &["-て"], &["v5"]),
+                suffix("って", "つ", &["-て"], &["v5"]),
+                suffix("って", "る", &["-て"], &["v5"]),
+                suffix("んで", "ぬ", &["-て"], &["v5"]),
+                suffix("んで", "ぶ", &["-て"], &["v5"]),
+                suffix("んで", "む", &["-て"], &["v5"]),
+                suffix("じて", "ずる", &["-て"], &["vz"]),
+                suffix("して", "する", &["-て"], &["vs"]),
+                suffix("為て", "為る", &["-て"], &["vs"]),
+                suffix("きて", "くる", &["-て"], &["vk"]),
+                suffix("来て", "来る", &["-て"], &["vk"]),
+                suffix("來て", "來る", &["-て"], &["vk"]),
+                suffix("いって", "いく", &["-て"], &["v5"]),
+                suffix("行って", "行く", &["-て"], &["v5"]),
+                suffix("まして", "ます", &["-て"], &["-ます"]),
+            ],
+        },
+        Transform {
+            name: "-ず",
+            rules: vec![
+                suffix("ず", "る", &[], &["v1"]),
+                suffix("かず", "く", &[], &["v5"]),
+                suffix("がず", "ぐ", &[], &["v5"]),
+                suffix("さず", "す", &[], &["v5"]),
+                suffix("たず", "つ", &[], &["v5"]),
+                suffix("なず", "ぬ", &[], &["v5"]),
+                suffix("ばず", "ぶ", &[], &["v5"]),
+                suffix("まず", "む", &[], &["v5"]),
+                suffix("らず", "る", &[], &["v5"]),
+                suffix("わず", "う", &[], &["v5"]),
+                suffix("ぜず", "ずる", &[], &["vz"]),
+                suffix("せず", "する", &[], &["vs"]),
+                suffix("為ず", "為る", &[], &["vs"]),
+                suffix("こず", "くる", &[], &["vk"]),
+                suffix("来ず", "来る", &[], &["vk"]),
+                suffix("來ず", "來る", &[], &["vk"]),
+            ],
+        },
+        Transform {
+            name: "-ぬ",
+            rules: vec![
+                suffix("ぬ", "る", &[], &["v1"]),
+                suffix("かぬ", "く", &[], &["v5"]),
+                suffix("がぬ", "ぐ", &[], &["v5"]),
+                suffix("さぬ", "す", &[], &["v5"]),
+                suffix("たぬ", "つ", &[], &["v5"]),
+                suffix("なぬ", "ぬ", &[], &["v5"]),
+                suffix("ばぬ", "ぶ", &[], &["v5"]),
+                suffix("まぬ", "む", &[], &["v5"]),
+                suffix("らぬ", "る", &[], &["v5"]),
+                suffix("わぬ", "う", &[], &["v5"]),
+                suffix("ぜぬ", "ずる", &[], &["vz"]),
+                suffix("せぬ", "する", &[], &["vs"]),
+                suffix("為ぬ", "為る", &[], &["vs"]),
+                suffix("こぬ", "くる", &[], &["vk"]),
+                suffix("来ぬ", "来る", &[], &["vk"]),
+                suffix("來ぬ", "來る", &[], &["vk"]),
+            ],
+        },
+        Transform {
+            name: "-ん",
+            rules: vec![
+                suffix("ん", "る", &["-ん"], &["v1"]),
+                suffix("かん", "く", &["-ん"], &["v5"]),
+                suffix("がん", "ぐ", &["-ん"], &["v5"]),
+                suffix("さん", "す", &["-ん"], &["v5"]),
+                suffix("たん", "つ", &["-ん"], &["v5"]),
+                suffix("なん", "ぬ", &["-ん"], &["v5"]),
+                suffix("ばん", "ぶ", &["-ん"], &["v5"]),
+                suffix("まん", "む", &["-ん"], &["v5"]),
+                suffix("らん", "る", &["-ん"], &["v5"]),
+                suffix("わん", "う", &["-ん"], &["v5"]),
+                suffix("ぜん", "ずる", &["-ん"], &["vz"]),
+                suffix("せん", "する", &["-ん"], &["vs"]),
+                suffix("為ん", "為る", &["-ん"], &["vs"]),
+                suffix("こん", "くる", &["-ん"], &["vk"]),
+                suffix("来ん", "来る", &["-ん"], &["vk"]),
+                suffix("來ん", "來る", &["-ん"], &["vk"]),
+            ],
+        },
+        Transform {
+            name: "-く",
+            rules: vec![
+                suffix("く", "い", &["-く"], &["adj-i"]),
+            ],
+        },
+        Transform {
+            name: "-さ",
+            rules: vec![
+                suffix("さ", "い", &[], &["adj-i"]),
+            ],
+        },
+        Transform {
+            name: "causative",
+            rules: vec![
+                suffix("させる", "る", &["v1"], &["v1"]),
+                suffix("かせる", "く", &["v1"], &["v5"]),
+                suffix("がせる", "ぐ", &["v1"], &["v5"]),
+                suffix("させる", "す", &["v1"], &["v5"]),
+                suffix("たせる", "つ", &["v1"], &["v5"]),
+                suffix("なせる", "ぬ", &["v1"], &["v5"]),
+                suffix("ばせる", "ぶ", &["v1"], &["v5"]),
+                suffix("ませる", "む", &["v1"], &["v5"]),
+                suffix("らせる", "る", &["v1"], &["v5"]),
+                suffix("わせる", "う", &["v1"], &["v5"]),
+                suffix("じさせる", "ずる", &["v1"], &["vz"]),
+                suffix("ぜさせる", "ずる", &["v1"], &["vz"]),
+                suffix("させる", "する", &["v1"], &["vs"]),
+                suffix("為せる", "為る", &["v1"], &["vs"]),
+                suffix("こさせる", "くる", &["v1"], &["vk"]),
+                suffix("来させる", "来る", &["v1"], &["vk"]),
+                suffix("來させる", "來る", &["v1"], &["vk"]),
+            ],
+        },
+        Transform {
+            name: "short causative",
+            rules: vec![
+                suffix("さす", "る", &["v5ss"], &["v1"]),
+                suffix("かす", "く", &["v5sp"], &["v5"]),
+                suffix("がす", "ぐ", &["v5sp"], &["v5"]),
+                suffix("さす", "す", &["v5ss"], &["v5"]),
+                suffix("たす", "つ", &["v5sp"], &["v5"]),
+                suffix("なす", "ぬ", &["v5sp"], &["v5"]),
+                suffix("ばす", "ぶ", &["v5sp"], &["v5"]),
+                suffix("ます", "む", &["v5sp"], &["v5"]),
+                suffix("らす", "る", &["v5sp"], &["v5"]),
+                suffix("わす", "う", &["v5sp"], &["v5"]),
+                suffix("じさす", "ずる", &["v5ss"], &["vz"]),
+                suffix("ぜさす", "ずる", &["v5ss"], &["vz"]),
+                suffix("さす", "する", &["v5ss"], &["vs"]),
+                suffix("為す", "為る", &["v5ss"], &["vs"]),
+                suffix("こさす", "くる", &["v5ss"], &["vk"]),
+                suffix("来さす", "来る", &["v5ss"], &["vk"]),
+                suffix("來さす", "來る", &["v5ss"], &["vk"]),
+            ],
+        },
+        Transform {
+            name: "imperative",
+            rules: vec![
+                suffix("ろ", "る", &[], &["v1"]),
+                suffix("よ", "る", &[], &["v1"]),
+                suffix("え", "う", &[], &["v5"]),
+                suffix("け", "く", &[], &["v5"]),
+                suffix("げ", "ぐ", &[], &["v5"]),
+                suffix("せ", "す", &[], &["v5"]),
+                suffix("て", "つ", &[], &["v5"]),
+                suffix("ね", "ぬ", &[], &["v5"]),
+                suffix("べ", "ぶ", &[], &["v5"]),
+                suffix("め", "む", &[], &["v5"]),
+                suffix("れ", "る", &[], &["v5"]),
+                suffix("じろ", "ずる", &[], &["vz"]),
+                suffix("ぜよ", "ずる", &[], &["vz"]),
+                suffix("しろ", "する", &[], &["vs"]),
+                suffix("せよ", "する", &[], &["vs"]),
+                suffix("為ろ", "為る", &[], &["vs"]),
+                suffix("為よ", "為る", &[], &["vs"]),
+                suffix("こい", "くる", &[], &["vk"]),
+                suffix("来い", "来る", &[], &["vk"]),
+                suffix("來い", "來る", &[], &["vk"]),
+            ],
+        },
+        Transform {
+            name: "continuative",
+            rules: vec![
+                suffix("い", "いる", &[], &["v1d"]),
+                suffix("え", "える", &[], &["v1d"]),
+                suffix("き", "きる", &[], &["v1d"]),
+                suffix("ぎ", "ぎる", &[], &["v1d"]),
+                suffix("け", "ける", &[], &["v1d"]),
+                suffix("げ", "げる", &[], &["v1d"]),
+                suffix("じ", "じる", &[], &["v1d"]),
+                suffix("せ", "せる", &[], &["v1d"]),
+                suffix("ぜ", "ぜる", &[], &["v1d"]),
+                suffix("ち", "ちる", &[], &["v1d"]),
+                suffix("て", "てる", &[], &["v1d"]),
+                suffix("で", "でる", &[], &["v1d"]),
+                suffix("に", "にる", &[], &["v1d"]),
+                suffix("ね", "ねる", &[], &["v1d"]),
+                suffix("ひ", "ひる", &[], &["v1d"]),
+                suffix("び", "びる", &[], &["v1d"]),
+                suffix("へ", "へる", &[], &["v1d"]),
+                suffix("べ", "べる", &[], &["v1d"]),
+                suffix("み", "みる", &[], &["v1d"]),
+                suffix("め", "める", &[], &["v1d"]),
+                suffix("り", "りる", &[], &["v1d"]),
+                suffix("れ", "れる", &[], &["v1d"]),
+                suffix("い", "う", &[], &["v5"]),
+                suffix("き", "く", &[], &["v5"]),
+                suffix("ぎ", "ぐ", &[], &["v5"]),
+                suffix("し", "す", &[], &["v5"]),
+                suffix("ち", "つ", &[], &["v5"]),
+                suffix("に", "ぬ", &[], &["v5"]),
+                suffix("び", "ぶ", &[], &["v5"]),
+                suffix("み", "む", &[], &["v5"]),
+                suffix("り", "る", &[], &["v5"]),
+                suffix("き", "くる", &[], &["vk"]),
+                suffix("し", "する", &[], &["vs"]),
+                suffix("来", "来る", &[], &["vk"]),
+                suffix("來", "來る", &[], &["vk"]),
+            ],
+        },
+        Transform {
+            name: "negative",
+            rules: vec![
+                suffix("くない", "い", &["adj-i"], &["adj-i"]),
+                suffix("ない", "る", &["adj-i"], &["v1"]),
+                suffix("かない", "く", &["adj-i"], &["v5"]),
+                suffix("がない", "ぐ", &["adj-i"], &["v5"]),
+                suffix("さない", "す", &["adj-i"], &["v5"]),
+                suffix("たない", "つ", &["adj-i"], &["v5"]),
+                suffix("なない", "ぬ", &["adj-i"], &["v5"]),
+                suffix("ばない", "ぶ", &["adj-i"], &["v5"]),
+                suffix("まない", "む", &["adj-i"], &["v5"]),
+                suffix("らない", "る", &["adj-i"], &["v5"]),
+                suffix("わない", "う", &["adj-i"], &["v5"]),
+                suffix("じない", "ずる", &["adj-i"], &["vz"]),
+                suffix("しない", "する", &["adj-i"], &["vs"]),
+                suffix("為ない", "為る", &["adj-i"], &["vs"]),
+                suffix("こない", "くる", &["adj-i"], &["vk"]),
+                suffix("来ない", "来る", &["adj-i"], &["vk"]),
+                suffix("來ない", "來る", &["adj-i"], &["vk"]),
+                suffix("ません", "ます", &["-ません"], &["-ます"]),
+            ],
+        },
+        Transform {
+            name: "past",
+            rules: vec![
+                suffix("かった", "い", &["-た"], &["adj-i"]),
+                suffix("た", "る", &["-た"], &["v1"]),
+                suffix("いた", "く", &["-た"], &["v5"]),
+                suffix("いだ", "ぐ", &["-た"], &["v5"]),
+                suffix("した", "す", &["-た"], &["v5"]),
+                suffix("った", "う", &["-た"], &["v5"]),
+                suffix("った", "つ", &["-た"], &["v5"]),
+                suffix("った", "る", &["-た"], &["v5"]),
+                suffix("んだ", "ぬ", &["-た"], &["v5"]),
+                suffix("んだ", "ぶ", &["-た"], &["v5"]),
+                suffix("んだ", "む", &["-た"], &["v5"]),
+                suffix("じた", "ずる", &["-た"], &["vz"]),
+                suffix("した", "する", &["-た"], &["vs"]),
+                suffix("為た", "為る", &["-た"], &["vs"]),
+                suffix("きた", "くる", &["-た"], &["vk"]),
+                suffix("来た", "来る", &["-た"], &["vk"]),
+                suffix("來た", "來る", &["-た"], &["vk"]),
+                suffix("いった", "いく", &["-た"], &["v5"]),
+                suffix("行った", "行く", &["-た"], &["v5"]),
+                suffix("ました", "ます", &["-た"], &["-ます"]),
+                suffix("でした", "", &["-た"], &["-ません"]),
+            ],
+        },
+        Transform {
+            name: "-ます",
+            rules: vec![
+                suffix("ます", "る", &["-ます"], &["v1"]),
+                suffix("います", "う", &["-ます"], &["v5d"]),
+                suffix("きます", "く", &["-ます"], &["v5d"]),
+                suffix("ぎます", "ぐ", &["-ます"], &["v5d"]),
+                suffix("します", "す", &["-ます"], &["v5d", "v5s"]),
+                suffix("ちます", "つ", &["-ます"], &["v5d"]),
+                suffix("にます", "ぬ", &["-ます"], &["v5d"]),
+                suffix("びます", "ぶ", &["-ます"], &["v5d"]),
+                suffix("みます", "む", &["-ます"], &["v5d"]),
+                suffix("ります", "る", &["-ます"], &["v5d"]),
+                suffix("じます", "ずる", &["-ます"], &["vz"]),
+                suffix("します", "する", &["-ます"], &["vs"]),
+                suffix("為ます", "為る", &["-ます"], &["vs"]),
+                suffix("きます", "くる", &["-ます"], &["vk"]),
+                suffix("来ます", "来る", &["-ます"], &["vk"]),
+                suffix("來ます", "來る", &["-ます"], &["vk"]),
+                suffix("くあります", "い", &["-ます"], &["adj-i"]),
+            ],
+        },
+        Transform {
+            name: "potential",
+            rules: vec![
+                suffix("れる", "る", &["v1"], &["v1", "v5d"]),
+                suffix("える", "う", &["v1"], &["v5d"]),
+                suffix("ける", "く", &["v1"], &["v5d"]),
+                suffix("げる", "ぐ", &["v1"], &["v5d"]),
+                suffix("せる", "す", &["v1"], &["v5d"]),
+                suffix("てる", "つ", &["v1"], &["v5d"]),
+                suffix("ねる", "ぬ", &["v1"], &["v5d"]),
+                suffix("べる", "ぶ", &["v1"], &["v5d"]),
+                suffix("める", "む", &["v1"], &["v5d"]),
+                suffix("できる", "する", &["v1"], &["vs"]),
+                suffix("出来る", "する", &["v1"], &["vs"]),
+                suffix("これる", "くる", &["v1"], &["vk"]),
+                suffix("来れる", "来る", &["v1"], &["vk"]),
+                suffix("來れる", "來る", &["v1"], &["vk"]),
+            ],
+        },
+        Transform {
+            name: "potential or passive",
+            rules: vec![
+                suffix("られる", "る", &["v1"], &["v1"]),
+                suffix("ざれる", "ずる", &["v1"], &["vz"]),
+                suffix("ぜられる", "ずる", &["v1"], &["vz"]),
+                suffix("せられる", "する", &["v1"], &["vs"]),
+                suffix("為られる", "為る", &["v1"], &["vs"]),
+                suffix("こられる", "くる", &["v1"], &["vk"]),
+                suffix("来られる", "来る", &["v1"], &["vk"]),
+                suffix("來られる", "來る", &["v1"], &["vk"]),
+            ],
+        },
+        Transform {
+            name: "passive",
+            rules: vec![
+                suffix("かれる", "く", &["v1"], &["v5"]),
+                suffix("がれる", "ぐ", &["v1"], &["v5"]),
+                suffix("される", "す", &["v1"], &["v5d", "v5sp"]),
+                suffix("たれる", "つ", &["v1"], &["v5"]),
+                suffix("なれる", "ぬ", &["v1"], &["v5"]),
+                suffix("ばれる", "ぶ", &["v1"], &["v5"]),
+                suffix("まれる", "む", &["v1"], &["v5"]),
+                suffix("われる", "う", &["v1"], &["v5"]),
+                suffix("られる", "る", &["v1"], &["v5"]),
+                suffix("じされる", "ずる", &["v1"], &["vz"]),
+                suffix("ぜされる", "ずる", &["v1"], &["vz"]),
+                suffix("される", "する", &["v1"], &["vs"]),
+                suffix("為れる", "為る", &["v1"], &["vs"]),
+                suffix("こられる", "くる", &["v1"], &["vk"]),
+                suffix("来られる", "来る", &["v1"], &["vk"]),
+                suffix("來られる", "來る", &["v1"], &["vk"]),
+            ],
+        },
+        Transform {
+            name: "volitional",
+            rules: vec![
+                suffix("よう", "る", &[], &["v1"]),
+                suffix("おう", "う", &[], &["v5"]),
+                suffix("こう", "く", &[], &["v5"]),
+                suffix("ごう", "ぐ", &[], &["v5"]),
+                suffix("そう", "す", &[], &["v5"]),
+                suffix("とう", "つ", &[], &["v5"]),
+                suffix("のう", "ぬ", &[], &["v5"]),
+                suffix("ぼう", "ぶ", &[], &["v5"]),
+                suffix("もう", "む", &[], &["v5"]),
+                suffix("ろう", "る", &[], &["v5"]),
+                suffix("じよう", "ずる", &[], &["vz"]),
+                suffix("しよう", "する", &[], &["vs"]),
+                suffix("為よう", "為る", &[], &["vs"]),
+                suffix("こよう", "くる", &[], &["vk"]),
+                suffix("来よう", "来る", &[], &["vk"]),
+                suffix("來よう", "來る", &[], &["vk"]),
+                suffix("ましょう", "ます", &[], &["-ます"]),
+                suffix("かろう", "い", &[], &["adj-i"]),
+            ],
+        },
+        Transform {
+            name: "-ている",
+            rules: vec![
+                suffix("ている", "て", &["v1"], &["-て"]),
+                suffix("でいる", "で", &["v1"], &["-て"]),
+                suffix("ておる", "て", &["v5"], &["-て"]),
+                suffix("でおる", "で", &["v5"], &["-て"]),
+                suffix("てる", "て", &["v1p"], &["-て"]),
+                suffix("でる", "で", &["v1p"], &["-て"]),
+            ],
+        },
+        Transform {
+            name: "-ておく",
+            rules: vec![
+                suffix("ておく", "て", &["v5"], &["-て"]),
+                suffix("でおく", "で", &["v5"], &["-て"]),
+                suffix("とく", "て", &["v5"], &["-て"]),
+                suffix("どく", "で", &["v5"], &["-て"]),
+            ],
+        },
+        Transform {
+            name: "-てある",
+            rules: vec![
+                suffix("てある", "て", &["v5"], &["-て"]),
+                suffix("である", "で", &["v5"], &["-て"]),
+            ],
+        },
+    ]
+}
@@ -0,0 +1,75 @@
+use super::*;
+
+fn deinflector() -> Deinflector {
+    Deinflector::new(japanese::descriptor()).unwrap()
+}
+
+fn find(source: &str, term: &str, rules: &str) -> Option<Vec<String>> {
+    let deinflector = deinflector();
+    deinflector
+        .deinflect(source)
+        .into_iter()
+        .filter(|c| c.term == term && deinflector.candidate_matches_rules(c, rules))
+        .map(|c| c.inflection_rules)
+        .min_by_key(|rules| rules.len())
+}
+
+#[test]
+fn should_include_source() {
+    let candidates = deinflector().deinflect("食べる");
+    let first = candidates.first().unwrap();
+    assert_eq!(first.term, "食べる");
+    assert_eq!(first.conditions, 0);
+    assert!(first.inflection_rules.is_empty());
+}
+
+#[test]
+fn should_deinflect_ichidan_negative_past() {
+    let rules = find("食べなかった", "食べる", "v1").unwrap();
+    assert_eq!(rules, vec!["negative", "past"]);
+}
+
+#[test]
+fn should_deinflect_godan_te() {
+    let rules = find("書いて", "書く", "v5").unwrap();
+    assert_eq!(rules, vec!["-て"]);
+}
+
+#[test]
+fn should_deinflect_irregular_iku() {
+    assert!(find("行った", "行く", "v5").is_some());
+}
+
+#[test]
+fn should_deinflect_adjective() {
+    let rules = find("高くなかった", "高い", "adj-i").unwrap();
+    assert_eq!(rules, vec!["negative", "past"]);
+}
+
+#[test]
+fn should_deinflect_polite_negative_past() {
+    let rules = find("食べませんでした", "食べる", "v1").unwrap();
+    assert_eq!(rules, vec!["-ます", "negative", "past"]);
+}
+
+#[test]
+fn should_deinflect_progressive() {
+    let rules = find("読んでいる", "読む", "v5").unwrap();
+    assert_eq!(rules, vec!["-て", "-ている"]);
+}
+
+#[test]
+fn should_not_match_wrong_rules() {
+    assert!(find("食べなかった", "食べる", "v5").is_none());
+    assert!(find("書いて", "書く", "").is_none());
+}
+
+#[test]
+fn should_resolve_sub_conditions() {
+    let deinflector = deinflector();
+    let v1 = deinflector.get_condition_flags_from_rules("v1");
+    let v5 = deinflector.get_condition_flags_from_rules("v5");
+    assert_ne!(v1, 0);
+    assert_eq!(v1 & v5, 0);
+    assert_eq!(deinflector.get_condition_flags_from_rules("n exp"), 0);
+}
@@ -1,21 +1,141 @@
-use console::style;
-use indicatif::ProgressBar;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
-use std::ffi::OsStr;
-use std::fs::{self, DirEntry};
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_valid::Validate;
+use std::fs::{self, File};
+use std::io::{self, Read};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
-use zip::ZipArchive; // 1. Import Rayon traits
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use zip::ZipArchive;
 
-use anyhow::{Context, bail};
+use anyhow::{Context, anyhow, bail};
 
 use crate::db::Db;
+use crate::db::tables::Dictionary;
+use crate::db::writer::DictionaryWriter;
 use crate::schemas::dictionary_index::DictionaryIndex;
-use crate::schemas::dictionary_tag_bank_v3::DictionaryTagBankV3;
-use crate::schemas::dictionary_term_bank_v3::DictionaryTermBankV3;
+use crate::schemas::dictionary_kanji_bank_v1::DictionaryKanjiBankV1;
+use crate::schemas::dictionary_kanji_bank_v3::DictionaryKanjiBankV3;
+use crate::schemas::dictionary_kanji_meta_bank_v3::DictionaryKanjiMetaBankV3;
+use crate::schemas::dictionary_tag_bank_v3::{DictionaryTagBankV3, DictionaryTagBankV3Row};
+use crate::schemas::dictionary_term_bank_v1::DictionaryTermBankV1;
+use crate::schemas::dictionary_term_bank_v2::DictionaryTermBankV2;
+use crate::schemas::dictionary_term_bank_v3::{DictionaryTermBankV3, DictionaryTermBankV3Row};
+use crate::schemas::dictionary_term_meta_bank_v3::DictionaryTermMetaBankV3;
 use crate::util::config::Config;
-use crate::util::progress::get_progress_bar;
+use crate::util::jmdict::JmdictImporter;
+use crate::util::mdict::MdictImporter;
+use crate::util::progress::{ImportPhase, ImportProgress};
+use crate::util::stardict::StardictImporter;
+use crate::util::validation::{self, ValidationIssue, ValidationMode, ValidationReport};
+
+/// Parsed banks waiting to be inserted. Along with the bank each rayon worker holds while
+/// waiting, this bounds how many banks are in memory at once.
+const BANK_CHANNEL_SIZE: usize = 4;
+
+/// Term rows an [`Importer`] hands to the writer at once.
+pub const TERM_BATCH_SIZE: usize = 5000;
+
+const BANK_PREFIXES: [&str; 5] = [
+    "tag_bank_",
+    "term_bank_",
+    "term_meta_bank_",
+    "kanji_bank_",
+    "kanji_meta_bank_",
+];
+
+/// What to do when a dictionary with the same title is already imported.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OnConflict {
+    /// Refuse to import.
+    #[default]
+    Error,
+    /// Replace the most recent one, keeping its id and settings.
+    Replace,
+    /// Import alongside the existing ones.
+    Add,
+}
+
+/// Source format of a dictionary file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DictFormat {
+    /// Yomitan zip archive.
+    #[default]
+    Yomitan,
+    /// JMdict or JMnedict XML from the EDRDG, plain or gzip compressed.
+    Jmdict,
+    /// StarDict .ifo file, along with the .idx, .dict and .syn files next to it.
+    Stardict,
+    /// MDict .mdx file, along with the .mdd resource files next to it.
+    Mdict,
+}
+
+/// Reads a dictionary format other than Yomitan archives into the same tables.
+/// Methods other than [`Importer::index`] are called from a blocking thread, in the order they
+/// are declared.
+pub trait Importer: Send + 'static {
+    /// Index of the dictionary, known before any entry is read.
+    fn index(&self) -> DictionaryIndex;
+    /// Size of the source, in the unit the importer reports progress in.
+    fn length(&self) -> u64;
+    /// Returns the next batch of up to about [`TERM_BATCH_SIZE`] rows, or `None` once every
+    /// entry has been read.
+    fn next_terms(
+        &mut self,
+        progress: &dyn ImportProgress,
+    ) -> anyhow::Result<Option<Vec<DictionaryTermBankV3Row>>>;
+    /// Tags used by the terms that were read.
+    fn tags(&mut self) -> Vec<DictionaryTagBankV3Row> {
+        vec![]
+    }
+    /// Writes resources such as images into `target`, starting the copy phase if there are any.
+    fn extract_files(
+        &mut self,
+        _target: &Path,
+        _progress: &dyn ImportProgress,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// A parsed bank along with the rows left out of it.
+type ParsedBank<B> = (String, B, Vec<ValidationIssue>);
+
+/// Receives the banks parsed by [`Dict::read_banks`].
+struct BankReader<B> {
+    rx: mpsc::Receiver<anyhow::Result<ParsedBank<B>>>,
+    parser: Option<JoinHandle<()>>,
+    progress: Arc<dyn ImportProgress>,
+}
+
+impl<B> BankReader<B> {
+    /// Returns the next parsed bank, or `None` once every bank has been read.
+    /// Rows that were left out are added to `report`.
+    async fn next(&mut self, report: &mut ValidationReport) -> anyhow::Result<Option<B>> {
+        if self.progress.is_cancelled() {
+            bail!("Import cancelled");
+        }
+        if let Some(bank) = self.rx.recv().await {
+            let (file_name, bank, issues) = bank?;
+            report.issues.extend(issues);
+            self.progress.set_message(&file_name);
+            return Ok(Some(bank));
+        }
+        if let Some(parser) = self.parser.take() {
+            parser.await?;
+        }
+        Ok(None)
+    }
+}
+
+enum ImportTarget {
+    /// Look for a dictionary with the same title.
+    Title(OnConflict),
+    Replace(i32),
+}
+
 pub struct Dict {
     pub config: Arc<Config>,
 }
@@ -25,177 +145,852 @@ impl Dict {
         Self { config }
     }
 
-    // TODO: duplicate check
-    pub async fn parse_dict(&self, dictionary: String, db: Db) -> anyhow::Result<()> {
-        println!("{} Extracting...", style("[1/4]").bold().dim(),);
-        let dict_extract_path = self.extract_dict(dictionary)?;
-
-        println!("{} Parsing...", style("[2/4]").bold().dim());
-        let entries =
-            fs::read_dir(&dict_extract_path).context("Failed to read dictionary directory")?;
-        let entries: Result<Vec<_>, _> = entries.collect();
-        let entries = entries?;
+    /// Imports the dictionary and returns its id. Files are checked according to the mode of
+    /// `report`, which receives every issue found.
+    pub async fn parse_dict(
+        &self,
+        dictionary: PathBuf,
+        db: &Db,
+        on_conflict: OnConflict,
+        report: &mut ValidationReport,
+        progress: Arc<dyn ImportProgress>,
+    ) -> anyhow::Result<i32> {
+        let result = self
+            .import(
+                dictionary,
+                db,
+                ImportTarget::Title(on_conflict),
+                report,
+                &progress,
+            )
+            .await;
+        progress.finish();
+        result
+    }
 
-        //TODO: optimize memory usage
-        let index = self.parse_index(dict_extract_path.join("index.json"))?;
-        let all_terms = Self::parse_term_bank(self, &entries)?;
-        let all_tags = Self::parse_tag_bank(self, &entries)?;
+    /// Imports the dictionary in place of the one with `dictionary_id`, whatever its title.
+    pub async fn replace_dict(
+        &self,
+        dictionary: PathBuf,
+        db: &Db,
+        dictionary_id: i32,
+        report: &mut ValidationReport,
+        progress: Arc<dyn ImportProgress>,
+    ) -> anyhow::Result<i32> {
+        let result = self
+            .import(
+                dictionary,
+                db,
+                ImportTarget::Replace(dictionary_id),
+                report,
+                &progress,
+            )
+            .await;
+        progress.finish();
+        result
+    }
 
-        println!("{} Inserting...", style("[3/4]").bold().dim());
-        let dictionary_id = db
-            .insert_dictionary_data(&index, &all_terms, &all_tags)
-            .await?;
+    async fn import(
+        &self,
+        dictionary: PathBuf,
+        db: &Db,
+        target: ImportTarget,
+        report: &mut ValidationReport,
+        progress: &Arc<dyn ImportProgress>,
+    ) -> anyhow::Result<i32> {
+        let mut archive = open_archive(&dictionary)?;
+        let file_names: Vec<String> = archive.file_names().map(String::from).collect();
+
+        let index = self.parse_index(&mut archive)?;
+        if report.mode != ValidationMode::Normal {
+            report
+                .issues
+                .extend(validation::validate(&index, "index.json", None));
+        }
+        let mut writer = self.begin_writer(db, &index, target).await?;
 
-        println!("{} Copying files...", style("[4/4]").bold().dim());
-        self.copy_dict(&dict_extract_path, dictionary_id)?;
+        // Term and kanji banks are read with the schema of the format, then stored as format 3
+        let format = index.get_format();
+        let bank_count = BANK_PREFIXES
+            .iter()
+            .map(|prefix| self.get_banks(&file_names, prefix).len())
+            .sum::<usize>();
+        for phase in [
+            ImportPhase::Extract,
+            ImportPhase::Parse,
+            ImportPhase::Insert,
+        ] {
+            progress.start(phase, bank_count as u64);
+        }
+        let mut banks = self.read_banks::<DictionaryTagBankV3, DictionaryTagBankV3>(
+            &dictionary,
+            &file_names,
+            "tag_bank_",
+            no_check,
+            report.mode,
+            progress,
+        );
+        while let Some(bank) = banks.next(report).await? {
+            writer.insert_tags(&bank).await?;
+            progress.inc(ImportPhase::Insert, 1);
+        }
+        let mut banks = match format {
+            1 => self.read_banks::<DictionaryTermBankV1, DictionaryTermBankV3>(
+                &dictionary,
+                &file_names,
+                "term_bank_",
+                no_check,
+                report.mode,
+                progress,
+            ),
+            2 => self.read_banks::<DictionaryTermBankV2, DictionaryTermBankV3>(
+                &dictionary,
+                &file_names,
+                "term_bank_",
+                no_check,
+                report.mode,
+                progress,
+            ),
+            _ => self.read_banks::<DictionaryTermBankV3, DictionaryTermBankV3>(
+                &dictionary,
+                &file_names,
+                "term_bank_",
+                no_check,
+                report.mode,
+                progress,
+            ),
+        };
+        while let Some(bank) = banks.next(report).await? {
+            writer.insert_terms(&bank).await?;
+            progress.inc(ImportPhase::Insert, 1);
+        }
+        let mut banks = self.read_banks::<DictionaryTermMetaBankV3, DictionaryTermMetaBankV3>(
+            &dictionary,
+            &file_names,
+            "term_meta_bank_",
+            check_term_meta_bank,
+            report.mode,
+            progress,
+        );
+        while let Some(bank) = banks.next(report).await? {
+            writer.insert_term_metas(&bank).await?;
+            progress.inc(ImportPhase::Insert, 1);
+        }
+        // Format 2 kanji banks already have the same rows as format 3
+        let mut banks = match format {
+            1 => self.read_banks::<DictionaryKanjiBankV1, DictionaryKanjiBankV3>(
+                &dictionary,
+                &file_names,
+                "kanji_bank_",
+                no_check,
+                report.mode,
+                progress,
+            ),
+            _ => self.read_banks::<DictionaryKanjiBankV3, DictionaryKanjiBankV3>(
+                &dictionary,
+                &file_names,
+                "kanji_bank_",
+                no_check,
+                report.mode,
+                progress,
+            ),
+        };
+        while let Some(bank) = banks.next(report).await? {
+            writer.insert_kanji(&bank).await?;
+            progress.inc(ImportPhase::Insert, 1);
+        }
+        let mut banks = self.read_banks::<DictionaryKanjiMetaBankV3, DictionaryKanjiMetaBankV3>(
+            &dictionary,
+            &file_names,
+            "kanji_meta_bank_",
+            no_check,
+            report.mode,
+            progress,
+        );
+        while let Some(bank) = banks.next(report).await? {
+            writer.insert_kanji_metas(&bank).await?;
+            progress.inc(ImportPhase::Insert, 1);
+        }
 
-        Ok(())
-    }
+        if report.mode == ValidationMode::Strict && !report.issues.is_empty() {
+            bail!(
+                "Found {} schema violations in {} rows, nothing was imported",
+                report.issues.len(),
+                report.invalid_rows()
+            );
+        }
 
-    fn parse_index(&self, index: PathBuf) -> anyhow::Result<DictionaryIndex> {
-        let content = fs::read_to_string(&index);
-        let index: DictionaryIndex = serde_json::from_str(&content?)?;
-        Ok(index)
+        progress.start(ImportPhase::Copy, archive.len() as u64);
+        let progress = progress.clone();
+        self.commit_with_files(writer, move |path| {
+            extract_files(&mut archive, path, progress.as_ref())
+        })
+        .await
     }
 
-    fn parse_term_bank(&self, entries: &[DirEntry]) -> anyhow::Result<DictionaryTermBankV3> {
-        let entries = self.get_entries(entries, "term_bank_".to_string())?;
-
-        let pb = get_progress_bar(entries.len() as u64);
-        let pb = Arc::new(pb);
+    /// Writes the files of the dictionary with `extract` on a blocking thread, then commits and
+    /// returns its id. Files are written next to the current ones and swapped in after
+    /// committing, so a failure leaves the previous rows and files untouched.
+    async fn commit_with_files(
+        &self,
+        writer: DictionaryWriter,
+        extract: impl FnOnce(&Path) -> anyhow::Result<()> + Send + 'static,
+    ) -> anyhow::Result<i32> {
+        let dictionary_id = writer.dictionary_id();
+        let dict_target_path = self.config.dir.dict.join(dictionary_id.to_string());
+        let dict_staging_path = self
+            .config
+            .dir
+            .dict
+            .join(format!("{}.partial", dictionary_id));
+        let staging_path = dict_staging_path.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let _ = fs::remove_dir_all(&staging_path);
+            fs::create_dir_all(&staging_path).context("Failed to create dict target directory")?;
+            extract(&staging_path)
+        })
+        .await
+        .unwrap_or_else(|e| Err(e.into()));
+        let result = match result {
+            Ok(()) => writer.commit().await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            let _ = fs::remove_dir_all(&dict_staging_path);
+            return Err(e);
+        }
 
-        let all_terms: anyhow::Result<Vec<DictionaryTermBankV3>> = entries
-            .par_iter()
-            .map(|entry| {
-                let file_name = &entry.file_name().unwrap_or(OsStr::new("never"));
-                pb.set_message(format!("{}", &file_name.to_string_lossy()));
+        if dict_target_path.exists() {
+            fs::remove_dir_all(&dict_target_path).context("Failed to remove previous files")?;
+        }
+        fs::rename(&dict_staging_path, &dict_target_path).context("Failed to move files")?;
 
-                let content = fs::read_to_string(entry)?;
-                let terms: DictionaryTermBankV3 = serde_json::from_str(&content)?;
+        Ok(dictionary_id)
+    }
 
-                pb.inc(1);
-                Ok(terms)
+    /// Removes a dictionary along with its files, for every profile. The files are moved aside
+    /// before the rows are committed and put back if that fails, so neither is removed without
+    /// the other.
+    pub async fn delete_dict(
+        &self,
+        db: &Db,
+        dictionary_id: i32,
+    ) -> anyhow::Result<Option<Dictionary>> {
+        let files_path = self.config.dir.dict.join(dictionary_id.to_string());
+        let deleted_path = self
+            .config
+            .dir
+            .dict
+            .join(format!("{}.deleted", dictionary_id));
+        let _ = fs::remove_dir_all(&deleted_path);
+
+        let result = db
+            .query_delete_dictionary(dictionary_id, || {
+                if files_path.exists() {
+                    fs::rename(&files_path, &deleted_path).context("Failed to move files")?;
+                }
+                Ok(())
             })
-            .collect();
-        let all_terms = all_terms?.into_iter().flatten().collect();
+            .await;
+        if result.is_err() && deleted_path.exists() && !files_path.exists() {
+            let _ = fs::rename(&deleted_path, &files_path);
+        }
+        let dictionary = result?;
 
-        Ok(all_terms)
+        // Left for `dict gc` if it can't be removed now
+        let _ = fs::remove_dir_all(&deleted_path);
+        Ok(dictionary)
+    }
+
+    /// Imports a dictionary in one of the formats read by an [`Importer`] and returns its id.
+    pub async fn parse_source(
+        &self,
+        dictionary: PathBuf,
+        format: DictFormat,
+        db: &Db,
+        on_conflict: OnConflict,
+        progress: Arc<dyn ImportProgress>,
+    ) -> anyhow::Result<i32> {
+        let result = async {
+            let importer =
+                tokio::task::spawn_blocking(move || open_importer(&dictionary, format)).await??;
+            self.import_with(importer, db, ImportTarget::Title(on_conflict), &progress)
+                .await
+        }
+        .await;
+        progress.finish();
+        result
     }
 
-    fn parse_tag_bank(&self, entries: &[DirEntry]) -> anyhow::Result<DictionaryTagBankV3> {
-        let entries = self.get_entries(entries, "tag_bank_".to_string())?;
-        let mut all_tags = Vec::new();
-        for entry in entries {
-            let content = fs::read_to_string(&entry);
-            match content {
-                Ok(content) => {
-                    let mut terms: DictionaryTagBankV3 = serde_json::from_str(&content)?;
-                    all_tags.append(&mut terms);
+    /// Inserts the terms of `importer` as they are read, so the source is never held in memory
+    /// at once unless the importer needs it.
+    async fn import_with(
+        &self,
+        mut importer: Box<dyn Importer>,
+        db: &Db,
+        target: ImportTarget,
+        progress: &Arc<dyn ImportProgress>,
+    ) -> anyhow::Result<i32> {
+        let index = importer.index();
+        let mut writer = self.begin_writer(db, &index, target).await?;
+
+        progress.start(ImportPhase::Parse, importer.length());
+        progress.start(ImportPhase::Insert, 0);
+        progress.set_message(&index.title);
+        let (tx, mut rx) = mpsc::channel(BANK_CHANNEL_SIZE);
+        let parser_progress = progress.clone();
+        let parser = tokio::task::spawn_blocking(move || {
+            while let Some(rows) = importer.next_terms(parser_progress.as_ref())? {
+                // The receiver is gone once inserting failed
+                if tx.blocking_send(rows).is_err() {
+                    bail!("Import stopped");
                 }
-                Err(e) => {
-                    bail!("Failed to read {}: {}", entry.display(), e)
+            }
+            anyhow::Ok(importer)
+        });
+
+        while let Some(rows) = rx.recv().await {
+            if progress.is_cancelled() {
+                bail!("Import cancelled");
+            }
+            writer.insert_terms(&rows).await?;
+            progress.inc(ImportPhase::Insert, 1);
+        }
+        let mut importer = parser.await??;
+        writer.insert_tags(&importer.tags()).await?;
+
+        let progress = progress.clone();
+        self.commit_with_files(writer, move |path| {
+            importer.extract_files(path, progress.as_ref())
+        })
+        .await
+    }
+
+    /// Starts writing the dictionary, as a new one or in place of the one `target` resolves to.
+    async fn begin_writer(
+        &self,
+        db: &Db,
+        index: &DictionaryIndex,
+        target: ImportTarget,
+    ) -> anyhow::Result<DictionaryWriter> {
+        let replace = match target {
+            ImportTarget::Title(on_conflict) => {
+                let existing = db.query_dictionaries_by_title(&index.title).await?.pop();
+                match (existing, on_conflict) {
+                    (Some(existing), OnConflict::Error) => bail!(
+                        "Dictionary {} is already imported with id {} and revision {}, \
+                        use --on-conflict replace or add to import it anyway",
+                        existing.title,
+                        existing.id,
+                        existing.revision
+                    ),
+                    (Some(existing), OnConflict::Replace) => Some(existing.id),
+                    _ => None,
                 }
             }
+            ImportTarget::Replace(dictionary_id) => Some(dictionary_id),
+        };
+        match replace {
+            Some(dictionary_id) => db.begin_dictionary_replace(dictionary_id, index).await,
+            None => db.begin_dictionary(index).await,
         }
-        Ok(all_tags)
     }
 
-    fn get_entries(&self, entries: &[DirEntry], prefix: String) -> anyhow::Result<Vec<PathBuf>> {
-        let entries: Vec<PathBuf> = entries
+    fn parse_index(&self, archive: &mut ZipArchive<File>) -> anyhow::Result<DictionaryIndex> {
+        let index = read_file(archive, "index.json")?;
+        let index: DictionaryIndex = serde_json::from_slice(&index)?;
+        Ok(index)
+    }
+
+    /// Parses the banks starting with `prefix` on the rayon pool in the background, then
+    /// converts their rows from the schema `S` of the dictionary format to the bank `B` stored.
+    /// Banks are handed over one at a time through a bounded channel, so memory use depends on
+    /// the size of a bank rather than the size of the dictionary.
+    fn read_banks<S, B>(
+        &self,
+        dictionary: &Path,
+        file_names: &[String],
+        prefix: &str,
+        check: fn(&S, &str) -> anyhow::Result<()>,
+        mode: ValidationMode,
+        progress: &Arc<dyn ImportProgress>,
+    ) -> BankReader<B>
+    where
+        S: DeserializeOwned + IntoIterator + FromIterator<S::Item> + 'static,
+        S::Item: DeserializeOwned + Validate + Into<B::Item>,
+        B: IntoIterator + FromIterator<B::Item> + Send + 'static,
+    {
+        let banks = self.get_banks(file_names, prefix);
+
+        let dictionary = dictionary.to_path_buf();
+        let (tx, rx) = mpsc::channel(BANK_CHANNEL_SIZE);
+        let parser_progress = progress.clone();
+        let parser = tokio::task::spawn_blocking(move || {
+            // Stops at the first error, or once the receiver is gone because inserting failed.
+            // Each worker opens its own handle since reading an entry needs the archive mutably.
+            let _ = banks.par_iter().try_for_each_init(
+                || open_archive(&dictionary),
+                |archive, file_name| {
+                    let bank = match archive {
+                        Ok(archive) => read_file(archive, file_name)
+                            .and_then(|content| {
+                                parser_progress.inc(ImportPhase::Extract, 1);
+                                parse_bank::<S>(&content, file_name, mode)
+                            })
+                            .and_then(|(rows, issues)| {
+                                check(&rows, file_name)?;
+                                parser_progress.inc(ImportPhase::Parse, 1);
+                                let rows = rows.into_iter().map(Into::into).collect();
+                                Ok((file_name.clone(), rows, issues))
+                            }),
+                        Err(e) => Err(anyhow!("{:#}", e)),
+                    };
+                    let failed = bank.is_err();
+                    match tx.blocking_send(bank) {
+                        Ok(()) if !failed => Ok(()),
+                        _ => Err(()),
+                    }
+                },
+            );
+        });
+
+        BankReader {
+            rx,
+            parser: Some(parser),
+            progress: progress.clone(),
+        }
+    }
+
+    /// Banks are only read from the root of the archive.
+    fn get_banks(&self, file_names: &[String], prefix: &str) -> Vec<String> {
+        let mut banks: Vec<String> = file_names
             .iter()
-            .filter(|e| {
-                let file_name = e.file_name();
-                let file_name = file_name.to_str();
-                if let Some(file_name) = file_name
-                    && file_name.starts_with(&prefix)
-                    && file_name.ends_with(".json")
-                {
-                    return true;
-                }
-                false
-            })
-            .map(|e| e.path())
+            .filter(|name| name.starts_with(prefix) && name.ends_with(".json"))
+            .cloned()
             .collect();
-        Ok(entries)
+        banks.sort();
+        banks
+    }
+}
+
+/// Writes every file other than the index and the banks, such as images and styles.
+fn extract_files(
+    archive: &mut ZipArchive<File>,
+    dict_target_path: &Path,
+    progress: &dyn ImportProgress,
+) -> anyhow::Result<()> {
+    for i in 0..archive.len() {
+        if progress.is_cancelled() {
+            bail!("Import cancelled");
+        }
+        let mut file = archive.by_index(i)?;
+        progress.inc(ImportPhase::Copy, 1);
+        if file.is_dir() || is_data_file(file.name()) {
+            continue;
+        }
+        let Some(relative_path) = file.enclosed_name() else {
+            bail!("Invalid file name in archive: {}", file.name());
+        };
+        progress.set_message(file.name());
+
+        let target_path = dict_target_path.join(relative_path);
+        if let Some(parent) = target_path.parent() {
+            fs::create_dir_all(parent).context("Failed to create target subdirectory")?;
+        }
+        let mut target = File::create(&target_path).context("Failed to create file")?;
+        io::copy(&mut file, &mut target).context("Failed to copy file")?;
     }
 
-    fn extract_dict(&self, dictionary: String) -> anyhow::Result<PathBuf> {
-        let dict_file_name = Path::new(&dictionary)
-            .file_name()
-            .context("Failed to get file name of dict")?;
-        let dict_extract_path = self.config.dir.temp.join(dict_file_name);
-        fs::create_dir_all(&dict_extract_path).context("Failed to create temp dir")?;
+    Ok(())
+}
 
-        let dict_file = fs::File::open(&dictionary)?;
-        let mut dict_archive = ZipArchive::new(&dict_file)?;
+/// Opens `dictionary` with the importer of `format`.
+fn open_importer(dictionary: &Path, format: DictFormat) -> anyhow::Result<Box<dyn Importer>> {
+    let importer: Box<dyn Importer> = match format {
+        DictFormat::Yomitan => bail!("Yomitan archives are imported with Dict::parse_dict"),
+        DictFormat::Jmdict => Box::new(JmdictImporter::open(dictionary)?),
+        DictFormat::Stardict => Box::new(StardictImporter::open(dictionary)?),
+        DictFormat::Mdict => Box::new(MdictImporter::open(dictionary)?),
+    };
+    Ok(importer)
+}
 
-        let pb = ProgressBar::new_spinner();
-        pb.enable_steady_tick(Duration::from_millis(100));
-        pb.set_message(format!("{}", dict_file_name.to_string_lossy()));
-        dict_archive.extract(&dict_extract_path)?;
-        pb.finish_and_clear();
+fn open_archive(dictionary: &Path) -> anyhow::Result<ZipArchive<File>> {
+    let file = File::open(dictionary)
+        .with_context(|| format!("Failed to open {}", dictionary.display()))?;
+    let archive = ZipArchive::new(file)
+        .with_context(|| format!("Failed to read {}", dictionary.display()))?;
+    Ok(archive)
+}
+
+fn read_file(archive: &mut ZipArchive<File>, name: &str) -> anyhow::Result<Vec<u8>> {
+    let mut file = archive
+        .by_name(name)
+        .with_context(|| format!("Missing {} in archive", name))?;
+    let mut content = Vec::with_capacity(file.size() as usize);
+    file.read_to_end(&mut content)
+        .with_context(|| format!("Failed to read {}", name))?;
+    Ok(content)
+}
 
-        Ok(dict_extract_path)
+/// Whether the file is imported into the database rather than copied.
+pub fn is_data_file(name: &str) -> bool {
+    name == "index.json"
+        || (!name.contains('/') && name.contains("_bank_") && name.ends_with(".json"))
+}
+
+/// Parses a bank as a whole, or row by row when validating so invalid rows can be reported.
+fn parse_bank<B>(
+    content: &[u8],
+    name: &str,
+    mode: ValidationMode,
+) -> anyhow::Result<(B, Vec<ValidationIssue>)>
+where
+    B: DeserializeOwned + IntoIterator + FromIterator<B::Item>,
+    B::Item: DeserializeOwned + Validate,
+{
+    let bank = match mode {
+        ValidationMode::Normal => serde_json::from_slice(content)
+            .map(|rows| (rows, vec![]))
+            .map_err(anyhow::Error::from),
+        ValidationMode::Strict | ValidationMode::Lenient => validation::parse_rows(content, name)
+            .map(|(rows, issues)| (rows.into_iter().collect(), issues)),
+    };
+    bank.with_context(|| format!("Failed to parse {}", name))
+}
+
+fn no_check<B>(_: &B, _: &str) -> anyhow::Result<()> {
+    Ok(())
+}
+
+fn check_term_meta_bank(metas: &DictionaryTermMetaBankV3, file_name: &str) -> anyhow::Result<()> {
+    if let Some(meta) = metas.iter().find(|m| !m.2.matches_mode(m.1)) {
+        bail!(
+            "Invalid {} data for {} in {}",
+            meta.1.as_str(),
+            meta.0,
+            file_name
+        );
     }
+    Ok(())
+}
 
-    //TODO: use library to copy
-    pub fn copy_dict(&self, dict_extract_path: &Path, dictionary_id: i32) -> anyhow::Result<()> {
-        let dict_target_path = self.config.dir.dict.join(dictionary_id.to_string());
-        fs::create_dir_all(&dict_target_path).context("Failed to create dict target directory")?;
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::query::{DEFAULT_PROFILE_ID, DictionarySettingsUpdate};
+    use crate::db::tables::{DefinitionTag, DictionaryEntry, KanjiEntry};
+    use crate::test_util::{NoProgress, temp_db, temp_dir, write_zip};
 
-        let entries =
-            fs::read_dir(dict_extract_path).context("Failed to read extract directory")?;
+    const INDEX: &str = r#"{"title":"Test","revision":"1","format":3,"sequenced":true}"#;
+    const TAG_BANK: &str = r#"[["v5","partOfSpeech",0,"Godan verb",0]]"#;
+    const KANJI_BANK: &str = r#"[["日","ニチ","ひ","",["day"],{}]]"#;
 
-        let pb = get_progress_bar(entries.count() as u64);
-        let pb = Arc::new(pb);
+    fn term_bank(rows: &[(&str, &str, i32)]) -> String {
+        let rows: Vec<_> = rows
+            .iter()
+            .map(|(expression, reading, sequence)| {
+                serde_json::json!([
+                    expression,
+                    reading,
+                    "v5",
+                    "v5",
+                    0,
+                    [expression],
+                    sequence,
+                    ""
+                ])
+            })
+            .collect();
+        serde_json::to_string(&rows).unwrap()
+    }
 
-        let entries =
-            fs::read_dir(dict_extract_path).context("Failed to read extract directory")?;
-        for entry in entries {
-            let entry = entry.context("Failed to read directory entry")?;
-            let source_path = entry.path();
-            let file_name = entry.file_name();
-            let target_path = dict_target_path.join(&file_name);
+    async fn import(
+        config: &Arc<Config>,
+        db: &Db,
+        files: &[(&str, &str)],
+        on_conflict: OnConflict,
+    ) -> anyhow::Result<i32> {
+        let path = config
+            .dir
+            .temp
+            .join(format!("{:016x}.zip", rand::random::<u64>()));
+        write_zip(&path, files);
+        let mut report = ValidationReport::new(ValidationMode::Normal);
+        Dict::new(config.clone())
+            .parse_dict(path, db, on_conflict, &mut report, Arc::new(NoProgress))
+            .await
+    }
 
-            pb.set_message(format!("{}", file_name.to_string_lossy()));
+    async fn expressions(db: &Db, dictionary_id: i32) -> Vec<String> {
+        let entries: Vec<DictionaryEntry> = db
+            .query_dictionary_rows("dictionary_entry", dictionary_id, 0, 100)
+            .await
+            .unwrap();
+        entries.into_iter().map(|entry| entry.expression).collect()
+    }
 
-            if source_path.is_dir() {
-                fs::create_dir_all(&target_path).context("Failed to create target subdirectory")?;
-                copy(&source_path, &target_path)?;
-            } else {
-                fs::copy(&source_path, &target_path).context("Failed to copy file")?;
-            }
+    #[tokio::test]
+    async fn should_insert_every_bank() {
+        let (config, db) = temp_db("dict-test").await;
+        let first = term_bank(&[("読む", "よむ", 1), ("書く", "かく", 2)]);
+        let second = term_bank(&[("話す", "はなす", 3)]);
+        let dictionary_id = import(
+            &config,
+            &db,
+            &[
+                ("index.json", INDEX),
+                ("tag_bank_1.json", TAG_BANK),
+                ("term_bank_2.json", &second),
+                ("term_bank_1.json", &first),
+                ("kanji_bank_1.json", KANJI_BANK),
+            ],
+            OnConflict::Error,
+        )
+        .await
+        .unwrap();
+
+        // Banks are inserted in the order of their names
+        assert_eq!(
+            expressions(&db, dictionary_id).await,
+            ["読む", "書く", "話す"]
+        );
+        let tags: Vec<DefinitionTag> = db
+            .query_dictionary_rows("definition_tag", dictionary_id, 0, 100)
+            .await
+            .unwrap();
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].notes, "Godan verb");
+        let kanji: Vec<KanjiEntry> = db
+            .query_dictionary_rows("kanji_entry", dictionary_id, 0, 100)
+            .await
+            .unwrap();
+        assert_eq!(kanji[0].meanings, ["day"]);
+    }
 
-            pb.inc(1);
-        }
+    #[tokio::test]
+    async fn should_leave_nothing_behind_when_a_bank_fails() {
+        let (config, db) = temp_db("dict-test").await;
+        let first = term_bank(&[("読む", "よむ", 1)]);
+        let result = import(
+            &config,
+            &db,
+            &[
+                ("index.json", INDEX),
+                ("term_bank_1.json", &first),
+                ("term_bank_2.json", r#"[["broken"]]"#),
+                ("images/a.png", "png"),
+            ],
+            OnConflict::Error,
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert!(db.query_dictionary_ids().await.unwrap().is_empty());
+        assert_eq!(fs::read_dir(&config.dir.dict).unwrap().count(), 0);
+    }
 
-        pb.finish_and_clear();
+    #[tokio::test]
+    async fn should_refuse_a_duplicate_title_by_default() {
+        let (config, db) = temp_db("dict-test").await;
+        let first = term_bank(&[("読む", "よむ", 1)]);
+        let files = [("index.json", INDEX), ("term_bank_1.json", first.as_str())];
+        import(&config, &db, &files, OnConflict::Error)
+            .await
+            .unwrap();
+
+        assert!(
+            import(&config, &db, &files, OnConflict::Error)
+                .await
+                .is_err()
+        );
+        assert_eq!(db.query_dictionary_ids().await.unwrap().len(), 1);
+    }
 
-        // Clean up temp directory
-        // fs::remove_dir_all(dict_extract_path).context("Failed to clean up temp directory")?;
+    #[tokio::test]
+    async fn should_replace_rows_and_files_keeping_id_and_settings() {
+        let (config, db) = temp_db("dict-test").await;
+        let first = term_bank(&[("読む", "よむ", 1)]);
+        let dictionary_id = import(
+            &config,
+            &db,
+            &[
+                ("index.json", INDEX),
+                ("term_bank_1.json", &first),
+                ("old.png", "png"),
+            ],
+            OnConflict::Error,
+        )
+        .await
+        .unwrap();
+        let update = DictionarySettingsUpdate {
+            priority: Some(5),
+            ..Default::default()
+        };
+        db.query_update_dictionary_settings(dictionary_id, DEFAULT_PROFILE_ID, &update)
+            .await
+            .unwrap();
+
+        let index = r#"{"title":"Test","revision":"2","format":3,"sequenced":true}"#;
+        let second = term_bank(&[("書く", "かく", 1)]);
+        let replaced_id = import(
+            &config,
+            &db,
+            &[
+                ("index.json", index),
+                ("term_bank_1.json", &second),
+                ("new.png", "png"),
+            ],
+            OnConflict::Replace,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(replaced_id, dictionary_id);
+        assert_eq!(expressions(&db, dictionary_id).await, ["書く"]);
+        let dictionary = db
+            .query_dictionary(dictionary_id, DEFAULT_PROFILE_ID)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(dictionary.revision, "2");
+        assert_eq!(dictionary.settings.priority, 5);
+        let files = config.dir.dict.join(dictionary_id.to_string());
+        assert!(!files.join("old.png").exists());
+        assert!(files.join("new.png").exists());
+    }
 
-        Ok(())
+    #[tokio::test]
+    async fn should_keep_the_previous_rows_when_a_replace_fails() {
+        let (config, db) = temp_db("dict-test").await;
+        let first = term_bank(&[("読む", "よむ", 1)]);
+        let dictionary_id = import(
+            &config,
+            &db,
+            &[("index.json", INDEX), ("term_bank_1.json", &first)],
+            OnConflict::Error,
+        )
+        .await
+        .unwrap();
+
+        let result = import(
+            &config,
+            &db,
+            &[
+                ("index.json", INDEX),
+                ("term_bank_1.json", r#"[["broken"]]"#),
+            ],
+            OnConflict::Replace,
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(expressions(&db, dictionary_id).await, ["読む"]);
+    }
+
+    #[tokio::test]
+    async fn should_add_a_duplicate_title_alongside() {
+        let (config, db) = temp_db("dict-test").await;
+        let first = term_bank(&[("読む", "よむ", 1)]);
+        let files = [("index.json", INDEX), ("term_bank_1.json", first.as_str())];
+        let first_id = import(&config, &db, &files, OnConflict::Error)
+            .await
+            .unwrap();
+        let second_id = import(&config, &db, &files, OnConflict::Add).await.unwrap();
+
+        assert_ne!(first_id, second_id);
+        assert_eq!(expressions(&db, first_id).await, ["読む"]);
+        assert_eq!(expressions(&db, second_id).await, ["読む"]);
     }
-}
 
-fn copy(source: &Path, target: &Path) -> anyhow::Result<()> {
-    if !target.exists() {
-        fs::create_dir_all(target).context("Failed to create target directory")?;
+    #[test]
+    fn should_extract_files_other_than_the_banks() {
+        let dir = temp_dir("dict-test");
+        let path = dir.join("dict.zip");
+        write_zip(
+            &path,
+            &[
+                ("index.json", INDEX),
+                ("term_bank_1.json", "[]"),
+                ("styles.css", "css"),
+                ("images/a.png", "png"),
+                // Only banks at the root are imported
+                ("images/term_bank_1.json", "[]"),
+            ],
+        );
+        let target = dir.join("files");
+        fs::create_dir_all(&target).unwrap();
+        extract_files(&mut open_archive(&path).unwrap(), &target, &NoProgress).unwrap();
+
+        assert!(!target.join("index.json").exists());
+        assert!(!target.join("term_bank_1.json").exists());
+        assert_eq!(
+            fs::read_to_string(target.join("styles.css")).unwrap(),
+            "css"
+        );
+        assert_eq!(
+            fs::read_to_string(target.join("images/a.png")).unwrap(),
+            "png"
+        );
+        assert!(target.join("images/term_bank_1.json").exists());
     }
 
-    let entries = fs::read_dir(source).context("Failed to read source directory")?;
-    for entry in entries {
-        let entry = entry.context("Failed to read directory entry")?;
-        let source_path = entry.path();
-        let file_name = entry.file_name();
-        let target_path = target.join(&file_name);
+    #[test]
+    fn should_reject_files_outside_the_target() {
+        let dir = temp_dir("dict-test");
+        let path = dir.join("dict.zip");
+        write_zip(&path, &[("index.json", INDEX), ("../escaped.png", "png")]);
+        let target = dir.join("files");
+        fs::create_dir_all(&target).unwrap();
 
-        if source_path.is_dir() {
-            copy(&source_path, &target_path)?;
-        } else {
-            fs::copy(&source_path, &target_path).context("Failed to copy file")?;
-        }
+        assert!(extract_files(&mut open_archive(&path).unwrap(), &target, &NoProgress).is_err());
+        assert!(!dir.join("escaped.png").exists());
     }
 
-    Ok(())
+    #[tokio::test]
+    async fn should_copy_files_into_the_dictionary_dir() {
+        let (config, db) = temp_db("dict-test").await;
+        let dictionary_id = import(
+            &config,
+            &db,
+            &[("index.json", INDEX), ("images/a.png", "png")],
+            OnConflict::Error,
+        )
+        .await
+        .unwrap();
+
+        let files = config.dir.dict.join(dictionary_id.to_string());
+        assert_eq!(
+            fs::read_to_string(files.join("images/a.png")).unwrap(),
+            "png"
+        );
+        assert!(
+            !config
+                .dir
+                .dict
+                .join(format!("{}.partial", dictionary_id))
+                .exists()
+        );
+    }
+
+    #[tokio::test]
+    async fn should_delete_the_rows_and_files() {
+        let (config, db) = temp_db("dict-test").await;
+        let files = [
+            ("index.json", INDEX),
+            ("term_bank_1.json", &term_bank(&[("読む", "よむ", 1)])),
+            ("images/a.png", "png"),
+        ];
+        let dictionary_id = import(&config, &db, &files, OnConflict::Error)
+            .await
+            .unwrap();
+
+        let dict = Dict::new(config.clone());
+        let dictionary = dict.delete_dict(&db, dictionary_id).await.unwrap().unwrap();
+        assert_eq!(dictionary.title, "Test");
+        assert!(expressions(&db, dictionary_id).await.is_empty());
+        assert!(!config.dir.dict.join(dictionary_id.to_string()).exists());
+        let deleted = dict.delete_dict(&db, dictionary_id).await.unwrap();
+        assert!(deleted.is_none());
+    }
 }
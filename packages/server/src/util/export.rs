@@ -0,0 +1,496 @@
+use anyhow::{Context, bail};
+use serde::Serialize;
+use sqlx::FromRow;
+use sqlx::sqlite::SqliteRow;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+use zip::ZipWriter;
+use zip::write::SimpleFileOptions;
+
+use crate::db::Db;
+use crate::db::query::DEFAULT_PROFILE_ID;
+use crate::db::tables::{
+    DefinitionTag, Dictionary, DictionaryEntry, KanjiEntry, KanjiMeta, TermMeta,
+};
+use crate::db::writer::html_text;
+use crate::schemas::dictionary_index::DictionaryIndex;
+use crate::schemas::dictionary_kanji_bank_v3::DictionaryKanjiBankV3Row;
+use crate::schemas::dictionary_kanji_meta_bank_v3::DictionaryKanjiMetaBankV3Row;
+use crate::schemas::dictionary_tag_bank_v3::DictionaryTagBankV3Row;
+use crate::schemas::dictionary_term_bank_v3::{
+    BreakFields, Definition, DetailedDefinition, DictionaryTermBankV3Row, HtmlDefinition,
+    StructuredContent, StructuredContentDefinition, StructuredContentObject,
+};
+use crate::schemas::dictionary_term_meta_bank_v3::DictionaryTermMetaBankV3Row;
+use crate::util::dict::{Dict, is_data_file};
+
+/// Rows per bank file.
+const BANK_SIZE: u32 = 10000;
+
+/// Serialized banks waiting to be written to the archive.
+const FILE_CHANNEL_SIZE: usize = 4;
+
+/// A table holding the rows of one kind of bank.
+trait BankTable: for<'r> FromRow<'r, SqliteRow> + Send + Unpin {
+    const TABLE: &'static str;
+    const PREFIX: &'static str;
+    type Row: Serialize;
+
+    fn id(&self) -> i32;
+    fn into_row(self) -> anyhow::Result<Self::Row>;
+}
+
+impl BankTable for DictionaryEntry {
+    const TABLE: &'static str = "dictionary_entry";
+    const PREFIX: &'static str = "term_bank_";
+    type Row = DictionaryTermBankV3Row;
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+
+    fn into_row(self) -> anyhow::Result<Self::Row> {
+        Ok(DictionaryTermBankV3Row(
+            self.expression,
+            self.reading,
+            Some(self.definition_tags),
+            self.rules,
+            self.score,
+            self.definitions
+                .into_iter()
+                .map(yomitan_definition)
+                .collect(),
+            self.sequence,
+            self.expression_tags,
+        ))
+    }
+}
+
+impl BankTable for DefinitionTag {
+    const TABLE: &'static str = "definition_tag";
+    const PREFIX: &'static str = "tag_bank_";
+    type Row = DictionaryTagBankV3Row;
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+
+    fn into_row(self) -> anyhow::Result<Self::Row> {
+        Ok(DictionaryTagBankV3Row(
+            self.name,
+            self.category,
+            self.order,
+            self.notes,
+            self.score,
+        ))
+    }
+}
+
+impl BankTable for TermMeta {
+    const TABLE: &'static str = "term_meta";
+    const PREFIX: &'static str = "term_meta_bank_";
+    type Row = DictionaryTermMetaBankV3Row;
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+
+    fn into_row(self) -> anyhow::Result<Self::Row> {
+        let mode = serde_json::from_value(self.mode.into())?;
+        Ok(DictionaryTermMetaBankV3Row(
+            self.expression,
+            mode,
+            self.data,
+        ))
+    }
+}
+
+impl BankTable for KanjiEntry {
+    const TABLE: &'static str = "kanji_entry";
+    const PREFIX: &'static str = "kanji_bank_";
+    type Row = DictionaryKanjiBankV3Row;
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+
+    fn into_row(self) -> anyhow::Result<Self::Row> {
+        Ok(DictionaryKanjiBankV3Row(
+            self.character,
+            self.onyomi,
+            self.kunyomi,
+            self.tags,
+            self.meanings,
+            self.stats,
+        ))
+    }
+}
+
+impl BankTable for KanjiMeta {
+    const TABLE: &'static str = "kanji_meta";
+    const PREFIX: &'static str = "kanji_meta_bank_";
+    type Row = DictionaryKanjiMetaBankV3Row;
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+
+    fn into_row(self) -> anyhow::Result<Self::Row> {
+        let mode = serde_json::from_value(self.mode.into())?;
+        Ok(DictionaryKanjiMetaBankV3Row(
+            self.character,
+            mode,
+            self.data,
+        ))
+    }
+}
+
+type FileSender = mpsc::Sender<(String, Vec<u8>)>;
+
+impl Dict {
+    /// Writes a dictionary to `out` as a Yomitan archive, with its rows split into format 3
+    /// banks and the files it was imported with. Yomitan can't read the HTML definitions of
+    /// StarDict and MDict dictionaries, so they are written as structured content holding
+    /// their lines of text.
+    pub async fn export_dict(&self, db: &Db, dictionary_id: i32, out: &Path) -> anyhow::Result<()> {
+        let Some(dictionary) = db
+            .query_dictionary(dictionary_id, DEFAULT_PROFILE_ID)
+            .await?
+        else {
+            bail!("Dictionary {} not found", dictionary_id);
+        };
+
+        // The archive only shows up at `out` once it is complete
+        let mut partial_name = out.file_name().unwrap_or_default().to_os_string();
+        partial_name.push(".partial");
+        let partial_path = out.with_file_name(partial_name);
+        let file = File::create(&partial_path)
+            .with_context(|| format!("Failed to create {}", partial_path.display()))?;
+
+        let files_path = self.config.dir.dict.join(dictionary_id.to_string());
+        let (tx, mut rx) = mpsc::channel::<(String, Vec<u8>)>(FILE_CHANNEL_SIZE);
+        let writer = tokio::task::spawn_blocking(move || {
+            let mut zip = ZipWriter::new(file);
+            let options = SimpleFileOptions::default();
+            while let Some((name, data)) = rx.blocking_recv() {
+                zip.start_file(name, options)?;
+                zip.write_all(&data)?;
+            }
+            if files_path.exists() {
+                add_files(&mut zip, &files_path, &files_path)?;
+            }
+            zip.finish()?;
+            anyhow::Ok(())
+        });
+
+        let sent = send_banks(db, dictionary, &tx).await;
+        drop(tx);
+        // Sending fails once writing did, so the error of the writer explains both
+        let result = writer.await?.and(sent);
+        match result {
+            Ok(()) => fs::rename(&partial_path, out)
+                .with_context(|| format!("Failed to write {}", out.display())),
+            Err(e) => {
+                let _ = fs::remove_file(&partial_path);
+                Err(e)
+            }
+        }
+    }
+}
+
+async fn send_banks(db: &Db, dictionary: Dictionary, tx: &FileSender) -> anyhow::Result<()> {
+    let dictionary_id = dictionary.id;
+    send_file(tx, "index.json".to_string(), &index(dictionary)).await?;
+    send_bank::<DefinitionTag>(db, dictionary_id, tx).await?;
+    send_bank::<DictionaryEntry>(db, dictionary_id, tx).await?;
+    send_bank::<TermMeta>(db, dictionary_id, tx).await?;
+    send_bank::<KanjiEntry>(db, dictionary_id, tx).await?;
+    send_bank::<KanjiMeta>(db, dictionary_id, tx).await?;
+    Ok(())
+}
+
+/// Sends the rows of `T` as numbered bank files of up to [`BANK_SIZE`] rows.
+async fn send_bank<T: BankTable>(
+    db: &Db,
+    dictionary_id: i32,
+    tx: &FileSender,
+) -> anyhow::Result<()> {
+    let mut after_id = 0;
+    for number in 1.. {
+        let rows: Vec<T> = db
+            .query_dictionary_rows(T::TABLE, dictionary_id, after_id, BANK_SIZE)
+            .await?;
+        let Some(last) = rows.last() else {
+            break;
+        };
+        after_id = last.id();
+        let bank = rows
+            .into_iter()
+            .map(T::into_row)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        send_file(tx, format!("{}{}.json", T::PREFIX, number), &bank).await?;
+    }
+    Ok(())
+}
+
+async fn send_file(tx: &FileSender, name: String, value: &impl Serialize) -> anyhow::Result<()> {
+    let data = serde_json::to_vec(value)?;
+    // The receiver is gone once writing failed
+    if tx.send((name, data)).await.is_err() {
+        bail!("Export stopped");
+    }
+    Ok(())
+}
+
+fn index(dictionary: Dictionary) -> DictionaryIndex {
+    DictionaryIndex {
+        title: dictionary.title,
+        revision: dictionary.revision,
+        minimum_yomitan_version: dictionary.minimum_yomitan_version,
+        sequenced: dictionary.sequenced,
+        // Banks are always written in format 3, whatever the source was in
+        format: Some(3),
+        version: None,
+        author: dictionary.author,
+        is_updatable: dictionary.is_updatable.then_some(true),
+        index_url: dictionary.index_url,
+        download_url: dictionary.download_url,
+        url: dictionary.url,
+        description: dictionary.description,
+        attribution: dictionary.attribution,
+        source_language: dictionary.source_language,
+        target_language: dictionary.target_language,
+        frequency_mode: dictionary.frequency_mode,
+        tag_meta: dictionary.tag_meta,
+    }
+}
+
+/// Replaces an HTML definition with structured content of its text, one line per line.
+fn yomitan_definition(definition: Definition) -> Definition {
+    let Definition::Detailed(detailed) = &definition else {
+        return definition;
+    };
+    let DetailedDefinition::Html(HtmlDefinition { html }) = detailed.as_ref() else {
+        return definition;
+    };
+    let mut content = vec![];
+    for line in html_text(html) {
+        if !content.is_empty() {
+            content.push(StructuredContent::Object(Box::new(
+                StructuredContentObject::Br(BreakFields { data: None }),
+            )));
+        }
+        content.push(StructuredContent::Text(line));
+    }
+    Definition::Detailed(Box::new(DetailedDefinition::StructuredContent(
+        StructuredContentDefinition {
+            content: Box::new(StructuredContent::Array(content)),
+        },
+    )))
+}
+
+/// Adds every file under `dir` to the archive, named by its path relative to `root`. The
+/// index and banks the archive was imported with are left out, they are rebuilt from the rows.
+fn add_files(zip: &mut ZipWriter<File>, root: &Path, dir: &Path) -> anyhow::Result<()> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<_>>()?;
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            add_files(zip, root, &path)?;
+            continue;
+        }
+        let name = path
+            .strip_prefix(root)?
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if is_data_file(&name) {
+            continue;
+        }
+        zip.start_file(name, SimpleFileOptions::default())?;
+        let mut file =
+            File::open(&path).with_context(|| format!("Failed to open {}", path.display()))?;
+        io::copy(&mut file, zip).with_context(|| format!("Failed to add {}", path.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::{NoProgress, temp_db, write_zip};
+    use crate::util::dict::{DictFormat, OnConflict};
+    use crate::util::validation::{ValidationMode, ValidationReport};
+    use chrono::Utc;
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use zip::ZipArchive;
+
+    const INDEX: &str = r#"{"title":"Test","revision":"1","format":3,"sequenced":true}"#;
+    const TERM_BANK: &str = r#"[["読む","よむ","","v5",0,["to read"],1,""]]"#;
+
+    /// Names of the files in an archive, checking each shows up once.
+    fn file_names(path: &Path) -> Vec<String> {
+        let archive = ZipArchive::new(File::open(path).unwrap()).unwrap();
+        let names: Vec<String> = archive.file_names().map(str::to_string).collect();
+        let unique: HashSet<&String> = names.iter().collect();
+        assert_eq!(unique.len(), names.len());
+        names
+    }
+
+    async fn entries(db: &Db, dictionary_id: i32) -> Vec<DictionaryEntry> {
+        db.query_dictionary_rows("dictionary_entry", dictionary_id, 0, 100)
+            .await
+            .unwrap()
+    }
+
+    /// Term bank rows of a dictionary as JSON, to compare dictionaries with.
+    async fn rows(db: &Db, dictionary_id: i32) -> serde_json::Value {
+        let rows = entries(db, dictionary_id)
+            .await
+            .into_iter()
+            .map(|entry| entry.into_row().unwrap())
+            .collect::<Vec<_>>();
+        serde_json::to_value(rows).unwrap()
+    }
+
+    #[tokio::test]
+    async fn should_export_an_importable_archive() {
+        let (config, db) = temp_db("export-test").await;
+        let archive = config.dir.temp.join("test.zip");
+        write_zip(
+            &archive,
+            &[
+                ("index.json", INDEX),
+                ("term_bank_1.json", TERM_BANK),
+                ("images/a.png", "png"),
+            ],
+        );
+        let dict = Dict::new(config.clone());
+        let mut report = ValidationReport::new(ValidationMode::Normal);
+        let dictionary_id = dict
+            .parse_dict(
+                archive,
+                &db,
+                OnConflict::Error,
+                &mut report,
+                Arc::new(NoProgress),
+            )
+            .await
+            .unwrap();
+        // Copied along with the media files by earlier versions
+        let files_path = config.dir.dict.join(dictionary_id.to_string());
+        fs::write(files_path.join("index.json"), INDEX).unwrap();
+        fs::write(files_path.join("term_bank_1.json"), TERM_BANK).unwrap();
+
+        let out = config.dir.temp.join("export.zip");
+        dict.export_dict(&db, dictionary_id, &out).await.unwrap();
+        let mut names = file_names(&out);
+        names.sort();
+        assert_eq!(names, ["images/a.png", "index.json", "term_bank_1.json"]);
+
+        let mut report = ValidationReport::new(ValidationMode::Normal);
+        let copy_id = dict
+            .parse_dict(out, &db, OnConflict::Add, &mut report, Arc::new(NoProgress))
+            .await
+            .unwrap();
+        assert_eq!(rows(&db, copy_id).await, rows(&db, dictionary_id).await);
+    }
+
+    #[tokio::test]
+    async fn should_export_html_definitions_as_structured_content() {
+        let (config, db) = temp_db("export-test").await;
+        let dict = Dict::new(config.clone());
+        let dictionary_id = dict
+            .parse_source(
+                PathBuf::from("src/fixtures/stardict/test.ifo"),
+                DictFormat::Stardict,
+                &db,
+                OnConflict::Error,
+                Arc::new(NoProgress),
+            )
+            .await
+            .unwrap();
+
+        let out = config.dir.temp.join("export.zip");
+        dict.export_dict(&db, dictionary_id, &out).await.unwrap();
+        // HTML definitions are refused in term banks, so this only imports once converted
+        let mut report = ValidationReport::new(ValidationMode::Normal);
+        let copy_id = dict
+            .parse_dict(out, &db, OnConflict::Add, &mut report, Arc::new(NoProgress))
+            .await
+            .unwrap();
+        let copy = db
+            .query_dictionary(copy_id, DEFAULT_PROFILE_ID)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(copy.title, "StarDict Test");
+        for (entry, copy) in entries(&db, dictionary_id)
+            .await
+            .into_iter()
+            .zip(entries(&db, copy_id).await)
+        {
+            let Definition::Detailed(definition) = &copy.definitions[0] else {
+                panic!("expected a detailed definition");
+            };
+            assert!(matches!(
+                definition.as_ref(),
+                DetailedDefinition::StructuredContent(_)
+            ));
+            let glossary = |definitions| {
+                crate::db::writer::glossary(&serde_json::to_value(definitions).unwrap())
+            };
+            assert_eq!(glossary(&copy.definitions), glossary(&entry.definitions));
+        }
+    }
+
+    #[test]
+    fn should_rebuild_term_rows() {
+        let data = fs::read_to_string("src/fixtures/term_bank.json").unwrap();
+        let rows: Vec<serde_json::Value> = serde_json::from_str(&data).unwrap();
+        // The fixture also holds rows that are meant to be rejected
+        let mut bank: Vec<DictionaryTermBankV3Row> = rows
+            .into_iter()
+            .filter_map(|row| serde_json::from_value(row).ok())
+            .collect();
+        assert!(!bank.is_empty());
+        assert!(bank.iter().any(|row| row.2.is_none()));
+        // Null tags are stored as no tags
+        for row in &mut bank {
+            row.2.get_or_insert_default();
+        }
+        let expected = serde_json::to_value(&bank).unwrap();
+
+        let rows: Vec<DictionaryTermBankV3Row> = bank
+            .into_iter()
+            .enumerate()
+            .map(|(id, row)| {
+                // Stored the way the writer does it
+                let definitions = serde_json::to_string(&row.5).unwrap();
+                let entry = DictionaryEntry {
+                    id: id as i32,
+                    created_at: Utc::now(),
+                    updated_at: Utc::now(),
+                    dictionary_id: 1,
+                    expression: row.0,
+                    reading: row.1,
+                    definitions: serde_json::from_str(&definitions).unwrap(),
+                    rules: row.3,
+                    score: row.4,
+                    sequence: row.6,
+                    definition_tags: row.2.unwrap_or_default(),
+                    expression_tags: row.7,
+                };
+                entry.into_row().unwrap()
+            })
+            .collect();
+        assert_eq!(serde_json::to_value(&rows).unwrap(), expected);
+    }
+}
@@ -0,0 +1,244 @@
+use anyhow::Context;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::db::Db;
+use crate::util::dict::Dict;
+
+/// Temp files and unfinished dictionary directories untouched for this long are left over from
+/// a run that stopped, anything newer may still be in use.
+const STALE_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// What [`Dict::gc`] removed and how much space that freed.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GcReport {
+    /// Files of dictionaries that are no longer imported.
+    pub orphan_dirs: Vec<PathBuf>,
+    /// Unfinished imports and deletes, uploads, exports and downloads.
+    pub stale_files: Vec<PathBuf>,
+    /// Bytes of the files and directories removed.
+    pub files_reclaimed: u64,
+    /// Rows removed from each table because their dictionary or profile is gone.
+    pub orphan_rows: Vec<OrphanRows>,
+    pub database_size_before: u64,
+    pub database_size_after: u64,
+    /// Bytes freed on disk, by the files and by compacting the database.
+    pub reclaimed: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrphanRows {
+    pub table: String,
+    pub count: u64,
+}
+
+impl Dict {
+    /// Removes what deletes and failed imports left behind: directories of dictionaries that
+    /// are gone, stale temp files and rows without a dictionary. The database is compacted and
+    /// its statistics refreshed afterwards.
+    pub async fn gc(&self, db: &Db) -> anyhow::Result<GcReport> {
+        let mut report = GcReport::default();
+        let now = SystemTime::now();
+
+        // Listed before the ids are read, an import that commits in between would otherwise
+        // have its files taken for those of a deleted dictionary
+        let paths = read_dir(&self.config.dir.dict)?;
+        let dictionary_ids: HashSet<i32> = db.query_dictionary_ids().await?.into_iter().collect();
+        for path in paths {
+            let id = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.parse::<i32>().ok());
+            match id {
+                // Files are only moved in once the rows are committed, so these aren't in use
+                Some(id) if !dictionary_ids.contains(&id) => {
+                    report.files_reclaimed += remove(&path)?;
+                    report.orphan_dirs.push(path);
+                }
+                Some(_) => {}
+                // Staged files of an import, or files of a delete that couldn't remove them
+                None if is_stale(&path, now) => {
+                    report.files_reclaimed += remove(&path)?;
+                    report.stale_files.push(path);
+                }
+                None => {}
+            }
+        }
+        for path in read_dir(&self.config.dir.temp)? {
+            if is_stale(&path, now) {
+                report.files_reclaimed += remove(&path)?;
+                report.stale_files.push(path);
+            }
+        }
+
+        report.orphan_rows = db
+            .query_delete_orphan_rows()
+            .await?
+            .into_iter()
+            .map(|(table, count)| OrphanRows {
+                table: table.to_string(),
+                count,
+            })
+            .collect();
+
+        report.database_size_before = db.query_database_size().await?;
+        db.vacuum().await?;
+        db.analyze().await?;
+        report.database_size_after = db.query_database_size().await?;
+        report.reclaimed = report.files_reclaimed
+            + report
+                .database_size_before
+                .saturating_sub(report.database_size_after);
+
+        Ok(report)
+    }
+}
+
+fn read_dir(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<_>>()?;
+    paths.sort();
+    Ok(paths)
+}
+
+fn is_stale(path: &Path, now: SystemTime) -> bool {
+    fs::symlink_metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| now.duration_since(modified).ok())
+        .is_some_and(|age| age >= STALE_AGE)
+}
+
+/// Removes a file or directory and returns the bytes it held.
+fn remove(path: &Path) -> anyhow::Result<u64> {
+    let size = size(path);
+    let is_dir = fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_dir());
+    let result = if is_dir {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    };
+    result.with_context(|| format!("Failed to remove {}", path.display()))?;
+    Ok(size)
+}
+
+fn size(path: &Path) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| size(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::{NoProgress, temp_db, temp_dir, write_zip};
+    use crate::util::dict::OnConflict;
+    use crate::util::progress::ImportProgress;
+    use crate::util::validation::{ValidationMode, ValidationReport};
+    use sqlx::Connection;
+    use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection};
+    use std::sync::Arc;
+
+    #[test]
+    fn should_only_find_old_files_stale() {
+        let dir = temp_dir("gc-test");
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("nested/file"), [0; 10]).unwrap();
+        fs::write(dir.join("file"), [0; 5]).unwrap();
+
+        let now = SystemTime::now();
+        assert!(!is_stale(&dir, now));
+        assert!(is_stale(&dir, now + STALE_AGE));
+        assert_eq!(size(&dir), 15);
+        assert_eq!(remove(&dir).unwrap(), 15);
+        assert!(!dir.exists());
+    }
+
+    #[tokio::test]
+    async fn should_remove_what_deleted_dictionaries_left() {
+        let (config, db) = temp_db("gc-test").await;
+        let dict = Dict::new(config.clone());
+        let path = config.dir.temp.join("dictionary.zip");
+        write_zip(
+            &path,
+            &[
+                (
+                    "index.json",
+                    r#"{"title":"Test","revision":"1","format":3}"#,
+                ),
+                (
+                    "term_bank_1.json",
+                    r#"[["読む","よむ","","",0,["read"],0,""]]"#,
+                ),
+                ("images/a.png", "png"),
+            ],
+        );
+        let mut report = ValidationReport::new(ValidationMode::Normal);
+        let progress: Arc<dyn ImportProgress> = Arc::new(NoProgress);
+        let dictionary_id = dict
+            .parse_dict(path, &db, OnConflict::Error, &mut report, progress)
+            .await
+            .unwrap();
+
+        let orphan_dir = config.dir.dict.join((dictionary_id + 1).to_string());
+        fs::create_dir_all(orphan_dir.join("images")).unwrap();
+        fs::write(orphan_dir.join("images/b.png"), [0; 7]).unwrap();
+        // Rows written with foreign keys off outlive their dictionary
+        let options = SqliteConnectOptions::new()
+            .filename(&config.file.db)
+            .foreign_keys(false);
+        let mut connection = SqliteConnection::connect_with(&options).await.unwrap();
+        sqlx::query(
+            r#"--sql
+            INSERT INTO term_meta (dictionary_id, expression, mode, data)
+            VALUES (?, '読む', 'freq', '1')
+            "#,
+        )
+        .bind(dictionary_id + 1)
+        .execute(&mut connection)
+        .await
+        .unwrap();
+        connection.close().await.unwrap();
+
+        let report = dict.gc(&db).await.unwrap();
+        assert_eq!(report.orphan_dirs, std::slice::from_ref(&orphan_dir));
+        assert!(report.stale_files.is_empty());
+        assert_eq!(report.files_reclaimed, 7);
+        let orphan_rows: Vec<(&str, u64)> = report
+            .orphan_rows
+            .iter()
+            .filter(|rows| rows.count > 0)
+            .map(|rows| (rows.table.as_str(), rows.count))
+            .collect();
+        assert_eq!(orphan_rows, [("term_meta", 1)]);
+        assert!(!orphan_dir.exists());
+        assert!(
+            config
+                .dir
+                .dict
+                .join(dictionary_id.to_string())
+                .join("images/a.png")
+                .exists()
+        );
+        assert_eq!(db.query_dictionary_ids().await.unwrap(), [dictionary_id]);
+    }
+}
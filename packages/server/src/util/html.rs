@@ -0,0 +1,744 @@
+use crate::db::query::FulltextResult;
+use crate::db::tables::DictionaryEntry;
+use crate::schemas::dictionary_term_bank_v3::{
+    Definition, DetailedDefinition, HtmlDefinition, ImageDefinition, ImageFields, LinkFields,
+    NumberOrString, StructuredContent, StructuredContentData, StructuredContentObject,
+    StructuredContentStyle, TextDecorationLine,
+};
+use crate::util::merge::MergedEntryMatch;
+use crate::util::translator::{DictionaryEntryMatch, ScanResult, SearchResult};
+use ammonia::{Builder, UrlRelative};
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+
+/// How the definitions of lookup results are returned.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum DefinitionFormat {
+    /// As they were imported.
+    #[default]
+    Json,
+    /// Rendered as sanitized HTML, see [`render_definitions`].
+    Html,
+}
+
+/// Tags kept from HTML definitions, the others are replaced by their content.
+const ALLOWED_TAGS: [&str; 44] = [
+    "a",
+    "b",
+    "blockquote",
+    "br",
+    "code",
+    "dd",
+    "del",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "img",
+    "ins",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "rp",
+    "rt",
+    "ruby",
+    "s",
+    "small",
+    "span",
+    "strong",
+    "sub",
+    "summary",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "tr",
+    "ul",
+];
+
+/// Tags removed from HTML definitions along with their content.
+const DROPPED_TAGS: [&str; 14] = [
+    "script", "style", "iframe", "object", "embed", "template", "noscript", "textarea", "title",
+    "head", "svg", "math", "select", "button",
+];
+
+/// CSS properties kept from structured content styles and the `style` attribute of HTML
+/// definitions.
+const ALLOWED_PROPERTIES: [&str; 39] = [
+    "background",
+    "background-color",
+    "border",
+    "border-bottom",
+    "border-color",
+    "border-left",
+    "border-radius",
+    "border-right",
+    "border-style",
+    "border-top",
+    "border-width",
+    "color",
+    "font-size",
+    "font-style",
+    "font-weight",
+    "height",
+    "image-rendering",
+    "list-style-type",
+    "margin",
+    "margin-bottom",
+    "margin-left",
+    "margin-right",
+    "margin-top",
+    "padding",
+    "padding-bottom",
+    "padding-left",
+    "padding-right",
+    "padding-top",
+    "text-align",
+    "text-decoration",
+    "text-decoration-color",
+    "text-decoration-line",
+    "text-decoration-style",
+    "text-emphasis",
+    "text-shadow",
+    "vertical-align",
+    "white-space",
+    "width",
+    "word-break",
+];
+
+/// Results with definitions that can be rendered as HTML.
+pub trait RenderHtml {
+    fn render_html(&mut self);
+}
+
+impl RenderHtml for DictionaryEntry {
+    fn render_html(&mut self) {
+        render_definitions(&mut self.definitions, self.dictionary_id);
+    }
+}
+
+impl RenderHtml for DictionaryEntryMatch {
+    fn render_html(&mut self) {
+        self.entry.render_html();
+    }
+}
+
+impl RenderHtml for MergedEntryMatch {
+    fn render_html(&mut self) {
+        for group in &mut self.definitions {
+            render_definitions(&mut group.definitions, group.dictionary_id);
+        }
+    }
+}
+
+impl RenderHtml for SearchResult {
+    fn render_html(&mut self) {
+        match self {
+            SearchResult::Flat(matches) => matches.render_html(),
+            SearchResult::Merged(merged) => merged.render_html(),
+        }
+    }
+}
+
+impl RenderHtml for ScanResult {
+    fn render_html(&mut self) {
+        for group in &mut self.groups {
+            group.entries.render_html();
+        }
+    }
+}
+
+impl RenderHtml for FulltextResult {
+    fn render_html(&mut self) {
+        self.entries.render_html();
+    }
+}
+
+impl<T: RenderHtml> RenderHtml for Vec<T> {
+    fn render_html(&mut self) {
+        for item in self {
+            item.render_html();
+        }
+    }
+}
+
+impl<T: RenderHtml> RenderHtml for BTreeMap<String, T> {
+    fn render_html(&mut self) {
+        for item in self.values_mut() {
+            item.render_html();
+        }
+    }
+}
+
+/// Replaces each definition with an HTML definition of it. Deinflections are kept as they are.
+/// Images point to the media of `dictionary_id`, links other than `http:`, `https:` and `?`
+/// search links lose their `href`, and HTML definitions only keep harmless tags and attributes.
+pub fn render_definitions(definitions: &mut [Definition], dictionary_id: i32) {
+    for definition in definitions {
+        let html = match definition {
+            Definition::Deinflection(_) => continue,
+            Definition::Text(text) => escape(text),
+            Definition::Detailed(detailed) => match detailed.as_ref() {
+                DetailedDefinition::Text(text) => escape(&text.text),
+                DetailedDefinition::Image(image) => {
+                    let mut out = String::new();
+                    push_image(&mut out, &Image::from(image), None, dictionary_id);
+                    out
+                }
+                DetailedDefinition::StructuredContent(structured) => {
+                    let mut out = String::new();
+                    push_content(&mut out, &structured.content, dictionary_id);
+                    out
+                }
+                DetailedDefinition::Html(html) => sanitize_html(&html.html, dictionary_id),
+            },
+        };
+        *definition =
+            Definition::Detailed(Box::new(DetailedDefinition::Html(HtmlDefinition { html })));
+    }
+}
+
+fn push_content(out: &mut String, content: &StructuredContent, dictionary_id: i32) {
+    match content {
+        StructuredContent::Text(text) => out.push_str(&escape(text)),
+        StructuredContent::Array(items) => {
+            for item in items {
+                push_content(out, item, dictionary_id);
+            }
+        }
+        StructuredContent::Object(object) => push_object(out, object, dictionary_id),
+    }
+}
+
+fn push_object(out: &mut String, object: &StructuredContentObject, dictionary_id: i32) {
+    use StructuredContentObject as O;
+
+    let tag = object.tag();
+    let content = match object {
+        O::Br(fields) => {
+            out.push_str("<br");
+            push_data(out, fields.data.as_ref());
+            out.push('>');
+            return;
+        }
+        O::Img(fields) => {
+            push_image(
+                out,
+                &Image::from(fields),
+                fields.data.as_deref(),
+                dictionary_id,
+            );
+            return;
+        }
+        O::A(fields) => {
+            push_link(out, fields, dictionary_id);
+            return;
+        }
+        O::Ruby(fields)
+        | O::Rt(fields)
+        | O::Rp(fields)
+        | O::Table(fields)
+        | O::Thead(fields)
+        | O::Tbody(fields)
+        | O::Tfoot(fields)
+        | O::Tr(fields) => {
+            out.push('<');
+            out.push_str(tag);
+            push_data(out, fields.data.as_ref());
+            push_attribute(out, "lang", fields.lang.as_deref());
+            &fields.content
+        }
+        O::Td(fields) | O::Th(fields) => {
+            out.push('<');
+            out.push_str(tag);
+            push_data(out, fields.data.as_ref());
+            let col_span = fields.col_span.map(|n| n.to_string());
+            let row_span = fields.row_span.map(|n| n.to_string());
+            push_attribute(out, "colspan", col_span.as_deref());
+            push_attribute(out, "rowspan", row_span.as_deref());
+            push_attribute(
+                out,
+                "style",
+                fields.style.as_ref().and_then(style).as_deref(),
+            );
+            push_attribute(out, "lang", fields.lang.as_deref());
+            &fields.content
+        }
+        O::Span(fields)
+        | O::Div(fields)
+        | O::Ol(fields)
+        | O::Ul(fields)
+        | O::Li(fields)
+        | O::Details(fields)
+        | O::Summary(fields) => {
+            out.push('<');
+            out.push_str(tag);
+            push_data(out, fields.data.as_ref());
+            push_attribute(
+                out,
+                "style",
+                fields.style.as_ref().and_then(style).as_deref(),
+            );
+            push_attribute(out, "title", fields.title.as_deref());
+            if matches!(object, O::Details(_)) && fields.open == Some(true) {
+                out.push_str(" open");
+            }
+            push_attribute(out, "lang", fields.lang.as_deref());
+            &fields.content
+        }
+    };
+    out.push('>');
+    if let Some(content) = content {
+        push_content(out, content, dictionary_id);
+    }
+    let _ = write!(out, "</{}>", tag);
+}
+
+fn push_link(out: &mut String, link: &LinkFields, dictionary_id: i32) {
+    out.push_str("<a");
+    if is_allowed_href(&link.href) {
+        push_attribute(out, "href", Some(&link.href));
+        if !link.href.starts_with('?') {
+            out.push_str(r#" rel="noopener noreferrer""#);
+        }
+    }
+    push_attribute(out, "lang", link.lang.as_deref());
+    out.push('>');
+    if let Some(content) = &link.content {
+        push_content(out, content, dictionary_id);
+    }
+    out.push_str("</a>");
+}
+
+/// Web links and `?` search links, the forms the term bank schema accepts.
+fn is_allowed_href(href: &str) -> bool {
+    let href = href.trim_start().to_ascii_lowercase();
+    href.starts_with("http:") || href.starts_with("https:") || href.starts_with('?')
+}
+
+/// The fields of image definitions and image tags that are rendered.
+struct Image<'a> {
+    path: &'a str,
+    width: Option<f32>,
+    height: Option<f32>,
+    title: Option<&'a str>,
+    alt: Option<&'a str>,
+    pixelated: bool,
+    image_rendering: &'a str,
+    vertical_align: Option<&'a str>,
+    border: Option<&'a str>,
+    border_radius: Option<&'a str>,
+    size_units: Option<&'a str>,
+}
+
+impl<'a> From<&'a ImageDefinition> for Image<'a> {
+    fn from(image: &'a ImageDefinition) -> Self {
+        Self {
+            path: &image.path,
+            width: image.width.map(|n| n as f32),
+            height: image.height.map(|n| n as f32),
+            title: image.title.as_deref(),
+            alt: image.alt.as_deref(),
+            pixelated: image.pixelated,
+            image_rendering: &image.image_rendering,
+            vertical_align: None,
+            border: None,
+            border_radius: None,
+            size_units: None,
+        }
+    }
+}
+
+impl<'a> From<&'a ImageFields> for Image<'a> {
+    fn from(image: &'a ImageFields) -> Self {
+        Self {
+            path: &image.path,
+            width: image.width,
+            height: image.height,
+            title: image.title.as_deref(),
+            alt: image.alt.as_deref(),
+            pixelated: image.pixelated,
+            image_rendering: &image.image_rendering,
+            vertical_align: image.vertical_align.as_deref(),
+            border: image.border.as_deref(),
+            border_radius: image.border_radius.as_deref(),
+            size_units: image.size_units.as_deref(),
+        }
+    }
+}
+
+/// Images with a path outside of the dictionary are left out.
+fn push_image(
+    out: &mut String,
+    image: &Image,
+    data: Option<&StructuredContentData>,
+    dictionary_id: i32,
+) {
+    let Some(src) = media_url(image.path, dictionary_id) else {
+        return;
+    };
+    out.push_str("<img");
+    push_data(out, data);
+    push_attribute(out, "src", Some(&src));
+    push_attribute(out, "alt", Some(image.alt.unwrap_or_default()));
+    push_attribute(out, "title", image.title);
+
+    let mut css = Css::default();
+    if image.size_units == Some("em") {
+        css.push("width", image.width.map(|n| format!("{}em", n)).as_deref());
+        css.push(
+            "height",
+            image.height.map(|n| format!("{}em", n)).as_deref(),
+        );
+    } else {
+        let width = image.width.map(|n| n.to_string());
+        let height = image.height.map(|n| n.to_string());
+        push_attribute(out, "width", width.as_deref());
+        push_attribute(out, "height", height.as_deref());
+    }
+    let rendering = match image.image_rendering {
+        "auto" if image.pixelated => Some("pixelated"),
+        "auto" => None,
+        rendering => Some(rendering),
+    };
+    css.push("image-rendering", rendering);
+    css.push("vertical-align", image.vertical_align);
+    css.push("border", image.border);
+    css.push("border-radius", image.border_radius);
+    push_attribute(out, "style", css.finish().as_deref());
+    out.push('>');
+}
+
+/// URL of a file of the dictionary for the media route. The path is a single segment there,
+/// so slashes are encoded too.
+fn media_url(path: &str, dictionary_id: i32) -> Option<String> {
+    let path = path.trim();
+    let is_outside = path.is_empty()
+        || path.starts_with(['/', '\\'])
+        || path.contains(':')
+        || path.split(['/', '\\']).any(|segment| segment == "..");
+    if is_outside {
+        return None;
+    }
+    let mut url = format!("/media/{}/", dictionary_id);
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            url.push(byte as char);
+        } else {
+            let _ = write!(url, "%{:02X}", byte);
+        }
+    }
+    Some(url)
+}
+
+/// Inline CSS of a style. Values the schema defaults to are left out, so they don't override
+/// the styles of the parent element.
+fn style(style: &StructuredContentStyle) -> Option<String> {
+    let not = |value: &'static str| move |v: &&str| *v != value;
+    let margin = |value: &NumberOrString| match value {
+        NumberOrString::Number(n) if *n == 0.0 => None,
+        NumberOrString::Number(n) => Some(format!("{}em", n)),
+        NumberOrString::String(s) => Some(s.clone()),
+    };
+    let decoration = match &style.text_decoration_line {
+        TextDecorationLine::Single(line) => line.clone(),
+        TextDecorationLine::Multiple(lines) => lines.join(" "),
+    };
+
+    let mut css = Css::default();
+    css.push(
+        "font-style",
+        Some(style.font_style.as_str()).filter(not("normal")),
+    );
+    css.push(
+        "font-weight",
+        Some(style.font_weight.as_str()).filter(not("normal")),
+    );
+    css.push(
+        "font-size",
+        Some(style.font_size.as_str()).filter(not("medium")),
+    );
+    css.push("color", style.color.as_deref());
+    css.push("background", style.background.as_deref());
+    css.push("background-color", style.background_color.as_deref());
+    css.push(
+        "text-decoration-line",
+        Some(decoration.as_str()).filter(not("none")),
+    );
+    css.push(
+        "text-decoration-style",
+        Some(style.text_decoration_style.as_str()).filter(not("solid")),
+    );
+    css.push(
+        "text-decoration-color",
+        style.text_decoration_color.as_deref(),
+    );
+    css.push("border-color", style.border_color.as_deref());
+    css.push("border-style", style.border_style.as_deref());
+    css.push("border-radius", style.border_radius.as_deref());
+    css.push("border-width", style.border_width.as_deref());
+    css.push(
+        "vertical-align",
+        Some(style.vertical_align.as_str()).filter(not("baseline")),
+    );
+    // Browsers don't support justify-all
+    css.push(
+        "text-align",
+        Some(style.text_align.as_str())
+            .filter(not("start"))
+            .filter(not("justify-all")),
+    );
+    css.push("text-emphasis", style.text_emphasis.as_deref());
+    css.push("text-shadow", style.text_shadow.as_deref());
+    css.push("margin", style.margin.as_deref());
+    css.push("margin-top", margin(&style.margin_top).as_deref());
+    css.push("margin-left", margin(&style.margin_left).as_deref());
+    css.push("margin-right", margin(&style.margin_right).as_deref());
+    css.push("margin-bottom", margin(&style.margin_bottom).as_deref());
+    css.push("padding", style.padding.as_deref());
+    css.push("padding-top", style.padding_top.as_deref());
+    css.push("padding-left", style.padding_left.as_deref());
+    css.push("padding-right", style.padding_right.as_deref());
+    css.push("padding-bottom", style.padding_bottom.as_deref());
+    css.push(
+        "word-break",
+        Some(style.word_break.as_str()).filter(not("normal")),
+    );
+    css.push(
+        "white-space",
+        Some(style.white_space.as_str()).filter(not("normal")),
+    );
+    css.push(
+        "list-style-type",
+        Some(style.list_style_type.as_str()).filter(not("disc")),
+    );
+    css.finish()
+}
+
+/// Declarations of an inline style. Properties other than [`ALLOWED_PROPERTIES`] and values that
+/// are unsafe are left out.
+#[derive(Default)]
+struct Css(String);
+
+impl Css {
+    fn push(&mut self, property: &str, value: Option<&str>) {
+        if !ALLOWED_PROPERTIES.contains(&property) {
+            return;
+        }
+        if let Some(value) = value.and_then(css_value) {
+            let _ = write!(self.0, "{}:{};", property, value);
+        }
+    }
+
+    fn finish(self) -> Option<String> {
+        (!self.0.is_empty()).then_some(self.0)
+    }
+}
+
+/// A CSS value, or none when it is empty, could load a resource or run script, or could end
+/// the declaration it is in.
+fn css_value(value: &str) -> Option<&str> {
+    let value = value.trim();
+    let lower = value.to_ascii_lowercase();
+    let is_unsafe = value.is_empty()
+        || value
+            .chars()
+            .any(|c| matches!(c, ';' | '{' | '}' | '<' | '>' | '\\' | '@') || c.is_control())
+        || ["url(", "image(", "image-set(", "expression(", "javascript:"]
+            .iter()
+            .any(|token| lower.contains(token));
+    (!is_unsafe).then_some(value)
+}
+
+fn push_data(out: &mut String, data: Option<&StructuredContentData>) {
+    let Some(data) = data else {
+        return;
+    };
+    let mut data: Vec<_> = data.iter().collect();
+    data.sort();
+    for (key, value) in data {
+        let is_name = !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'));
+        if is_name {
+            push_attribute(out, &format!("data-sc-{}", key), Some(value));
+        }
+    }
+}
+
+fn push_attribute(out: &mut String, name: &str, value: Option<&str>) {
+    if let Some(value) = value {
+        let _ = write!(out, r#" {}="{}""#, name, escape(value));
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Keeps the tags of [`ALLOWED_TAGS`] with a few harmless attributes, drops the tags of
+/// [`DROPPED_TAGS`] with their content and unwraps other tags. The fragment is parsed like
+/// browsers do, so it can't close elements around it.
+pub fn sanitize_html(html: &str, dictionary_id: i32) -> String {
+    Builder::empty()
+        .tags(HashSet::from(ALLOWED_TAGS))
+        .clean_content_tags(HashSet::from(DROPPED_TAGS))
+        .generic_attributes(HashSet::from(["lang", "style", "title"]))
+        .tag_attributes(HashMap::from([
+            ("a", HashSet::from(["href"])),
+            ("details", HashSet::from(["open"])),
+            ("img", HashSet::from(["alt", "height", "src", "width"])),
+            ("ol", HashSet::from(["start"])),
+            ("td", HashSet::from(["colspan", "rowspan"])),
+            ("th", HashSet::from(["colspan", "rowspan"])),
+        ]))
+        .url_schemes(HashSet::from(["http", "https"]))
+        .url_relative(UrlRelative::PassThrough)
+        .attribute_filter(
+            move |element, attribute, value| match (element, attribute) {
+                ("a", "href") => is_allowed_href(value).then_some(value.into()),
+                ("img", "src") => media_url(value, dictionary_id).map(Cow::from),
+                (_, "width" | "height" | "colspan" | "rowspan" | "start") => {
+                    let is_number = !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit());
+                    is_number.then_some(value.into())
+                }
+                (_, "style") => sanitize_style(value).map(Cow::from),
+                _ => Some(value.into()),
+            },
+        )
+        .clean(html)
+        .to_string()
+}
+
+/// The declarations of an inline style that [`Css`] keeps.
+fn sanitize_style(style: &str) -> Option<String> {
+    let mut css = Css::default();
+    for declaration in style.split(';') {
+        if let Some((property, value)) = declaration.split_once(':') {
+            css.push(&property.trim().to_ascii_lowercase(), Some(value));
+        }
+    }
+    css.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn render(definition: serde_json::Value) -> String {
+        let mut definitions = vec![serde_json::from_value(definition).unwrap()];
+        render_definitions(&mut definitions, 7);
+        match &definitions[0] {
+            Definition::Detailed(detailed) => match detailed.as_ref() {
+                DetailedDefinition::Html(html) => html.html.clone(),
+                _ => panic!("Not rendered"),
+            },
+            _ => panic!("Not rendered"),
+        }
+    }
+
+    #[test]
+    fn should_render_structured_content() {
+        let html = render(json!({
+            "type": "structured-content",
+            "content": [
+                { "tag": "span", "style": { "fontWeight": "bold", "marginTop": 0.5 }, "content": "<b>" },
+                { "tag": "ruby", "content": ["人", { "tag": "rt", "content": "ひと" }] },
+                { "tag": "br", "data": { "kind": "x", "bad name": "y" } },
+                {
+                    "tag": "img",
+                    "path": "img/a b.png",
+                    "width": 2,
+                    "sizeUnits": "em",
+                    "border": "url(https://example.com)"
+                },
+                { "tag": "img", "path": "../../secret" },
+                { "tag": "a", "href": "?query=人", "content": "search" },
+                { "tag": "a", "href": "javascript:alert(1)", "content": "bad" },
+                { "tag": "td", "colSpan": 2, "style": { "color": "red;}" }, "content": "cell" }
+            ]
+        }));
+        assert_eq!(
+            html,
+            concat!(
+                r#"<span style="font-weight:bold;margin-top:0.5em;">&lt;b&gt;</span>"#,
+                "<ruby>人<rt>ひと</rt></ruby>",
+                r#"<br data-sc-kind="x">"#,
+                r#"<img src="/media/7/img%2Fa%20b.png" alt="" style="width:2em;">"#,
+                r#"<a href="?query=人">search</a>"#,
+                "<a>bad</a>",
+                r#"<td colspan="2">cell</td>"#,
+            )
+        );
+
+        assert_eq!(
+            render(json!({
+                "type": "structured-content",
+                "content": {
+                    "tag": "div",
+                    "style": { "clipPath": "circle(50%)", "cursor": "pointer", "color": "red" },
+                }
+            })),
+            r#"<div style="color:red;"></div>"#
+        );
+        assert_eq!(render(json!("a < b")), "a &lt; b");
+        assert_eq!(
+            render(json!({ "type": "image", "path": "pic.png", "width": 10, "pixelated": true })),
+            r#"<img src="/media/7/pic.png" alt="" width="10" style="image-rendering:pixelated;">"#
+        );
+        let mut definitions = vec![serde_json::from_value(json!(["食べる", ["past"]])).unwrap()];
+        render_definitions(&mut definitions, 7);
+        assert!(matches!(definitions[0], Definition::Deinflection(_)));
+    }
+
+    #[test]
+    fn should_sanitize_html() {
+        let html = concat!(
+            r#"<div class="entry" onclick="alert(1)" style="color: red; position: fixed">"#,
+            "<script>alert('<b>')</script><b>bold</b> &amp; A&B<!-- comment -->",
+            r#"<a href="&#106;avascript:alert(1)">x</a><a href='https://example.com'>y</a>"#,
+            r#"<img src="images/a.png" onerror=alert(1)><img src="http://example.com/a.png">"#,
+            "<font color=red>plain</font><p>open</div></div><ul><li>item",
+        );
+        assert_eq!(
+            sanitize_html(html, 7),
+            concat!(
+                r#"<div style="color:red;"><b>bold</b> &amp; A&amp;B"#,
+                r#"<a rel="noopener noreferrer">x</a>"#,
+                r#"<a href="https://example.com" rel="noopener noreferrer">y</a>"#,
+                r#"<img src="/media/7/images%2Fa.png"><img>"#,
+                "plain<p>open</p></div><ul><li>item</li></ul>",
+            )
+        );
+        assert_eq!(sanitize_html("1 < 2 <br/> 3", 7), "1 &lt; 2 <br> 3");
+    }
+}
@@ -0,0 +1,665 @@
+use crate::schemas::dictionary_index::DictionaryIndex;
+use crate::schemas::dictionary_tag_bank_v3::DictionaryTagBankV3Row;
+use crate::schemas::dictionary_term_bank_v3::{
+    Definition, DetailedDefinition, DictionaryTermBankV3Row, StructuredContent,
+    StructuredContentDefinition, StructuredContentObject, StyledContainerFields,
+};
+use crate::util::dict::{Importer, TERM_BATCH_SIZE};
+use crate::util::progress::{ImportPhase, ImportProgress};
+use anyhow::{Context, bail};
+use flate2::read::GzDecoder;
+use quick_xml::Reader;
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::events::Event;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::mem;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Language of the glosses that are imported, senses without any are left out.
+const GLOSS_LANGUAGE: &str = "eng";
+
+/// Priorities that make a headword common, as defined by the EDRDG.
+const COMMON_PRIORITIES: [&str; 5] = ["news1", "ichi1", "spec1", "spec2", "gai1"];
+
+/// Tag added to the expression tags of common headwords.
+const POPULAR_TAG: &str = "P";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JmdictKind {
+    /// The JMdict dictionary of words.
+    Jmdict,
+    /// The JMnedict dictionary of proper names.
+    Jmnedict,
+}
+
+/// A single `<entry>`, with entity references kept as the name of the entity.
+#[derive(Debug, Default)]
+pub struct Entry {
+    /// `<ent_seq>`, shared by every row of the entry.
+    pub sequence: i32,
+    pub kanji: Vec<Kanji>,
+    pub readings: Vec<Reading>,
+    /// `<sense>` in JMdict and `<trans>` in JMnedict.
+    pub senses: Vec<Sense>,
+}
+
+/// `<k_ele>`
+#[derive(Debug, Default)]
+pub struct Kanji {
+    pub text: String,
+    pub info: Vec<String>,
+    pub priorities: Vec<String>,
+}
+
+/// `<r_ele>`
+#[derive(Debug, Default)]
+pub struct Reading {
+    pub text: String,
+    /// The reading is not a true reading of any of the kanji.
+    pub no_kanji: bool,
+    /// Kanji the reading applies to, every kanji when empty.
+    pub restrictions: Vec<String>,
+    pub info: Vec<String>,
+    pub priorities: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct Sense {
+    /// Kanji the sense applies to, every kanji when empty.
+    pub kanji_restrictions: Vec<String>,
+    /// Readings the sense applies to, every reading when empty.
+    pub reading_restrictions: Vec<String>,
+    /// Carried over from the previous sense when a sense has none, as the DTD specifies.
+    pub parts_of_speech: Vec<String>,
+    pub misc: Vec<String>,
+    pub fields: Vec<String>,
+    pub dialects: Vec<String>,
+    /// `<name_type>` of JMnedict translations.
+    pub name_types: Vec<String>,
+    pub info: Vec<String>,
+    pub references: Vec<String>,
+    pub antonyms: Vec<String>,
+    pub glosses: Vec<Gloss>,
+}
+
+#[derive(Debug, Default)]
+pub struct Gloss {
+    pub text: String,
+    /// ISO 639-2 code of the gloss.
+    pub lang: String,
+}
+
+/// Streams the entries of a JMdict or JMnedict XML file.
+pub struct JmdictReader<R: BufRead> {
+    reader: Reader<R>,
+    buf: Vec<u8>,
+    kind: JmdictKind,
+    /// Descriptions of the entities declared in the DTD, keyed by name.
+    entities: HashMap<String, String>,
+    /// Date from the `JMdict created` comment.
+    created: Option<String>,
+}
+
+impl JmdictReader<Box<dyn BufRead + Send>> {
+    /// Reads plain XML, or gzip compressed XML as it is distributed.
+    pub fn open(reader: impl Read + Send + 'static) -> anyhow::Result<Self> {
+        let mut reader = BufReader::new(reader);
+        let gzip = reader.fill_buf()?.starts_with(&[0x1f, 0x8b]);
+        let reader: Box<dyn BufRead + Send> = if gzip {
+            Box::new(BufReader::new(GzDecoder::new(reader)))
+        } else {
+            Box::new(reader)
+        };
+        Self::new(reader)
+    }
+}
+
+impl<R: BufRead> JmdictReader<R> {
+    /// Reads everything up to the root element.
+    pub fn new(reader: R) -> anyhow::Result<Self> {
+        let mut reader = Reader::from_reader(reader);
+        reader.config_mut().expand_empty_elements = true;
+        let mut buf = vec![];
+        let mut entities = HashMap::new();
+        let mut created = None;
+        let kind = loop {
+            buf.clear();
+            match reader.read_event_into(&mut buf)? {
+                Event::DocType(dtd) => entities = parse_entities(&dtd.decode()?),
+                Event::Comment(comment) => {
+                    if let Some((_, date)) = comment.decode()?.split_once("created:") {
+                        created = Some(date.trim().to_string());
+                    }
+                }
+                Event::Start(root) => match root.name().as_ref() {
+                    b"JMdict" => break JmdictKind::Jmdict,
+                    b"JMnedict" => break JmdictKind::Jmnedict,
+                    name => bail!(
+                        "Expected a JMdict or JMnedict file, found <{}>",
+                        String::from_utf8_lossy(name)
+                    ),
+                },
+                Event::Eof => bail!("Expected a JMdict or JMnedict file"),
+                _ => {}
+            }
+        };
+
+        Ok(Self {
+            reader,
+            buf,
+            kind,
+            entities,
+            created,
+        })
+    }
+
+    pub fn entities(&self) -> &HashMap<String, String> {
+        &self.entities
+    }
+
+    /// Index of the dictionary, the revision is the creation date of the file.
+    pub fn index(&self) -> DictionaryIndex {
+        let (title, url) = match self.kind {
+            JmdictKind::Jmdict => ("JMdict", "https://www.edrdg.org/jmdict/j_jmdict.html"),
+            JmdictKind::Jmnedict => (
+                "JMnedict",
+                "https://www.edrdg.org/enamdict/enamdict_doc.html",
+            ),
+        };
+        DictionaryIndex {
+            title: title.to_string(),
+            revision: self
+                .created
+                .clone()
+                .unwrap_or_else(|| "unknown".to_string()),
+            minimum_yomitan_version: None,
+            sequenced: true,
+            format: Some(3),
+            version: None,
+            author: Some("Electronic Dictionary Research and Development Group".to_string()),
+            is_updatable: None,
+            index_url: None,
+            download_url: None,
+            url: Some(url.to_string()),
+            description: None,
+            attribution: Some(format!(
+                "{} is the property of the Electronic Dictionary Research and Development Group, \
+                and is used in conformance with the Group's licence.",
+                title
+            )),
+            source_language: Some("ja".to_string()),
+            target_language: Some("en".to_string()),
+            frequency_mode: None,
+            tag_meta: None,
+        }
+    }
+
+    /// Returns the next entry, or `None` at the end of the file.
+    pub fn next_entry(&mut self) -> anyhow::Result<Option<Entry>> {
+        let mut entry = Entry::default();
+        let mut kanji = Kanji::default();
+        let mut reading = Reading::default();
+        let mut sense = Sense::default();
+        let mut lang = String::new();
+        // Content of the innermost element, entity references are replaced by their name
+        let mut text = String::new();
+        loop {
+            self.buf.clear();
+            match self.reader.read_event_into(&mut self.buf)? {
+                Event::Start(element) => {
+                    text.clear();
+                    if matches!(element.name().as_ref(), b"gloss" | b"trans_det") {
+                        lang = match element.try_get_attribute("xml:lang")? {
+                            Some(attribute) => attribute.unescape_value()?.into_owned(),
+                            None => GLOSS_LANGUAGE.to_string(),
+                        };
+                    }
+                }
+                Event::Text(content) => text.push_str(&content.xml_content()?),
+                Event::GeneralRef(reference) => {
+                    if let Some(c) = reference.resolve_char_ref()? {
+                        text.push(c);
+                    } else {
+                        let name = reference.decode()?;
+                        text.push_str(resolve_predefined_entity(&name).unwrap_or(&name));
+                    }
+                }
+                Event::End(element) => {
+                    let text = mem::take(&mut text);
+                    match element.name().as_ref() {
+                        b"ent_seq" => {
+                            entry.sequence = text
+                                .trim()
+                                .parse()
+                                .with_context(|| format!("Invalid ent_seq {}", text))?;
+                        }
+                        b"keb" => kanji.text = text,
+                        b"ke_inf" => kanji.info.push(text),
+                        b"ke_pri" => kanji.priorities.push(text),
+                        b"k_ele" => entry.kanji.push(mem::take(&mut kanji)),
+                        b"reb" => reading.text = text,
+                        b"re_nokanji" => reading.no_kanji = true,
+                        b"re_restr" => reading.restrictions.push(text),
+                        b"re_inf" => reading.info.push(text),
+                        b"re_pri" => reading.priorities.push(text),
+                        b"r_ele" => entry.readings.push(mem::take(&mut reading)),
+                        b"stagk" => sense.kanji_restrictions.push(text),
+                        b"stagr" => sense.reading_restrictions.push(text),
+                        b"pos" => sense.parts_of_speech.push(text),
+                        b"misc" => sense.misc.push(text),
+                        b"field" => sense.fields.push(text),
+                        b"dial" => sense.dialects.push(text),
+                        b"name_type" => sense.name_types.push(text),
+                        b"s_inf" => sense.info.push(text),
+                        b"xref" => sense.references.push(text),
+                        b"ant" => sense.antonyms.push(text),
+                        b"gloss" | b"trans_det" => sense.glosses.push(Gloss {
+                            text,
+                            lang: mem::take(&mut lang),
+                        }),
+                        b"sense" | b"trans" => {
+                            if sense.parts_of_speech.is_empty()
+                                && let Some(previous) = entry.senses.last()
+                            {
+                                sense.parts_of_speech = previous.parts_of_speech.clone();
+                            }
+                            entry.senses.push(mem::take(&mut sense));
+                        }
+                        b"entry" => return Ok(Some(entry)),
+                        _ => {}
+                    }
+                }
+                Event::Eof => return Ok(None),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Imports the entries of a JMdict or JMnedict file, reporting progress in bytes of the file.
+pub struct JmdictImporter {
+    reader: JmdictReader<Box<dyn BufRead + Send>>,
+    converter: JmdictConverter,
+    /// Bytes of the file read and not reported yet.
+    read: Arc<AtomicU64>,
+    length: u64,
+}
+
+impl JmdictImporter {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let length = file.metadata()?.len();
+        let read = Arc::new(AtomicU64::new(0));
+        let file = CountingReader {
+            inner: file,
+            read: read.clone(),
+        };
+        let reader = JmdictReader::open(file)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(Self {
+            reader,
+            converter: JmdictConverter::default(),
+            read,
+            length,
+        })
+    }
+}
+
+impl Importer for JmdictImporter {
+    fn index(&self) -> DictionaryIndex {
+        self.reader.index()
+    }
+
+    fn length(&self) -> u64 {
+        self.length
+    }
+
+    fn next_terms(
+        &mut self,
+        progress: &dyn ImportProgress,
+    ) -> anyhow::Result<Option<Vec<DictionaryTermBankV3Row>>> {
+        let mut rows = Vec::with_capacity(TERM_BATCH_SIZE);
+        while rows.len() < TERM_BATCH_SIZE {
+            match self.reader.next_entry()? {
+                Some(entry) => rows.extend(self.converter.term_rows(&entry)),
+                None => break,
+            }
+        }
+        progress.inc(ImportPhase::Parse, self.read.swap(0, Ordering::Relaxed));
+        Ok((!rows.is_empty()).then_some(rows))
+    }
+
+    fn tags(&mut self) -> Vec<DictionaryTagBankV3Row> {
+        self.converter.tag_rows(self.reader.entities())
+    }
+}
+
+/// Counts the bytes read, shared with the importer reporting them.
+struct CountingReader<R> {
+    inner: R,
+    read: Arc<AtomicU64>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.read.fetch_add(read as u64, Ordering::Relaxed);
+        Ok(read)
+    }
+}
+
+/// Reads `<!ENTITY name "description">` declarations.
+fn parse_entities(dtd: &str) -> HashMap<String, String> {
+    dtd.split("<!ENTITY")
+        .skip(1)
+        .filter_map(|declaration| {
+            let (name, rest) = declaration.trim_start().split_once(char::is_whitespace)?;
+            let rest = rest.trim_start();
+            let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+            let (description, _) = rest[1..].split_once(quote)?;
+            Some((name.to_string(), description.to_string()))
+        })
+        .collect()
+}
+
+/// Turns entries into term bank rows and keeps track of the tags they use.
+#[derive(Default)]
+pub struct JmdictConverter {
+    /// Category of every tag used so far, keyed by name.
+    tags: BTreeMap<String, &'static str>,
+}
+
+impl JmdictConverter {
+    /// Returns a row for every sense of every headword, in the order of the senses.
+    pub fn term_rows(&mut self, entry: &Entry) -> Vec<DictionaryTermBankV3Row> {
+        let mut rows = vec![];
+        for (kanji, reading) in entry.headwords() {
+            // A kanji headword has the priorities both its kanji and its reading have
+            let priorities: BTreeSet<&str> = reading
+                .priorities
+                .iter()
+                .filter(|p| kanji.is_none_or(|kanji| kanji.priorities.contains(p)))
+                .map(String::as_str)
+                .collect();
+            let score = priorities.iter().map(|p| priority_score(p)).sum();
+
+            let mut expression_tags: Vec<&str> = kanji
+                .iter()
+                .flat_map(|kanji| &kanji.info)
+                .chain(&reading.info)
+                .map(String::as_str)
+                .collect();
+            if priorities.iter().any(|p| COMMON_PRIORITIES.contains(p)) {
+                expression_tags.push(POPULAR_TAG);
+            }
+            for tag in &expression_tags {
+                self.add_tag(tag, "");
+            }
+
+            let (expression, reading_text) = match kanji {
+                Some(kanji) => (&kanji.text, reading.text.clone()),
+                None => (&reading.text, String::new()),
+            };
+            for sense in &entry.senses {
+                if !sense.applies_to(kanji, reading) {
+                    continue;
+                }
+                let glosses: Vec<&Gloss> = sense
+                    .glosses
+                    .iter()
+                    .filter(|gloss| gloss.lang == GLOSS_LANGUAGE)
+                    .collect();
+                if glosses.is_empty() {
+                    continue;
+                }
+
+                let mut definition_tags = vec![];
+                for (tags, category) in [
+                    (&sense.parts_of_speech, "partOfSpeech"),
+                    (&sense.name_types, "name"),
+                    (&sense.misc, ""),
+                    (&sense.fields, ""),
+                    (&sense.dialects, ""),
+                ] {
+                    for tag in tags {
+                        self.add_tag(tag, category);
+                        definition_tags.push(tag.as_str());
+                    }
+                }
+
+                rows.push(DictionaryTermBankV3Row(
+                    expression.clone(),
+                    reading_text.clone(),
+                    Some(definition_tags.join(" ")),
+                    rules(&sense.parts_of_speech),
+                    score,
+                    vec![definition(sense, &glosses)],
+                    entry.sequence,
+                    expression_tags.join(" "),
+                ));
+            }
+        }
+        rows
+    }
+
+    /// Rows for the tags used by the converted entries, described by their entity.
+    pub fn tag_rows(&self, entities: &HashMap<String, String>) -> Vec<DictionaryTagBankV3Row> {
+        self.tags
+            .iter()
+            .map(|(name, category)| {
+                let (notes, score) = match name.as_str() {
+                    POPULAR_TAG => ("popular term".to_string(), 10.0),
+                    name => (entities.get(name).cloned().unwrap_or_default(), 0.0),
+                };
+                DictionaryTagBankV3Row(name.clone(), category.to_string(), 0.0, notes, score)
+            })
+            .collect()
+    }
+
+    fn add_tag(&mut self, name: &str, category: &'static str) {
+        if name == POPULAR_TAG {
+            self.tags.insert(name.to_string(), "popular");
+        } else if !self.tags.contains_key(name) {
+            self.tags.insert(name.to_string(), category);
+        }
+    }
+}
+
+impl Entry {
+    /// Pairs of kanji and reading, or a lone reading for entries and readings without kanji.
+    fn headwords(&self) -> Vec<(Option<&Kanji>, &Reading)> {
+        let mut headwords = vec![];
+        for reading in &self.readings {
+            if self.kanji.is_empty() || reading.no_kanji {
+                headwords.push((None, reading));
+                continue;
+            }
+            for kanji in &self.kanji {
+                if reading.restrictions.is_empty() || reading.restrictions.contains(&kanji.text) {
+                    headwords.push((Some(kanji), reading));
+                }
+            }
+        }
+        headwords
+    }
+}
+
+impl Sense {
+    fn applies_to(&self, kanji: Option<&Kanji>, reading: &Reading) -> bool {
+        let kanji_applies = self.kanji_restrictions.is_empty()
+            || kanji.is_some_and(|kanji| self.kanji_restrictions.contains(&kanji.text));
+        let reading_applies = self.reading_restrictions.is_empty()
+            || self.reading_restrictions.contains(&reading.text);
+        kanji_applies && reading_applies
+    }
+}
+
+/// First-level priorities count twice as much as second-level ones.
+fn priority_score(priority: &str) -> f32 {
+    match priority {
+        "news1" | "ichi1" | "spec1" | "gai1" => 2.0,
+        "news2" | "ichi2" | "spec2" | "gai2" => 1.0,
+        _ => 0.0,
+    }
+}
+
+/// Deinflection rules for the parts of speech that inflect.
+fn rules(parts_of_speech: &[String]) -> String {
+    let mut rules: Vec<&str> = vec![];
+    for pos in parts_of_speech {
+        let rule = match pos.as_str() {
+            "v1" | "v1-s" => "v1",
+            "vk" => "vk",
+            "vz" => "vz",
+            "vs-i" | "vs-s" => "vs",
+            "adj-i" | "adj-ix" => "adj-i",
+            pos if pos.starts_with("v5") => "v5",
+            _ => continue,
+        };
+        if !rules.contains(&rule) {
+            rules.push(rule);
+        }
+    }
+    rules.join(" ")
+}
+
+/// A list of the glosses followed by the notes of the sense.
+fn definition(sense: &Sense, glosses: &[&Gloss]) -> Definition {
+    let items = glosses
+        .iter()
+        .map(|gloss| element(StructuredContentObject::Li, text(&gloss.text)))
+        .collect();
+    let mut content = vec![element(
+        StructuredContentObject::Ul,
+        StructuredContent::Array(items),
+    )];
+    for (notes, prefix) in [
+        (&sense.info, ""),
+        (&sense.references, "See also: "),
+        (&sense.antonyms, "Antonym: "),
+    ] {
+        if !notes.is_empty() {
+            let notes = format!("{}{}", prefix, notes.join("; "));
+            content.push(element(StructuredContentObject::Div, text(&notes)));
+        }
+    }
+
+    Definition::Detailed(Box::new(DetailedDefinition::StructuredContent(
+        StructuredContentDefinition {
+            content: Box::new(StructuredContent::Array(content)),
+        },
+    )))
+}
+
+fn element(
+    tag: fn(StyledContainerFields) -> StructuredContentObject,
+    content: StructuredContent,
+) -> StructuredContent {
+    StructuredContent::Object(Box::new(tag(StyledContainerFields {
+        content: Some(content),
+        data: None,
+        style: None,
+        title: None,
+        open: None,
+        lang: None,
+    })))
+}
+
+fn text(text: &str) -> StructuredContent {
+    StructuredContent::Text(text.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+
+    fn read_fixture() -> (JmdictReader<Box<dyn BufRead + Send>>, Vec<Entry>) {
+        let file = fs::File::open("src/fixtures/jmdict.xml").unwrap();
+        let mut reader = JmdictReader::open(file).unwrap();
+        let mut entries = vec![];
+        while let Some(entry) = reader.next_entry().unwrap() {
+            entries.push(entry);
+        }
+        (reader, entries)
+    }
+
+    #[test]
+    fn should_read_entries() {
+        let (reader, entries) = read_fixture();
+        let index = reader.index();
+        assert_eq!(index.title, "JMdict");
+        assert_eq!(index.revision, "2026-10-01");
+        assert_eq!(reader.entities()["v5m"], "Godan verb with 'mu' ending");
+        assert_eq!(entries.len(), 4);
+
+        let yomu = &entries[0];
+        assert_eq!(yomu.sequence, 1358280);
+        assert_eq!(yomu.kanji[1].info, ["rK"]);
+        // The second sense has no <pos> and takes the one of the first
+        assert_eq!(yomu.senses[1].parts_of_speech, ["v5m", "vt"]);
+        assert_eq!(yomu.senses[1].info, ["esp. & of waka"]);
+        assert_eq!(yomu.senses[0].glosses[1].lang, "ger");
+        assert!(entries[1].readings[2].no_kanji);
+    }
+
+    #[test]
+    fn should_convert_entries() {
+        let (reader, entries) = read_fixture();
+        let mut converter = JmdictConverter::default();
+        let rows: Vec<DictionaryTermBankV3Row> = entries
+            .iter()
+            .flat_map(|entry| converter.term_rows(entry))
+            .collect();
+        let headwords: Vec<(&str, &str, usize)> = rows
+            .iter()
+            .map(|row| (row.0.as_str(), row.1.as_str(), row.5.len()))
+            .collect();
+        assert_eq!(
+            headwords,
+            [
+                ("読む", "よむ", 1),
+                ("詠む", "よむ", 1),
+                ("詠む", "よむ", 1),
+                ("彼処", "あそこ", 1),
+                ("彼処", "あそこ", 1),
+                ("彼処", "あすこ", 1),
+                ("アソコ", "", 1),
+                ("ヽ", "", 1),
+            ]
+        );
+
+        let yomu = &rows[0];
+        assert_eq!(yomu.2.as_deref(), Some("v5m vt"));
+        assert_eq!(yomu.3, "v5");
+        assert_eq!(yomu.4, 4.0);
+        assert_eq!(yomu.6, 1358280);
+        assert_eq!(yomu.7, "P");
+        assert_eq!(rows[1].7, "rK");
+        assert_eq!(rows[3].2.as_deref(), Some("n uk"));
+        assert_eq!(rows[4].2.as_deref(), Some("n ksb"));
+
+        let definition = serde_json::to_value(&rows[3].5[0]).unwrap();
+        assert_eq!(definition["type"], "structured-content");
+        assert_eq!(definition["content"][0]["tag"], "ul");
+        assert_eq!(
+            definition["content"][0]["content"][1]["content"],
+            "over there"
+        );
+        assert_eq!(
+            definition["content"][1]["content"],
+            "See also: 何処・どこ・1"
+        );
+
+        let tags = converter.tag_rows(reader.entities());
+        let v5m = tags.iter().find(|tag| tag.0 == "v5m").unwrap();
+        assert_eq!(v5m.1, "partOfSpeech");
+        assert_eq!(v5m.3, "Godan verb with 'mu' ending");
+        assert!(tags.iter().any(|tag| tag.0 == "P" && tag.1 == "popular"));
+    }
+}
@@ -0,0 +1,414 @@
+use crate::db::Db;
+use crate::db::tables::Dictionary;
+use crate::util::config::Config;
+use crate::util::dict::{Dict, OnConflict};
+use crate::util::progress::{ImportPhase, ImportProgress};
+use crate::util::updater::Updater;
+use crate::util::validation::ValidationReport;
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use tempfile::TempPath;
+use tokio::sync::{Semaphore, watch};
+
+/// How long finished jobs stay listed.
+const FINISHED_JOB_TTL: TimeDelta = TimeDelta::hours(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(
+            self,
+            JobStatus::Completed | JobStatus::Failed | JobStatus::Cancelled
+        )
+    }
+}
+
+/// Counts of one phase of an import, see [`ImportPhase`] for their unit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhaseProgress {
+    pub phase: ImportPhase,
+    pub position: u64,
+    /// 0 when it isn't known in advance.
+    pub length: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobState {
+    pub id: u32,
+    pub status: JobStatus,
+    /// Phase started last.
+    pub phase: Option<ImportPhase>,
+    /// Every phase started so far, in order.
+    pub phases: Vec<PhaseProgress>,
+    /// Bank or file being processed.
+    pub message: Option<String>,
+    /// Set once the import completes.
+    pub dictionary_id: Option<i32>,
+    pub error: Option<String>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// A dictionary import running in the background. Every change to its state is published so
+/// it can be streamed to clients.
+pub struct Job {
+    state: watch::Sender<JobState>,
+    cancelled: AtomicBool,
+}
+
+impl Job {
+    fn new(id: u32) -> Self {
+        let (state, _) = watch::channel(JobState {
+            id,
+            status: JobStatus::Queued,
+            phase: None,
+            phases: vec![],
+            message: None,
+            dictionary_id: None,
+            error: None,
+            finished_at: None,
+        });
+        Self {
+            state,
+            cancelled: AtomicBool::new(false),
+        }
+    }
+
+    pub fn state(&self) -> JobState {
+        self.state.borrow().clone()
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<JobState> {
+        self.state.subscribe()
+    }
+
+    /// Asks the job to stop. A queued job is cancelled right away, a running one stops at the
+    /// next bank or file and rolls back everything it imported.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        self.state.send_if_modified(|state| {
+            if state.status != JobStatus::Queued {
+                return false;
+            }
+            state.status = JobStatus::Cancelled;
+            state.finished_at = Some(Utc::now());
+            true
+        });
+    }
+
+    fn run(&self) {
+        self.state
+            .send_modify(|state| state.status = JobStatus::Running);
+    }
+
+    fn complete(&self, result: anyhow::Result<i32>) {
+        let cancelled = self.is_cancelled();
+        self.state.send_modify(|state| {
+            match result {
+                Ok(dictionary_id) => {
+                    state.status = JobStatus::Completed;
+                    state.dictionary_id = Some(dictionary_id);
+                }
+                Err(_) if cancelled => state.status = JobStatus::Cancelled,
+                Err(err) => {
+                    state.status = JobStatus::Failed;
+                    state.error = Some(format!("{:#}", err));
+                }
+            }
+            state.finished_at = Some(Utc::now());
+        });
+    }
+}
+
+impl ImportProgress for Job {
+    fn start(&self, phase: ImportPhase, length: u64) {
+        self.state.send_modify(|state| {
+            state.phase = Some(phase);
+            state.phases.retain(|started| started.phase != phase);
+            state.phases.push(PhaseProgress {
+                phase,
+                position: 0,
+                length,
+            });
+            state.message = None;
+        });
+    }
+
+    fn set_message(&self, message: &str) {
+        self.state
+            .send_modify(|state| state.message = Some(message.to_string()));
+    }
+
+    fn inc(&self, phase: ImportPhase, delta: u64) {
+        self.state.send_if_modified(|state| {
+            let Some(started) = state
+                .phases
+                .iter_mut()
+                .find(|started| started.phase == phase)
+            else {
+                return false;
+            };
+            started.position += delta;
+            true
+        });
+    }
+
+    fn finish(&self) {}
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Background imports and updates, run one at a time in the order they were queued. Jobs are
+/// kept in memory until [`FINISHED_JOB_TTL`] after they finish.
+pub struct Jobs {
+    next_id: AtomicU32,
+    jobs: Mutex<HashMap<u32, Arc<Job>>>,
+    imports: Semaphore,
+}
+
+impl Default for Jobs {
+    fn default() -> Self {
+        Self {
+            next_id: AtomicU32::new(1),
+            jobs: Mutex::new(HashMap::new()),
+            imports: Semaphore::new(1),
+        }
+    }
+}
+
+impl Jobs {
+    pub fn get(&self, id: u32) -> Option<Arc<Job>> {
+        self.evict(Utc::now());
+        self.jobs.lock().unwrap().get(&id).cloned()
+    }
+
+    pub fn list(&self) -> Vec<JobState> {
+        self.evict(Utc::now());
+        let mut jobs: Vec<JobState> = self
+            .jobs
+            .lock()
+            .unwrap()
+            .values()
+            .map(|job| job.state())
+            .collect();
+        jobs.sort_by_key(|job| job.id);
+        jobs
+    }
+
+    /// Queues the import of `archive`, which is removed once the job finishes or is cancelled.
+    pub fn import(
+        self: &Arc<Self>,
+        config: Arc<Config>,
+        db: Arc<Db>,
+        archive: TempPath,
+        on_conflict: OnConflict,
+    ) -> Arc<Job> {
+        self.queue(move |job| async move {
+            Dict::new(config)
+                .parse_dict(
+                    archive.to_path_buf(),
+                    &db,
+                    on_conflict,
+                    &mut ValidationReport::default(),
+                    job,
+                )
+                .await
+        })
+    }
+
+    /// Queues the update of `dictionary` to its latest revision. The job completes without
+    /// changes when there is none.
+    pub fn update(
+        self: &Arc<Self>,
+        config: Arc<Config>,
+        db: Arc<Db>,
+        updater: Arc<Updater>,
+        dictionary: Dictionary,
+    ) -> Arc<Job> {
+        self.queue(move |job| async move {
+            updater.update(config, &db, &dictionary, job).await?;
+            Ok(dictionary.id)
+        })
+    }
+
+    /// Runs `task` in the background once the jobs queued before it are done, unless the job is
+    /// cancelled first.
+    fn queue<F, Fut>(self: &Arc<Self>, task: F) -> Arc<Job>
+    where
+        F: FnOnce(Arc<Job>) -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<i32>> + Send,
+    {
+        self.evict(Utc::now());
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let job = Arc::new(Job::new(id));
+        self.jobs.lock().unwrap().insert(id, job.clone());
+
+        let jobs = self.clone();
+        let task_job = job.clone();
+        tokio::spawn(async move {
+            let job = task_job;
+            let _permit = jobs.imports.acquire().await;
+            if !job.is_cancelled() {
+                job.run();
+                let result = task(job.clone()).await;
+                job.complete(result);
+            }
+        });
+
+        job
+    }
+
+    /// Removes the jobs that finished more than [`FINISHED_JOB_TTL`] before `now`.
+    fn evict(&self, now: DateTime<Utc>) {
+        self.jobs.lock().unwrap().retain(|_, job| {
+            job.state()
+                .finished_at
+                .is_none_or(|finished_at| now - finished_at <= FINISHED_JOB_TTL)
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use anyhow::anyhow;
+    use tokio::sync::oneshot;
+
+    async fn finished(job: &Job) -> JobState {
+        job.subscribe()
+            .wait_for(|state| state.status.is_finished())
+            .await
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn should_complete_with_the_dictionary_id() {
+        let job = Job::new(1);
+        assert_eq!(job.state().status, JobStatus::Queued);
+        job.run();
+        assert_eq!(job.state().status, JobStatus::Running);
+        job.complete(Ok(5));
+
+        let state = job.state();
+        assert_eq!(state.status, JobStatus::Completed);
+        assert_eq!(state.dictionary_id, Some(5));
+        assert!(state.finished_at.is_some());
+    }
+
+    #[test]
+    fn should_fail_with_the_error() {
+        let job = Job::new(1);
+        job.run();
+        job.complete(Err(anyhow!("broken bank")));
+
+        let state = job.state();
+        assert_eq!(state.status, JobStatus::Failed);
+        assert_eq!(state.error.as_deref(), Some("broken bank"));
+    }
+
+    #[test]
+    fn should_cancel_queued_jobs_right_away() {
+        let job = Job::new(1);
+        job.cancel();
+        assert_eq!(job.state().status, JobStatus::Cancelled);
+        assert!(job.state().finished_at.is_some());
+    }
+
+    #[test]
+    fn should_cancel_running_jobs_once_they_stop() {
+        let job = Job::new(1);
+        job.run();
+        job.cancel();
+        assert_eq!(job.state().status, JobStatus::Running);
+        assert!(job.is_cancelled());
+        job.complete(Err(anyhow!("Import cancelled")));
+
+        let state = job.state();
+        assert_eq!(state.status, JobStatus::Cancelled);
+        assert_eq!(state.error, None);
+    }
+
+    #[test]
+    fn should_count_each_phase() {
+        let job = Job::new(1);
+        job.start(ImportPhase::Extract, 2);
+        job.start(ImportPhase::Parse, 2);
+        job.inc(ImportPhase::Extract, 2);
+        job.inc(ImportPhase::Parse, 1);
+        // Phases that weren't started aren't counted
+        job.inc(ImportPhase::Copy, 1);
+
+        let state = job.state();
+        assert_eq!(state.phase, Some(ImportPhase::Parse));
+        assert_eq!(
+            state.phases,
+            [
+                PhaseProgress {
+                    phase: ImportPhase::Extract,
+                    position: 2,
+                    length: 2,
+                },
+                PhaseProgress {
+                    phase: ImportPhase::Parse,
+                    position: 1,
+                    length: 2,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn should_run_jobs_one_at_a_time() {
+        let jobs = Arc::new(Jobs::default());
+        let (tx, rx) = oneshot::channel::<()>();
+        let first = jobs.queue(move |_| async move {
+            rx.await?;
+            Ok(1)
+        });
+        let second = jobs.queue(|_| async { Ok(2) });
+        let third = jobs.queue(|_| async { Ok(3) });
+        third.cancel();
+        tokio::task::yield_now().await;
+
+        assert_eq!(first.state().status, JobStatus::Running);
+        assert_eq!(second.state().status, JobStatus::Queued);
+        tx.send(()).unwrap();
+        assert_eq!(finished(&first).await.dictionary_id, Some(1));
+        assert_eq!(finished(&second).await.dictionary_id, Some(2));
+        assert_eq!(third.state().dictionary_id, None);
+    }
+
+    #[tokio::test]
+    async fn should_evict_finished_jobs_after_a_while() {
+        let jobs = Arc::new(Jobs::default());
+        let (_tx, rx) = oneshot::channel::<()>();
+        let running = jobs.queue(move |_| async move {
+            rx.await?;
+            Ok(1)
+        });
+        let cancelled = jobs.queue(|_| async { Ok(2) });
+        cancelled.cancel();
+
+        jobs.evict(Utc::now());
+        assert_eq!(jobs.list().len(), 2);
+        jobs.evict(Utc::now() + FINISHED_JOB_TTL + TimeDelta::seconds(1));
+        let ids: Vec<u32> = jobs.list().iter().map(|job| job.id).collect();
+        assert_eq!(ids, [running.state().id]);
+    }
+}
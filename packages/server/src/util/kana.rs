@@ -0,0 +1,127 @@
+const HIRAGANA_START: u32 = 0x3041;
+const HIRAGANA_END: u32 = 0x3096;
+const KATAKANA_START: u32 = 0x30a1;
+const KATAKANA_END: u32 = 0x30f6;
+const KANA_OFFSET: u32 = KATAKANA_START - HIRAGANA_START;
+
+/// Converts katakana to hiragana, leaving every other character untouched.
+pub fn to_hiragana(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            let code = c as u32;
+            if (KATAKANA_START..=KATAKANA_END).contains(&code) {
+                char::from_u32(code - KANA_OFFSET).unwrap_or(c)
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Converts hiragana to katakana, leaving every other character untouched.
+pub fn to_katakana(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            let code = c as u32;
+            if (HIRAGANA_START..=HIRAGANA_END).contains(&code) {
+                char::from_u32(code + KANA_OFFSET).unwrap_or(c)
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+const HALFWIDTH_KATAKANA: &str = "｡｢｣､･ｦｧｨｩｪｫｬｭｮｯｰｱｲｳｴｵｶｷｸｹｺｻｼｽｾｿﾀﾁﾂﾃﾄﾅﾆﾇﾈﾉﾊﾋﾌﾍﾎﾏﾐﾑﾒﾓﾔﾕﾖﾗﾘﾙﾚﾛﾜﾝﾞﾟ";
+const FULLWIDTH_KATAKANA: &str = "。「」、・ヲァィゥェォャュョッーアイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワン゛゜";
+const HALFWIDTH_DAKUTEN: char = 'ﾞ';
+const HALFWIDTH_HANDAKUTEN: char = 'ﾟ';
+
+/// Converts half-width katakana to full-width, combining voiced sound marks with the
+/// preceding character when possible.
+pub fn to_fullwidth_katakana(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        let combined = match (result.chars().last(), c) {
+            (Some(previous), HALFWIDTH_DAKUTEN) => to_voiced(previous),
+            (Some(previous), HALFWIDTH_HANDAKUTEN) => to_semi_voiced(previous),
+            _ => None,
+        };
+        if let Some(combined) = combined {
+            result.pop();
+            result.push(combined);
+            continue;
+        }
+        match HALFWIDTH_KATAKANA.chars().position(|h| h == c) {
+            Some(i) => result.extend(FULLWIDTH_KATAKANA.chars().nth(i)),
+            None => result.push(c),
+        }
+    }
+    result
+}
+
+fn to_voiced(c: char) -> Option<char> {
+    match c {
+        'ウ' => Some('ヴ'),
+        'カ' | 'キ' | 'ク' | 'ケ' | 'コ' | 'サ' | 'シ' | 'ス' | 'セ' | 'ソ' | 'タ' | 'チ'
+        | 'ツ' | 'テ' | 'ト' | 'ハ' | 'ヒ' | 'フ' | 'ヘ' | 'ホ' => {
+            char::from_u32(c as u32 + 1)
+        }
+        _ => None,
+    }
+}
+
+fn to_semi_voiced(c: char) -> Option<char> {
+    match c {
+        'ハ' | 'ヒ' | 'フ' | 'ヘ' | 'ホ' => char::from_u32(c as u32 + 2),
+        _ => None,
+    }
+}
+
+/// Converts full-width ASCII variants and the ideographic space to ASCII.
+pub fn to_halfwidth_ascii(text: &str) -> String {
+    text.chars()
+        .map(|c| match c as u32 {
+            0xff01..=0xff5e => char::from_u32(c as u32 - 0xfee0).unwrap_or(c),
+            0x3000 => ' ',
+            _ => c,
+        })
+        .collect()
+}
+
+/// Normalizes text for matching, so that hiragana, katakana and half-width katakana, as well
+/// as full-width and half-width alphanumerics, compare equal.
+pub fn normalize(text: &str) -> String {
+    to_hiragana(&to_fullwidth_katakana(&to_halfwidth_ascii(text)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_convert_kana() {
+        assert_eq!(to_hiragana("イヌとネコ"), "いぬとねこ");
+        assert_eq!(to_hiragana("ラーメン"), "らーめん");
+        assert_eq!(to_hiragana("犬"), "犬");
+        assert_eq!(to_katakana("しょう"), "ショウ");
+    }
+
+    #[test]
+    fn should_convert_halfwidth_katakana() {
+        assert_eq!(to_fullwidth_katakana("ｲﾇ"), "イヌ");
+        assert_eq!(to_fullwidth_katakana("ｶﾞｯｺｳ"), "ガッコウ");
+        assert_eq!(to_fullwidth_katakana("ﾊﾟﾝ"), "パン");
+        assert_eq!(to_fullwidth_katakana("ｳﾞｧ"), "ヴァ");
+        assert_eq!(to_fullwidth_katakana("ﾞ"), "゛");
+    }
+
+    #[test]
+    fn should_normalize() {
+        assert_eq!(normalize("イヌ"), "いぬ");
+        assert_eq!(normalize("ｲﾇ"), "いぬ");
+        assert_eq!(normalize("いぬ"), "いぬ");
+        assert_eq!(normalize("Ｔシャツ"), "Tしゃつ");
+        assert_eq!(normalize("犬"), "犬");
+    }
+}
@@ -0,0 +1,639 @@
+use crate::schemas::dictionary_index::DictionaryIndex;
+use crate::schemas::dictionary_term_bank_v3::{
+    Definition, DetailedDefinition, DictionaryTermBankV3Row, HtmlDefinition,
+};
+use crate::util::dict::{Importer, TERM_BATCH_SIZE};
+use crate::util::progress::{ImportPhase, ImportProgress};
+use anyhow::{Context, bail};
+use encoding_rs::{Encoding, UTF_8, UTF_16LE};
+use flate2::read::ZlibDecoder;
+use quick_xml::Reader;
+use quick_xml::events::Event;
+use ripemd::{Digest, Ripemd128};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+
+/// Records that redirect to the records of another key start with this.
+const LINK_PREFIX: &str = "@@@LINK=";
+
+/// Links followed before giving up on a chain of them.
+const MAX_LINK_DEPTH: usize = 5;
+
+/// Imports an MDict dictionary, reporting progress in keys. Records are HTML stored as is.
+pub struct MdictImporter {
+    title: String,
+    mdx: MdictFile,
+    /// Resource files, the .mdd next to the .mdx followed by the numbered ones.
+    mdd: Vec<PathBuf>,
+    position: usize,
+    /// Keys whose record is a link, along with the key they link to.
+    links: Vec<(usize, String)>,
+    links_resolved: bool,
+}
+
+impl MdictImporter {
+    /// Opens the .mdx file, resources are read from the .mdd files next to it when extracting.
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let mdx = MdictFile::open(path, false)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let title = match mdx.header.get("Title").map(|title| title.trim()) {
+            // Left as is by MdxBuilder when no title is given
+            Some(title) if !title.is_empty() && !title.starts_with("Title (") => title.to_string(),
+            _ => path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+        };
+
+        let mut mdd = vec![path.with_extension("mdd")];
+        while mdd.last().is_some_and(|path| path.exists()) {
+            mdd.push(path.with_extension(format!("{}.mdd", mdd.len())));
+        }
+        mdd.pop();
+
+        Ok(Self {
+            title,
+            mdx,
+            mdd,
+            position: 0,
+            links: vec![],
+            links_resolved: false,
+        })
+    }
+
+    fn row(&self, key: usize, html: String, sequence: usize) -> DictionaryTermBankV3Row {
+        DictionaryTermBankV3Row(
+            self.mdx.keys[key].1.clone(),
+            String::new(),
+            Some(String::new()),
+            String::new(),
+            0.0,
+            vec![Definition::Detailed(Box::new(DetailedDefinition::Html(
+                HtmlDefinition { html },
+            )))],
+            sequence as i32,
+            String::new(),
+        )
+    }
+
+    /// Rows for the keys that link to others, with the records of the key they lead to.
+    fn link_rows(&mut self) -> anyhow::Result<Vec<DictionaryTermBankV3Row>> {
+        let links: HashMap<usize, String> = self.links.drain(..).collect();
+        let mut records: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (index, (_, key)) in self.mdx.keys.iter().enumerate() {
+            records.entry(key).or_default().push(index);
+        }
+
+        let mut targets = vec![];
+        for (&link, target) in &links {
+            let mut target = target.as_str();
+            for _ in 0..MAX_LINK_DEPTH {
+                let Some(indexes) = records.get(target) else {
+                    break;
+                };
+                match indexes.iter().find_map(|index| links.get(index)) {
+                    Some(next) => target = next,
+                    None => {
+                        targets.extend(indexes.iter().map(|index| (link, *index)));
+                        break;
+                    }
+                }
+            }
+        }
+        targets.sort();
+
+        let mut rows = Vec::with_capacity(targets.len());
+        for (link, index) in targets {
+            let html = self.mdx.text(index)?;
+            rows.push(self.row(link, html, index));
+        }
+        Ok(rows)
+    }
+}
+
+impl Importer for MdictImporter {
+    fn index(&self) -> DictionaryIndex {
+        let header = |key: &str| {
+            self.mdx
+                .header
+                .get(key)
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+                .map(String::from)
+        };
+        DictionaryIndex {
+            title: self.title.clone(),
+            revision: header("CreationDate").unwrap_or_default(),
+            minimum_yomitan_version: None,
+            // Links share the sequence of the record they lead to
+            sequenced: true,
+            format: Some(3),
+            version: None,
+            author: None,
+            is_updatable: None,
+            index_url: None,
+            download_url: None,
+            url: None,
+            description: header("Description"),
+            attribution: None,
+            source_language: None,
+            target_language: None,
+            frequency_mode: None,
+            tag_meta: None,
+        }
+    }
+
+    fn length(&self) -> u64 {
+        self.mdx.keys.len() as u64
+    }
+
+    fn next_terms(
+        &mut self,
+        progress: &dyn ImportProgress,
+    ) -> anyhow::Result<Option<Vec<DictionaryTermBankV3Row>>> {
+        let end = (self.position + TERM_BATCH_SIZE).min(self.mdx.keys.len());
+        if self.position == end {
+            if self.links_resolved {
+                return Ok(None);
+            }
+            self.links_resolved = true;
+            return Ok(Some(self.link_rows()?));
+        }
+
+        let mut rows = Vec::with_capacity(end - self.position);
+        for index in self.position..end {
+            let html = self.mdx.text(index)?;
+            match html.strip_prefix(LINK_PREFIX) {
+                Some(target) => self.links.push((index, target.trim().to_string())),
+                None => rows.push(self.row(index, html, index)),
+            }
+        }
+        progress.inc(ImportPhase::Parse, (end - self.position) as u64);
+        self.position = end;
+        Ok(Some(rows))
+    }
+
+    fn extract_files(
+        &mut self,
+        target: &Path,
+        progress: &dyn ImportProgress,
+    ) -> anyhow::Result<()> {
+        let mut files = vec![];
+        for path in &self.mdd {
+            let file = MdictFile::open(path, true)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            files.push(file);
+        }
+        if files.is_empty() {
+            return Ok(());
+        }
+
+        let length = files.iter().map(|file| file.keys.len() as u64).sum();
+        progress.start(ImportPhase::Copy, length);
+        for file in &mut files {
+            for index in 0..file.keys.len() {
+                if progress.is_cancelled() {
+                    bail!("Import cancelled");
+                }
+                progress.inc(ImportPhase::Copy, 1);
+                let key = file.keys[index].1.clone();
+                let Some(relative_path) = resource_path(&key) else {
+                    bail!("Invalid resource name: {}", key);
+                };
+                progress.set_message(&key);
+
+                let target_path = target.join(relative_path);
+                if let Some(parent) = target_path.parent() {
+                    fs::create_dir_all(parent).context("Failed to create target subdirectory")?;
+                }
+                fs::write(&target_path, file.record(index)?).context("Failed to write file")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An .mdx or .mdd file with its keys read, records are read when asked for.
+struct MdictFile {
+    file: File,
+    header: HashMap<String, String>,
+    encoding: &'static Encoding,
+    /// Keys along with the offset of their record once every record block is decompressed.
+    keys: Vec<(u64, String)>,
+    record_blocks: Vec<RecordBlock>,
+    /// Last record block read, keys are stored in the order of their records.
+    cache: Option<(usize, Vec<u8>)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RecordBlock {
+    file_offset: u64,
+    compressed_size: u64,
+    /// Offset of the first record of the block once every record block is decompressed.
+    offset: u64,
+    decompressed_size: u64,
+}
+
+impl MdictFile {
+    /// Reads the header and the keys. Keys of `resources` files are always UTF-16.
+    fn open(path: &Path, resources: bool) -> anyhow::Result<Self> {
+        let mut file = File::open(path)?;
+
+        let header_size = read_number(&mut file, 4)?;
+        let header = read_bytes(&mut file, header_size)?;
+        // Followed by the checksum of the header
+        file.seek(SeekFrom::Current(4))?;
+        let (header, _) = UTF_16LE.decode_without_bom_handling(&header);
+        let header = parse_header(header.trim_end_matches('\0'))?;
+
+        let version: f32 = header
+            .get("GeneratedByEngineVersion")
+            .and_then(|version| version.trim().parse().ok())
+            .unwrap_or(2.0);
+        if version >= 3.0 {
+            bail!("MDict {} files are not supported", version);
+        }
+        let number_width = if version >= 2.0 { 8 } else { 4 };
+        let encrypted: u8 = match header.get("Encrypted").map(String::as_str) {
+            Some("Yes") => 1,
+            Some(encrypted) => encrypted.parse().unwrap_or(0),
+            None => 0,
+        };
+        if encrypted & 1 != 0 {
+            bail!("Encrypted MDict files need a registration key, which is not supported");
+        }
+        let encoding = match header.get("Encoding") {
+            _ if resources => UTF_16LE,
+            Some(label) => Encoding::for_label(label.trim().as_bytes()).unwrap_or(UTF_8),
+            None => UTF_8,
+        };
+
+        let (key_block_info, key_blocks_size) = if number_width == 8 {
+            let [_, _, info_decompressed_size, info_size, blocks_size] =
+                read_numbers(&mut file, number_width)?;
+            // Followed by the checksum of the numbers
+            file.seek(SeekFrom::Current(4))?;
+            let mut info = read_bytes(&mut file, info_size)?;
+            if encrypted & 2 != 0 {
+                info = decrypt_key_block_info(&info)?;
+            }
+            (decompress(&info, info_decompressed_size)?, blocks_size)
+        } else {
+            let [_, _, info_size, blocks_size] = read_numbers(&mut file, number_width)?;
+            (read_bytes(&mut file, info_size)?, blocks_size)
+        };
+        let key_block_sizes =
+            parse_key_block_info(&key_block_info, number_width, encoding == UTF_16LE)?;
+
+        let key_blocks = read_bytes(&mut file, key_blocks_size)?;
+        let mut keys = vec![];
+        let mut start: usize = 0;
+        for (compressed_size, decompressed_size) in key_block_sizes {
+            let end = start.checked_add(compressed_size);
+            let Some(block) = end.and_then(|end| key_blocks.get(start..end)) else {
+                bail!("Key block is out of bounds");
+            };
+            let block = decompress(block, decompressed_size)?;
+            split_keys(&block, number_width, encoding, &mut keys)?;
+            // Within the key blocks, so this can't overflow
+            start += compressed_size;
+        }
+
+        let [block_count, _, info_size, _] = read_numbers(&mut file, number_width)?;
+        let mut file_offset = file
+            .stream_position()?
+            .checked_add(info_size as u64)
+            .context("Record block info is larger than a file can be")?;
+        let mut offset = 0;
+        let mut record_blocks = vec![];
+        for _ in 0..block_count {
+            let compressed_size = read_number(&mut file, number_width)? as u64;
+            let decompressed_size = read_number(&mut file, number_width)? as u64;
+            record_blocks.push(RecordBlock {
+                file_offset,
+                compressed_size,
+                offset,
+                decompressed_size,
+            });
+            file_offset = file_offset
+                .checked_add(compressed_size)
+                .context("Record blocks are larger than a file can be")?;
+            offset = offset
+                .checked_add(decompressed_size)
+                .context("Record blocks are larger than a file can be")?;
+        }
+
+        Ok(Self {
+            file,
+            header,
+            encoding,
+            keys,
+            record_blocks,
+            cache: None,
+        })
+    }
+
+    /// Raw record of the key at `index`, which ends where the record of the next key starts.
+    fn record(&mut self, index: usize) -> anyhow::Result<&[u8]> {
+        let (start, key) = &self.keys[index];
+        let start = *start;
+        let position = self
+            .record_blocks
+            .partition_point(|block| block.offset + block.decompressed_size <= start);
+        let Some(block) = self.record_blocks.get(position).copied() else {
+            bail!("Record of {} is out of bounds", key);
+        };
+
+        let data = match self.cache.take() {
+            Some((cached, data)) if cached == position => data,
+            _ => {
+                self.file.seek(SeekFrom::Start(block.file_offset))?;
+                let compressed = read_bytes(&mut self.file, block.compressed_size as usize)?;
+                decompress(&compressed, block.decompressed_size as usize)?
+            }
+        };
+        let data = &self.cache.insert((position, data)).1;
+        let block_end = block.offset + block.decompressed_size;
+        let end = match self.keys.get(index + 1) {
+            Some((next, _)) => (*next).clamp(start, block_end),
+            None => block_end,
+        };
+        let range = (start - block.offset) as usize..(end - block.offset) as usize;
+        data.get(range).context("Record is larger than its block")
+    }
+
+    /// Record of the key at `index` as text.
+    fn text(&mut self, index: usize) -> anyhow::Result<String> {
+        let encoding = self.encoding;
+        let record = self.record(index)?;
+        let (text, _) = encoding.decode_without_bom_handling(record);
+        Ok(text.trim_end_matches('\0').trim().to_string())
+    }
+}
+
+/// Reads the attributes of the `<Dictionary>` or `<Library_Data>` element.
+fn parse_header(header: &str) -> anyhow::Result<HashMap<String, String>> {
+    let mut reader = Reader::from_str(header);
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) | Event::Empty(element) => {
+                let mut attributes = HashMap::new();
+                for attribute in element.attributes().with_checks(false) {
+                    let attribute = attribute?;
+                    let value = match attribute.unescape_value() {
+                        Ok(value) => value.into_owned(),
+                        // Descriptions are often HTML with unescaped entities
+                        Err(_) => String::from_utf8_lossy(&attribute.value).into_owned(),
+                    };
+                    let key = String::from_utf8_lossy(attribute.key.as_ref()).into_owned();
+                    attributes.insert(key, value);
+                }
+                return Ok(attributes);
+            }
+            Event::Eof => bail!("Invalid MDict header"),
+            _ => {}
+        }
+    }
+}
+
+/// Reads `size` bytes. Sizes come from the file itself, so they are checked against what is
+/// left of it before allocating.
+fn read_bytes(file: &mut File, size: usize) -> anyhow::Result<Vec<u8>> {
+    let remaining = file.metadata()?.len().saturating_sub(file.stream_position()?);
+    if size as u64 > remaining {
+        bail!("Unexpected end of file");
+    }
+    let mut bytes = Vec::with_capacity(size);
+    file.take(size as u64)
+        .read_to_end(&mut bytes)
+        .context("Failed to read file")?;
+    if bytes.len() != size {
+        bail!("Unexpected end of file");
+    }
+    Ok(bytes)
+}
+
+/// Reads a big endian number of `width` bytes.
+fn read_number(file: &mut File, width: usize) -> anyhow::Result<usize> {
+    let bytes = read_bytes(file, width)?;
+    Ok(parse_number(&bytes))
+}
+
+fn read_numbers<const N: usize>(file: &mut File, width: usize) -> anyhow::Result<[usize; N]> {
+    let mut numbers = [0; N];
+    for number in &mut numbers {
+        *number = read_number(file, width)?;
+    }
+    Ok(numbers)
+}
+
+fn parse_number(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .fold(0usize, |value, byte| (value << 8) | *byte as usize)
+}
+
+/// Splits a big endian number of `width` bytes off the start of `data`.
+fn split_number(data: &mut &[u8], width: usize) -> anyhow::Result<usize> {
+    if data.len() < width {
+        bail!("Unexpected end of block");
+    }
+    let (number, rest) = data.split_at(width);
+    *data = rest;
+    Ok(parse_number(number))
+}
+
+/// Blocks start with their compression type and checksum. LZO is only found in old files.
+/// Blocks that don't decompress to `size` bytes are refused, without inflating more than that.
+fn decompress(block: &[u8], size: usize) -> anyhow::Result<Vec<u8>> {
+    let Some((kind, data)) = block.split_first_chunk::<4>() else {
+        bail!("Unexpected end of block");
+    };
+    let Some(data) = data.get(4..) else {
+        bail!("Unexpected end of block");
+    };
+    let decompressed = match u32::from_le_bytes(*kind) {
+        0 => data.to_vec(),
+        1 => bail!("LZO compressed MDict files are not supported"),
+        2 => {
+            let mut decompressed = vec![];
+            // One byte more than the declared size tells a larger block apart
+            ZlibDecoder::new(data)
+                .take((size as u64).saturating_add(1))
+                .read_to_end(&mut decompressed)
+                .context("Failed to decompress block")?;
+            decompressed
+        }
+        kind => bail!("Unknown MDict block compression {}", kind),
+    };
+    if decompressed.len() != size {
+        bail!(
+            "Block decompressed to {} bytes instead of {}",
+            decompressed.len(),
+            size
+        );
+    }
+    Ok(decompressed)
+}
+
+/// The key block info of version 2 files with `Encrypted="2"` is scrambled with a key derived
+/// from its checksum.
+fn decrypt_key_block_info(info: &[u8]) -> anyhow::Result<Vec<u8>> {
+    if info.len() < 8 {
+        bail!("Unexpected end of block");
+    }
+    let mut hasher = Ripemd128::new();
+    hasher.update(&info[4..8]);
+    hasher.update(0x3695u32.to_le_bytes());
+    let key = hasher.finalize();
+
+    let mut decrypted = info[..8].to_vec();
+    let mut previous = 0x36;
+    for (i, byte) in info[8..].iter().enumerate() {
+        decrypted.push(byte.rotate_left(4) ^ previous ^ i as u8 ^ key[i % key.len()]);
+        previous = *byte;
+    }
+    Ok(decrypted)
+}
+
+/// Returns the compressed and decompressed size of every key block. Each block is described by
+/// its number of keys, its first and last key, then its sizes.
+fn parse_key_block_info(
+    mut info: &[u8],
+    number_width: usize,
+    utf16: bool,
+) -> anyhow::Result<Vec<(usize, usize)>> {
+    // Version 1 files have shorter key sizes and no terminator after the keys
+    let (size_width, terminator) = if number_width == 8 { (2, 1) } else { (1, 0) };
+    let char_width = if utf16 { 2 } else { 1 };
+    let mut sizes = vec![];
+    while !info.is_empty() {
+        split_number(&mut info, number_width)?;
+        for _ in 0..2 {
+            let key_size = split_number(&mut info, size_width)?;
+            let Some(rest) = info.get((key_size + terminator) * char_width..) else {
+                bail!("Unexpected end of block");
+            };
+            info = rest;
+        }
+        let compressed_size = split_number(&mut info, number_width)?;
+        sizes.push((compressed_size, split_number(&mut info, number_width)?));
+    }
+    Ok(sizes)
+}
+
+/// Reads the offset of the record and the NUL terminated text of every key in the block.
+fn split_keys(
+    mut block: &[u8],
+    number_width: usize,
+    encoding: &'static Encoding,
+    keys: &mut Vec<(u64, String)>,
+) -> anyhow::Result<()> {
+    let char_width = if encoding == UTF_16LE { 2 } else { 1 };
+    while !block.is_empty() {
+        let offset = split_number(&mut block, number_width)? as u64;
+        let end = block
+            .chunks(char_width)
+            .position(|c| c.iter().all(|b| *b == 0))
+            .map_or(block.len(), |i| i * char_width);
+        let (key, _) = encoding.decode_without_bom_handling(&block[..end]);
+        keys.push((offset, key.trim().to_string()));
+        block = block.get(end + char_width..).unwrap_or_default();
+    }
+    Ok(())
+}
+
+/// Resource keys are Windows style paths such as `\images\a.png`. Returns `None` for paths that
+/// would leave the directory.
+fn resource_path(key: &str) -> Option<PathBuf> {
+    let path = PathBuf::from(key.replace('\\', "/").trim_start_matches('/'));
+    let mut components = path.components().peekable();
+    let valid = components.peek().is_some()
+        && components.all(|component| matches!(component, Component::Normal(_)));
+    valid.then_some(path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::temp_dir;
+    use flate2::Compression;
+    use flate2::write::ZlibEncoder;
+    use std::io::Write;
+
+    #[test]
+    fn should_split_keys() {
+        let mut block = 0u64.to_be_bytes().to_vec();
+        block.extend("読む\0".as_bytes());
+        block.extend(12u64.to_be_bytes());
+        block.extend(" 見る \0".as_bytes());
+        let mut keys = vec![];
+        split_keys(&block, 8, UTF_8, &mut keys).unwrap();
+        assert_eq!(keys, [(0, "読む".to_string()), (12, "見る".to_string())]);
+
+        let mut block = 5u32.to_be_bytes().to_vec();
+        block.extend([b'\\', 0, b'a', 0, 0, 0]);
+        let mut keys = vec![];
+        split_keys(&block, 4, UTF_16LE, &mut keys).unwrap();
+        assert_eq!(keys, [(5, "\\a".to_string())]);
+    }
+
+    #[test]
+    fn should_parse_key_block_info() {
+        let mut info = vec![];
+        for (first, last, size) in [("あ", "い", 100u64), ("う", "え", 50)] {
+            info.extend(2u64.to_be_bytes());
+            for key in [first, last] {
+                info.extend((key.len() as u16).to_be_bytes());
+                info.extend(key.as_bytes());
+                info.push(0);
+            }
+            info.extend(size.to_be_bytes());
+            info.extend((size * 2).to_be_bytes());
+        }
+        assert_eq!(
+            parse_key_block_info(&info, 8, false).unwrap(),
+            [(100, 200), (50, 100)]
+        );
+        assert!(parse_key_block_info(&info[..info.len() - 1], 8, false).is_err());
+    }
+
+    #[test]
+    fn should_only_inflate_the_declared_size() {
+        let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+        encoder.write_all(&[0; 1000]).unwrap();
+        let mut block = 2u32.to_le_bytes().to_vec();
+        block.extend([0; 4]);
+        block.extend(encoder.finish().unwrap());
+
+        assert_eq!(decompress(&block, 1000).unwrap(), [0; 1000]);
+        assert!(decompress(&block, 10).is_err());
+        assert!(decompress(&block, 2000).is_err());
+        assert_eq!(decompress(&[0, 0, 0, 0, 0, 0, 0, 0, 1], 1).unwrap(), [1]);
+    }
+
+    #[test]
+    fn should_refuse_sizes_past_the_end_of_the_file() {
+        let path = temp_dir("mdict-test").join("a.mdx");
+        fs::write(&path, [0, 0, 0, 2, 1, 2]).unwrap();
+        let mut file = File::open(&path).unwrap();
+
+        assert_eq!(read_number(&mut file, 4).unwrap(), 2);
+        assert!(read_bytes(&mut file, usize::MAX).is_err());
+        assert!(read_bytes(&mut file, 3).is_err());
+        assert_eq!(read_bytes(&mut file, 2).unwrap(), [1, 2]);
+    }
+
+    #[test]
+    fn should_keep_resources_inside_the_directory() {
+        assert_eq!(
+            resource_path("\\images\\a.png"),
+            Some(PathBuf::from("images/a.png"))
+        );
+        assert_eq!(resource_path("\\..\\a.png"), None);
+        assert_eq!(resource_path("\\"), None);
+    }
+}
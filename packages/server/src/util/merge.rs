@@ -0,0 +1,409 @@
+use crate::db::query::normalize_reading;
+use crate::db::tables::{DefinitionTag, Dictionary, DictionaryEntry, MergeMode};
+use crate::schemas::dictionary_term_bank_v3::Definition;
+use crate::util::kana;
+use crate::util::tags::{TagResolver, sort_tags};
+use crate::util::translator::DictionaryEntryMatch;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// How the entries found by a lookup are returned.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ResultMode {
+    /// Every entry on its own.
+    #[default]
+    Flat,
+    /// Entries of the same word merged into one result, see [`MergedEntryMatch`].
+    Merged,
+}
+
+/// A spelling of a merged result.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Headword {
+    pub expression: String,
+    pub reading: String,
+    /// Tags of the expression without duplicates.
+    pub tags: Vec<String>,
+    /// The tags resolved to their definitions, when asked for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved_tags: Option<Vec<DefinitionTag>>,
+}
+
+/// Definitions a single dictionary gives for a merged result.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DictionaryDefinitions {
+    pub dictionary_id: i32,
+    /// Ids of the entries the definitions come from.
+    pub entry_ids: Vec<i32>,
+    pub definitions: Vec<Definition>,
+    /// Tags of the definitions without duplicates.
+    pub tags: Vec<String>,
+    /// The tags resolved to their definitions, when asked for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved_tags: Option<Vec<DefinitionTag>>,
+}
+
+/// Entries of one word merged into a single result: the entries of a sequenced dictionary that
+/// share a sequence number, along with the entries of other dictionaries spelled the same way.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergedEntryMatch {
+    /// Every spelling of the word, in the order the entries were found.
+    pub headwords: Vec<Headword>,
+    /// Ordered by the priority of the dictionaries.
+    pub definitions: Vec<DictionaryDefinitions>,
+    /// Sequence number the entries were merged by, none for an entry that stands alone.
+    pub sequence: Option<i32>,
+    /// Deinflection rules of every entry without duplicates.
+    pub rules: Vec<String>,
+    /// Highest score of the entries.
+    pub score: f32,
+    /// The text that was looked up.
+    pub source: String,
+    /// Every chain of inflections that turns the word into the source, an empty chain is an
+    /// exact match.
+    pub inflection_rule_chains: Vec<Vec<String>>,
+}
+
+/// Entries being merged, ordered by the position of the first match in them.
+struct Group {
+    order: usize,
+    key: Option<(i32, i32)>,
+    entries: Vec<DictionaryEntry>,
+    source: String,
+    inflection_rule_chains: Vec<Vec<String>>,
+}
+
+impl Group {
+    fn new(order: usize, key: Option<(i32, i32)>, m: DictionaryEntryMatch) -> Self {
+        Self {
+            order,
+            key,
+            entries: vec![m.entry],
+            source: m.source,
+            inflection_rule_chains: m.inflection_rule_chains,
+        }
+    }
+
+    fn add(&mut self, m: DictionaryEntryMatch) {
+        if m.source == self.source {
+            for chain in m.inflection_rule_chains {
+                if !self.inflection_rule_chains.contains(&chain) {
+                    self.inflection_rule_chains.push(chain);
+                }
+            }
+        }
+        self.entries.push(m.entry);
+    }
+
+    fn has_spelling(&self, entry: &DictionaryEntry) -> bool {
+        let spelling = spelling(entry);
+        self.entries.iter().any(|e| spelling == self::spelling(e))
+    }
+}
+
+fn spelling(entry: &DictionaryEntry) -> (String, String) {
+    (
+        kana::normalize(&entry.expression),
+        normalize_reading(&entry.expression, &entry.reading),
+    )
+}
+
+/// The `(dictionary_id, sequence)` an entry is merged by, if its dictionary merges entries by
+/// sequence number. Format 1 terms have -1 and unsequenced entries 0, those aren't merged.
+fn sequence_key(
+    entry: &DictionaryEntry,
+    dictionaries: &HashMap<i32, Dictionary>,
+) -> Option<(i32, i32)> {
+    let merges = dictionaries
+        .get(&entry.dictionary_id)
+        .is_some_and(|d| d.sequenced && d.settings.merge_mode == MergeMode::Merge);
+    (merges && entry.sequence > 0).then_some((entry.dictionary_id, entry.sequence))
+}
+
+/// The `(dictionary_id, sequence)` of the matches that are merged by their sequence number, the
+/// other entries with them are merged in as well.
+pub fn merged_sequences(
+    matches: &[DictionaryEntryMatch],
+    dictionaries: &HashMap<i32, Dictionary>,
+) -> Vec<(i32, i32)> {
+    let mut sequences = Vec::new();
+    for m in matches {
+        if let Some(key) = sequence_key(&m.entry, dictionaries)
+            && !sequences.contains(&key)
+        {
+            sequences.push(key);
+        }
+    }
+    sequences
+}
+
+/// Merges the matches of sequenced dictionaries by sequence number, along with `siblings`, the
+/// entries that share a sequence number with them but weren't matched. Matches of other
+/// dictionaries join a merged result with the same spelling when their merge mode allows it, or
+/// stand alone. Results keep the order of the first match in them. Tags are resolved with
+/// `tags` when given.
+pub fn merge_matches(
+    matches: Vec<DictionaryEntryMatch>,
+    siblings: Vec<DictionaryEntry>,
+    dictionaries: &HashMap<i32, Dictionary>,
+    tags: Option<&TagResolver>,
+) -> Vec<MergedEntryMatch> {
+    let mut groups: Vec<Group> = Vec::new();
+    let mut rest = Vec::new();
+    for (order, m) in matches.into_iter().enumerate() {
+        let Some(key) = sequence_key(&m.entry, dictionaries) else {
+            rest.push((order, m));
+            continue;
+        };
+        match groups.iter_mut().find(|g| g.key == Some(key)) {
+            Some(group) => group.add(m),
+            None => groups.push(Group::new(order, Some(key), m)),
+        }
+    }
+
+    for entry in siblings {
+        let key = sequence_key(&entry, dictionaries);
+        if let Some(group) = groups.iter_mut().find(|g| key.is_some() && g.key == key) {
+            group.entries.push(entry);
+        }
+    }
+
+    for (order, m) in rest {
+        let merges = dictionaries
+            .get(&m.entry.dictionary_id)
+            .is_some_and(|d| d.settings.merge_mode == MergeMode::Merge);
+        let group = groups
+            .iter_mut()
+            .find(|g| merges && g.key.is_some() && g.has_spelling(&m.entry));
+        match group {
+            Some(group) => group.add(m),
+            None => groups.push(Group::new(order, None, m)),
+        }
+    }
+
+    groups.sort_by_key(|g| g.order);
+    groups
+        .into_iter()
+        .map(|group| into_merged(group, dictionaries, tags))
+        .collect()
+}
+
+fn into_merged(
+    group: Group,
+    dictionaries: &HashMap<i32, Dictionary>,
+    tags: Option<&TagResolver>,
+) -> MergedEntryMatch {
+    let mut headwords: Vec<Headword> = Vec::new();
+    let mut definitions: Vec<DictionaryDefinitions> = Vec::new();
+    let mut rules = Vec::new();
+    let mut score = f32::MIN;
+    for entry in group.entries {
+        let i = headwords
+            .iter()
+            .position(|h| h.expression == entry.expression && h.reading == entry.reading)
+            .unwrap_or_else(|| {
+                headwords.push(Headword {
+                    expression: entry.expression.clone(),
+                    reading: entry.reading.clone(),
+                    tags: Vec::new(),
+                    resolved_tags: tags.map(|_| Vec::new()),
+                });
+                headwords.len() - 1
+            });
+        push_unique(&mut headwords[i].tags, &entry.expression_tags);
+        if let (Some(tags), Some(resolved)) = (tags, &mut headwords[i].resolved_tags) {
+            push_resolved(
+                resolved,
+                tags.resolve(entry.dictionary_id, &entry.expression_tags),
+            );
+        }
+        push_unique(&mut rules, &entry.rules);
+        score = score.max(entry.score);
+
+        let i = definitions
+            .iter()
+            .position(|d| d.dictionary_id == entry.dictionary_id)
+            .unwrap_or_else(|| {
+                definitions.push(DictionaryDefinitions {
+                    dictionary_id: entry.dictionary_id,
+                    entry_ids: Vec::new(),
+                    definitions: Vec::new(),
+                    tags: Vec::new(),
+                    resolved_tags: tags.map(|_| Vec::new()),
+                });
+                definitions.len() - 1
+            });
+        let group = &mut definitions[i];
+        group.entry_ids.push(entry.id);
+        push_unique(&mut group.tags, &entry.definition_tags);
+        if let (Some(tags), Some(resolved)) = (tags, &mut group.resolved_tags) {
+            push_resolved(
+                resolved,
+                tags.resolve(entry.dictionary_id, &entry.definition_tags),
+            );
+        }
+        group.definitions.extend(entry.definitions);
+    }
+    definitions.sort_by_key(|d| {
+        Reverse(
+            dictionaries
+                .get(&d.dictionary_id)
+                .map_or(0, |d| d.settings.priority),
+        )
+    });
+
+    MergedEntryMatch {
+        headwords,
+        definitions,
+        sequence: group.key.map(|(_, sequence)| sequence),
+        rules,
+        score,
+        source: group.source,
+        inflection_rule_chains: group.inflection_rule_chains,
+    }
+}
+
+/// Adds the space-separated `tags` that aren't in `target` yet.
+fn push_unique(target: &mut Vec<String>, tags: &str) {
+    for tag in tags.split_whitespace() {
+        if !target.iter().any(|t| t == tag) {
+            target.push(tag.to_string());
+        }
+    }
+}
+
+/// Adds the `tags` that aren't in `target` yet, keeping it sorted.
+fn push_resolved(target: &mut Vec<DefinitionTag>, tags: Vec<DefinitionTag>) {
+    for tag in tags {
+        let exists = target
+            .iter()
+            .any(|t| t.dictionary_id == tag.dictionary_id && t.name == tag.name);
+        if !exists {
+            target.push(tag);
+        }
+    }
+    sort_tags(target);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util;
+    use chrono::Utc;
+
+    fn dictionary(id: i32, sequenced: bool, priority: i32, merge_mode: MergeMode) -> Dictionary {
+        let mut dictionary = test_util::dictionary(id);
+        dictionary.sequenced = sequenced;
+        dictionary.settings.priority = priority;
+        dictionary.settings.merge_mode = merge_mode;
+        dictionary
+    }
+
+    fn entry(
+        id: i32,
+        dictionary_id: i32,
+        expression: &str,
+        reading: &str,
+        sequence: i32,
+    ) -> DictionaryEntry {
+        DictionaryEntry {
+            id,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            dictionary_id,
+            expression: expression.to_string(),
+            reading: reading.to_string(),
+            definitions: vec![Definition::Text(format!("definition {}", id))],
+            rules: "v1".to_string(),
+            score: id as f32,
+            sequence,
+            definition_tags: "v1 common".to_string(),
+            expression_tags: String::new(),
+        }
+    }
+
+    fn matched(entry: DictionaryEntry) -> DictionaryEntryMatch {
+        DictionaryEntryMatch {
+            entry,
+            source: "たべる".to_string(),
+            inflection_rule_chains: vec![vec![]],
+            resolved_tags: None,
+        }
+    }
+
+    #[test]
+    fn should_merge_entries_by_sequence() {
+        let dictionaries: HashMap<i32, Dictionary> = [
+            dictionary(1, true, 0, MergeMode::Merge),
+            dictionary(2, false, 10, MergeMode::Merge),
+            dictionary(3, false, 0, MergeMode::Separate),
+        ]
+        .into_iter()
+        .map(|d| (d.id, d))
+        .collect();
+
+        let matches = vec![
+            matched(entry(1, 1, "食べる", "たべる", 100)),
+            matched(entry(3, 2, "食べる", "たべる", 0)),
+            matched(entry(4, 2, "食う", "くう", 0)),
+            matched(entry(5, 3, "食べる", "たべる", 0)),
+        ];
+        let siblings = vec![entry(2, 1, "たべる", "", 100)];
+        let merged = merge_matches(matches, siblings, &dictionaries, None);
+
+        assert_eq!(merged.len(), 3);
+        let word = &merged[0];
+        assert_eq!(word.sequence, Some(100));
+        let headwords: Vec<_> = word
+            .headwords
+            .iter()
+            .map(|h| h.expression.as_str())
+            .collect();
+        assert_eq!(headwords, ["食べる", "たべる"]);
+        // The dictionary with the higher priority comes first
+        let ids: Vec<_> = word
+            .definitions
+            .iter()
+            .map(|d| d.entry_ids.clone())
+            .collect();
+        assert_eq!(ids, [vec![3], vec![1, 2]]);
+        assert_eq!(word.definitions[1].tags, ["v1", "common"]);
+        assert_eq!(word.rules, ["v1"]);
+        assert!(word.headwords[0].resolved_tags.is_none());
+
+        assert_eq!(merged[1].sequence, None);
+        assert_eq!(merged[1].headwords[0].expression, "食う");
+        // Dictionaries set to separate are never merged
+        assert_eq!(merged[2].definitions[0].entry_ids, [5]);
+    }
+
+    #[test]
+    fn should_not_merge_entries_without_a_sequence() {
+        let dictionaries: HashMap<i32, Dictionary> = [dictionary(1, true, 0, MergeMode::Merge)]
+            .into_iter()
+            .map(|d| (d.id, d))
+            .collect();
+
+        let matches = vec![
+            matched(entry(1, 1, "食べる", "たべる", 0)),
+            matched(entry(2, 1, "食う", "くう", 0)),
+            matched(entry(3, 1, "喰う", "くう", -1)),
+            matched(entry(4, 1, "飲む", "のむ", -1)),
+        ];
+        assert!(merged_sequences(&matches, &dictionaries).is_empty());
+
+        let siblings = vec![entry(5, 1, "たべる", "", 0)];
+        let merged = merge_matches(matches, siblings, &dictionaries, None);
+        let ids: Vec<_> = merged
+            .iter()
+            .map(|m| m.definitions[0].entry_ids.clone())
+            .collect();
+        assert_eq!(ids, [vec![1], vec![2], vec![3], vec![4]]);
+        assert!(merged.iter().all(|m| m.sequence.is_none()));
+    }
+}
@@ -0,0 +1,45 @@
+use crate::db::tables::Profile;
+use crate::util::{response::ErrorResponse, state::AppState};
+use axum::{
+    extract::{FromRequestParts, Query},
+    http::{StatusCode, request::Parts},
+};
+use serde::Deserialize;
+
+/// Header naming the profile of a request, the `profile` query param takes precedence.
+pub const PROFILE_HEADER: &str = "x-profile";
+
+/// The profile selected by a request, the default profile when it names none.
+pub struct SelectedProfile(pub Profile);
+
+#[derive(Deserialize)]
+struct ProfileQueryParams {
+    profile: Option<String>,
+}
+
+impl FromRequestParts<AppState> for SelectedProfile {
+    type Rejection = ErrorResponse;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let from_query = Query::<ProfileQueryParams>::try_from_uri(&parts.uri)
+            .ok()
+            .and_then(|Query(params)| params.profile);
+        let from_header = parts
+            .headers
+            .get(PROFILE_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let name = from_query.or(from_header);
+
+        match state.db.query_selected_profile(name.as_deref()).await? {
+            Some(profile) => Ok(Self(profile)),
+            None => Err(ErrorResponse {
+                error: anyhow::anyhow!("Profile not found"),
+                status_code: StatusCode::NOT_FOUND,
+            }),
+        }
+    }
+}
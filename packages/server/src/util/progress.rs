@@ -1,4 +1,7 @@
-use indicatif::{ProgressBar, ProgressStyle};
+use console::style;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use serde::Serialize;
+use std::sync::Mutex;
 
 pub fn get_progress_bar(length: u64) -> ProgressBar {
     let pb = ProgressBar::new(length);
@@ -14,3 +17,90 @@ pub fn get_style() -> ProgressStyle {
         .unwrap()
         .progress_chars("#>-")
 }
+
+/// Steps of a dictionary import, in order. The first three overlap, since banks are inserted
+/// while the next ones are read and parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportPhase {
+    /// Reading the banks out of the archive, counted in banks. Formats read by an
+    /// [`Importer`](crate::util::dict::Importer) are decompressed as they are parsed and skip it.
+    Extract,
+    /// Parsing the banks, counted in banks, or in the unit of
+    /// [`Importer::length`](crate::util::dict::Importer::length) for other formats.
+    Parse,
+    /// Inserting the parsed rows, counted in banks, or in batches of terms for other formats,
+    /// whose number isn't known in advance.
+    Insert,
+    /// Copying images and other files out of the archive, counted in files.
+    Copy,
+}
+
+impl ImportPhase {
+    fn step(self) -> usize {
+        self as usize + 1
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ImportPhase::Extract => "Extracting",
+            ImportPhase::Parse => "Parsing",
+            ImportPhase::Insert => "Inserting",
+            ImportPhase::Copy => "Copying files",
+        }
+    }
+}
+
+/// Receives the progress of a dictionary import.
+pub trait ImportProgress: Send + Sync {
+    /// Starts counting `phase` up to `length`, 0 when the length isn't known.
+    fn start(&self, phase: ImportPhase, length: u64);
+    fn set_message(&self, message: &str);
+    fn inc(&self, phase: ImportPhase, delta: u64);
+    fn finish(&self);
+    /// Checked between banks and files, the import stops with an error once this is true.
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+}
+
+/// Prints each phase and shows a progress bar for each of them in the terminal.
+#[derive(Default)]
+pub struct TerminalProgress {
+    bars: MultiProgress,
+    phases: Mutex<Vec<(ImportPhase, ProgressBar)>>,
+}
+
+impl ImportProgress for TerminalProgress {
+    fn start(&self, phase: ImportPhase, length: u64) {
+        let step = format!("[{}/{}]", phase.step(), ImportPhase::Copy.step());
+        let line = format!("{} {}...", style(step).bold().dim(), phase.label());
+        // Printed above the bars, which aren't drawn when not in a terminal
+        if self.bars.is_hidden() {
+            println!("{}", line);
+        } else {
+            let _ = self.bars.println(line);
+        }
+        let pb = self.bars.add(get_progress_bar(length));
+        self.phases.lock().unwrap().push((phase, pb));
+    }
+
+    fn set_message(&self, message: &str) {
+        if let Some((_, pb)) = self.phases.lock().unwrap().last() {
+            pb.set_message(message.to_string());
+        }
+    }
+
+    fn inc(&self, phase: ImportPhase, delta: u64) {
+        let phases = self.phases.lock().unwrap();
+        if let Some((_, pb)) = phases.iter().find(|(started, _)| *started == phase) {
+            pb.inc(delta);
+        }
+    }
+
+    fn finish(&self) {
+        for (_, pb) in self.phases.lock().unwrap().drain(..) {
+            pb.finish_and_clear();
+        }
+    }
+}
@@ -0,0 +1,360 @@
+use crate::db::Db;
+use crate::db::query::TermFrequency;
+use crate::util::tags::{TagResolver, tag_names};
+use crate::util::translator::DictionaryEntryMatch;
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// A criterion lookup results are sorted by, each puts the preferred results first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    /// Longest matched text first.
+    Length,
+    /// Exact matches before deinflected ones.
+    Exact,
+    /// Most common first, by the frequency dictionaries in order of priority. Entries a
+    /// dictionary has no frequency for come after the ones it has.
+    Frequency,
+    /// Entries of dictionaries with a higher priority first.
+    Priority,
+    /// Highest score of the entry first.
+    Score,
+    /// Highest sum of the scores of the entry's tags first, from tag definitions or the
+    /// `tag_meta` of the dictionary.
+    Tags,
+}
+
+impl SortKey {
+    const ALL: [SortKey; 6] = [
+        SortKey::Length,
+        SortKey::Exact,
+        SortKey::Frequency,
+        SortKey::Priority,
+        SortKey::Score,
+        SortKey::Tags,
+    ];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            SortKey::Length => "length",
+            SortKey::Exact => "exact",
+            SortKey::Frequency => "frequency",
+            SortKey::Priority => "priority",
+            SortKey::Score => "score",
+            SortKey::Tags => "tags",
+        }
+    }
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SortKey::ALL
+            .into_iter()
+            .find(|key| key.as_str() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = SortKey::ALL.iter().map(|key| key.as_str()).collect();
+                format!(
+                    "unknown sort key `{}`, expected one of {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Sort keys applied in order, each one breaking the ties of the ones before it. Written as a
+/// comma-separated list such as `frequency,priority`, results the keys can't tell apart keep
+/// the order they were found in.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Ranking(Vec<SortKey>);
+
+impl Default for Ranking {
+    /// Matches of the whole text first, then the most common words and readings.
+    fn default() -> Self {
+        Self(SortKey::ALL.to_vec())
+    }
+}
+
+impl FromStr for Ranking {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(SortKey::from_str)
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+impl TryFrom<String> for Ranking {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for Ranking {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<_> = self.0.iter().map(|key| key.as_str()).collect();
+        f.write_str(&names.join(","))
+    }
+}
+
+/// What a match is ranked by.
+#[derive(Debug, Default)]
+struct Facts {
+    length: usize,
+    exact: bool,
+    /// Rank in each frequency dictionary, lower is more common.
+    frequencies: Vec<Option<f64>>,
+    priority: i32,
+    score: f32,
+    tag_score: f32,
+}
+
+impl Ranking {
+    fn uses(&self, key: SortKey) -> bool {
+        self.0.contains(&key)
+    }
+
+    /// Sorts each list of matches on its own, with the dictionaries and tags enabled for the
+    /// profile. What the matches are ranked by is queried once for every list.
+    pub async fn sort(
+        &self,
+        db: &Db,
+        lists: &mut [Vec<DictionaryEntryMatch>],
+        profile_id: i32,
+    ) -> anyhow::Result<()> {
+        if self.0.is_empty() || lists.iter().all(|matches| matches.len() < 2) {
+            return Ok(());
+        }
+        let matches = || lists.iter().flatten();
+
+        let frequencies = if self.uses(SortKey::Frequency) {
+            let mut expressions: Vec<String> =
+                matches().map(|m| m.entry.expression.clone()).collect();
+            expressions.sort();
+            expressions.dedup();
+            Frequencies::new(db.query_term_frequencies(&expressions, profile_id).await?)
+        } else {
+            Frequencies::default()
+        };
+        let priorities: HashMap<i32, i32> = if self.uses(SortKey::Priority) {
+            db.query_dictionaries(profile_id)
+                .await?
+                .into_iter()
+                .map(|d| (d.id, d.settings.priority))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+        let tags = if self.uses(SortKey::Tags) {
+            TagResolver::load(db, matches().map(|m| &m.entry), profile_id).await?
+        } else {
+            TagResolver::default()
+        };
+
+        for matches in lists {
+            let mut ranked: Vec<(Facts, DictionaryEntryMatch)> = matches
+                .drain(..)
+                .map(|m| {
+                    let entry = &m.entry;
+                    let facts = Facts {
+                        length: m.source.chars().count(),
+                        exact: m.inflection_rule_chains.iter().any(Vec::is_empty),
+                        frequencies: frequencies.of(&entry.expression, &entry.reading),
+                        priority: priorities.get(&entry.dictionary_id).copied().unwrap_or(0),
+                        score: entry.score,
+                        tag_score: tag_names(entry)
+                            .filter_map(|name| tags.get(entry.dictionary_id, name))
+                            .map(|tag| tag.score)
+                            .sum(),
+                    };
+                    (facts, m)
+                })
+                .collect();
+            ranked.sort_by(|(a, _), (b, _)| self.compare(a, b));
+            matches.extend(ranked.into_iter().map(|(_, m)| m));
+        }
+        Ok(())
+    }
+
+    fn compare(&self, a: &Facts, b: &Facts) -> Ordering {
+        self.0
+            .iter()
+            .map(|key| match key {
+                SortKey::Length => b.length.cmp(&a.length),
+                SortKey::Exact => b.exact.cmp(&a.exact),
+                SortKey::Frequency => compare_frequencies(&a.frequencies, &b.frequencies),
+                SortKey::Priority => b.priority.cmp(&a.priority),
+                SortKey::Score => b.score.total_cmp(&a.score),
+                SortKey::Tags => b.tag_score.total_cmp(&a.tag_score),
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+/// The first dictionary that ranks one entry and not the other, or ranks it as more common,
+/// decides.
+fn compare_frequencies(a: &[Option<f64>], b: &[Option<f64>]) -> Ordering {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| match (a, b) {
+            (Some(a), Some(b)) => a.total_cmp(b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Frequencies of the matched expressions, by frequency dictionary.
+#[derive(Default)]
+struct Frequencies {
+    /// Dictionary ids in order of priority.
+    dictionaries: Vec<i32>,
+    /// Frequencies by dictionary and expression, holding ranks in place of counts.
+    ranks: HashMap<(i32, String), Vec<TermFrequency>>,
+}
+
+impl Frequencies {
+    fn new(frequencies: Vec<TermFrequency>) -> Self {
+        let mut this = Self::default();
+        for mut f in frequencies {
+            if !this.dictionaries.contains(&f.dictionary_id) {
+                this.dictionaries.push(f.dictionary_id);
+            }
+            // Ranks are compared, a count of occurrences is the opposite of one
+            if f.occurrence_based {
+                f.frequency = -f.frequency;
+            }
+            this.ranks
+                .entry((f.dictionary_id, f.expression.clone()))
+                .or_default()
+                .push(f);
+        }
+        this
+    }
+
+    /// Best rank of the word in each dictionary. Frequencies of its reading come first, the ones
+    /// that aren't restricted to a reading only count when a dictionary has none for it.
+    fn of(&self, expression: &str, reading: &str) -> Vec<Option<f64>> {
+        let reading = if reading.is_empty() {
+            expression
+        } else {
+            reading
+        };
+        self.dictionaries
+            .iter()
+            .map(|&dictionary_id| {
+                let frequencies = self.ranks.get(&(dictionary_id, expression.to_string()))?;
+                let best = |reading: Option<&str>| {
+                    frequencies
+                        .iter()
+                        .filter(|f| f.reading.as_deref() == reading)
+                        .map(|f| f.frequency)
+                        .min_by(f64::total_cmp)
+                };
+                best(Some(reading)).or_else(|| best(None))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn frequency(dictionary_id: i32, reading: Option<&str>, frequency: f64) -> TermFrequency {
+        TermFrequency {
+            dictionary_id,
+            expression: "日本".to_string(),
+            reading: reading.map(str::to_string),
+            frequency,
+            occurrence_based: dictionary_id == 2,
+        }
+    }
+
+    #[test]
+    fn should_parse_ranking() {
+        assert_eq!(
+            Ranking::default().to_string(),
+            "length,exact,frequency,priority,score,tags"
+        );
+        let ranking: Ranking = "frequency, priority".parse().unwrap();
+        assert_eq!(ranking.0, [SortKey::Frequency, SortKey::Priority]);
+        assert_eq!("".parse::<Ranking>().unwrap().0, []);
+        assert!("frequency,popularity".parse::<Ranking>().is_err());
+    }
+
+    #[test]
+    fn should_rank_common_readings_first() {
+        let frequencies = Frequencies::new(vec![
+            frequency(1, Some("にほん"), 100.0),
+            frequency(1, Some("にっぽん"), 5000.0),
+            frequency(1, None, 10.0),
+            frequency(2, None, 30.0),
+        ]);
+        let nihon = frequencies.of("日本", "にほん");
+        let nippon = frequencies.of("日本", "にっぽん");
+        assert_eq!(nihon, [Some(100.0), Some(-30.0)]);
+        assert_eq!(nippon, [Some(5000.0), Some(-30.0)]);
+        assert_eq!(frequencies.of("日本語", "にほんご"), [None, None]);
+        // A frequency for any reading only counts when there is none for the reading itself
+        assert_eq!(
+            frequencies.of("日本", "ひのもと"),
+            [Some(10.0), Some(-30.0)]
+        );
+
+        let ranking = Ranking::default();
+        let facts = |frequencies: Vec<Option<f64>>, exact: bool, score: f32| Facts {
+            length: 2,
+            exact,
+            frequencies,
+            score,
+            ..Default::default()
+        };
+        assert_eq!(
+            ranking.compare(
+                &facts(nihon.clone(), true, 0.0),
+                &facts(nippon.clone(), true, 9.0)
+            ),
+            Ordering::Less
+        );
+        // Exact matches come before more common deinflected ones
+        assert_eq!(
+            ranking.compare(
+                &facts(nihon.clone(), false, 0.0),
+                &facts(nippon.clone(), true, 0.0)
+            ),
+            Ordering::Greater
+        );
+        // Entries without a frequency come last
+        assert_eq!(
+            ranking.compare(
+                &facts(vec![None, None], true, 9.0),
+                &facts(nippon, true, 0.0)
+            ),
+            Ordering::Greater
+        );
+        let ranking: Ranking = "score".parse().unwrap();
+        assert_eq!(
+            ranking.compare(
+                &facts(nihon, true, 0.0),
+                &facts(vec![None, None], true, 9.0)
+            ),
+            Ordering::Greater
+        );
+    }
+}
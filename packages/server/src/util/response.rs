@@ -1,6 +1,9 @@
 use axum::{
     Json,
-    extract::rejection::{PathRejection, QueryRejection},
+    extract::{
+        multipart::{MultipartError, MultipartRejection},
+        rejection::{JsonRejection, PathRejection, QueryRejection},
+    },
     http::StatusCode,
     response::IntoResponse,
 };
@@ -83,6 +86,15 @@ impl From<ValidationErrors> for ErrorResponse {
     }
 }
 
+impl From<MultipartError> for ErrorResponse {
+    fn from(value: MultipartError) -> Self {
+        Self {
+            error: anyhow::anyhow!(value.body_text()),
+            status_code: value.status(),
+        }
+    }
+}
+
 impl IntoResponse for ErrorResponse {
     fn into_response(self) -> axum::response::Response {
         if self.status_code == StatusCode::INTERNAL_SERVER_ERROR {
@@ -113,6 +125,22 @@ impl From<PathRejection> for RejectionResponse {
     }
 }
 
+impl From<JsonRejection> for RejectionResponse {
+    fn from(value: JsonRejection) -> Self {
+        Self {
+            message: value.body_text(),
+        }
+    }
+}
+
+impl From<MultipartRejection> for RejectionResponse {
+    fn from(value: MultipartRejection) -> Self {
+        Self {
+            message: value.body_text(),
+        }
+    }
+}
+
 impl IntoResponse for RejectionResponse {
     fn into_response(self) -> axum::response::Response {
         fail::<()>(self.message, StatusCode::BAD_REQUEST).into_response()
@@ -0,0 +1,400 @@
+use crate::schemas::dictionary_index::DictionaryIndex;
+use crate::schemas::dictionary_term_bank_v3::{
+    Definition, DetailedDefinition, DictionaryTermBankV3Row, HtmlDefinition,
+};
+use crate::util::dict::{Importer, TERM_BATCH_SIZE};
+use crate::util::progress::{ImportPhase, ImportProgress};
+use anyhow::{Context, bail};
+use flate2::read::MultiGzDecoder;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::mem;
+use std::path::{Path, PathBuf};
+
+/// Imports a StarDict dictionary, reporting progress in entries and synonyms.
+/// The definitions are decompressed into memory since entries point anywhere in them.
+pub struct StardictImporter {
+    info: HashMap<String, String>,
+    entries: Vec<IndexEntry>,
+    /// Alternative headwords along with the index of their entry.
+    synonyms: Vec<(String, usize)>,
+    dict: Vec<u8>,
+    /// Rows are returned for entries first, then for synonyms.
+    position: usize,
+}
+
+#[derive(Debug)]
+struct IndexEntry {
+    word: String,
+    offset: usize,
+    size: usize,
+}
+
+impl StardictImporter {
+    /// Opens the .ifo file and the files with the same name next to it.
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let ifo = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let info = parse_ifo(&ifo)?;
+        let offset_bits = match info.get("idxoffsetbits").map(String::as_str) {
+            Some("64") => 64,
+            _ => 32,
+        };
+
+        let idx = read_sibling(path, &["idx", "idx.gz"])?;
+        let entries = parse_idx(&idx, offset_bits)?;
+        let synonyms = match read_sibling(path, &["syn", "syn.dz"]) {
+            Ok(syn) => parse_syn(&syn, entries.len())?,
+            Err(_) => vec![],
+        };
+        let dict = read_sibling(path, &["dict", "dict.dz"])?;
+
+        Ok(Self {
+            info,
+            entries,
+            synonyms,
+            dict,
+            position: 0,
+        })
+    }
+
+    /// Definitions of the entry at `index`, in the order of its fields.
+    fn definitions(&self, index: usize) -> anyhow::Result<Vec<Definition>> {
+        let entry = &self.entries[index];
+        let Some(data) = self.dict.get(entry.offset..entry.offset + entry.size) else {
+            bail!("Definition of {} is out of bounds", entry.word);
+        };
+        let fields = parse_fields(data, self.info.get("sametypesequence").map(String::as_str))
+            .with_context(|| format!("Failed to read the definition of {}", entry.word))?;
+        Ok(fields
+            .into_iter()
+            .filter_map(|(kind, content)| definition(kind, content))
+            .collect())
+    }
+
+    fn row(&self, word: &str, index: usize) -> anyhow::Result<Option<DictionaryTermBankV3Row>> {
+        let definitions = self.definitions(index)?;
+        if definitions.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(DictionaryTermBankV3Row(
+            word.to_string(),
+            String::new(),
+            Some(String::new()),
+            String::new(),
+            0.0,
+            definitions,
+            index as i32,
+            String::new(),
+        )))
+    }
+}
+
+impl Importer for StardictImporter {
+    fn index(&self) -> DictionaryIndex {
+        let info = |key: &str| self.info.get(key).filter(|v| !v.is_empty()).cloned();
+        DictionaryIndex {
+            title: info("bookname").unwrap_or_default(),
+            revision: info("date").or_else(|| info("version")).unwrap_or_default(),
+            minimum_yomitan_version: None,
+            // Synonyms share the sequence of their entry
+            sequenced: true,
+            format: Some(3),
+            version: None,
+            author: info("author"),
+            is_updatable: None,
+            index_url: None,
+            download_url: None,
+            url: info("website"),
+            description: info("description"),
+            attribution: None,
+            source_language: None,
+            target_language: None,
+            frequency_mode: None,
+            tag_meta: None,
+        }
+    }
+
+    fn length(&self) -> u64 {
+        (self.entries.len() + self.synonyms.len()) as u64
+    }
+
+    fn next_terms(
+        &mut self,
+        progress: &dyn ImportProgress,
+    ) -> anyhow::Result<Option<Vec<DictionaryTermBankV3Row>>> {
+        let end = (self.position + TERM_BATCH_SIZE).min(self.length() as usize);
+        if self.position == end {
+            return Ok(None);
+        }
+
+        let mut rows = Vec::with_capacity(end - self.position);
+        for position in self.position..end {
+            let row = match position.checked_sub(self.entries.len()) {
+                None => self.row(&self.entries[position].word, position)?,
+                Some(synonym) => {
+                    let (word, index) = &self.synonyms[synonym];
+                    self.row(word, *index)?
+                }
+            };
+            rows.extend(row);
+        }
+        progress.inc(ImportPhase::Parse, (end - self.position) as u64);
+        self.position = end;
+        Ok(Some(rows))
+    }
+}
+
+/// Reads the `key=value` lines following the magic line.
+fn parse_ifo(ifo: &str) -> anyhow::Result<HashMap<String, String>> {
+    let mut lines = ifo.lines();
+    if lines.next().map(str::trim) != Some("StarDict's dict ifo file") {
+        bail!("Not a StarDict .ifo file");
+    }
+    let info: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect();
+    if !info.contains_key("bookname") {
+        bail!("Missing bookname in .ifo file");
+    }
+    Ok(info)
+}
+
+/// Reads the first of the files named like `ifo` with one of `extensions` that exists,
+/// decompressing it if it ends with .gz or .dz.
+fn read_sibling(ifo: &Path, extensions: &[&str]) -> anyhow::Result<Vec<u8>> {
+    let paths: Vec<PathBuf> = extensions
+        .iter()
+        .map(|extension| ifo.with_extension(extension))
+        .collect();
+    let Some(path) = paths.iter().find(|path| path.exists()) else {
+        bail!("Missing {}", paths[0].display());
+    };
+    let mut content = vec![];
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let result = match path.extension().and_then(|e| e.to_str()) {
+        // Dictzip files are gzip files split into chunks that can be read as a whole
+        Some("gz" | "dz") => MultiGzDecoder::new(file).read_to_end(&mut content),
+        _ => file.read_to_end(&mut content),
+    };
+    result.with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(content)
+}
+
+/// Splits a NUL terminated string off the start of `data`.
+fn split_word(data: &[u8]) -> anyhow::Result<(String, &[u8])> {
+    let Some(end) = data.iter().position(|b| *b == 0) else {
+        bail!("Unterminated word");
+    };
+    let word = String::from_utf8_lossy(&data[..end]).into_owned();
+    Ok((word, &data[end + 1..]))
+}
+
+fn split_number(data: &[u8], bytes: usize) -> anyhow::Result<(usize, &[u8])> {
+    if data.len() < bytes {
+        bail!("Unexpected end of file");
+    }
+    let (number, rest) = data.split_at(bytes);
+    let number = number
+        .iter()
+        .fold(0usize, |value, byte| (value << 8) | *byte as usize);
+    Ok((number, rest))
+}
+
+/// Entries are a word followed by the offset and size of its definition, big endian.
+fn parse_idx(mut idx: &[u8], offset_bits: usize) -> anyhow::Result<Vec<IndexEntry>> {
+    let mut entries = vec![];
+    while !idx.is_empty() {
+        let (word, rest) = split_word(idx)?;
+        let (offset, rest) = split_number(rest, offset_bits / 8)?;
+        let (size, rest) = split_number(rest, 4)?;
+        entries.push(IndexEntry { word, offset, size });
+        idx = rest;
+    }
+    Ok(entries)
+}
+
+/// Synonyms are a word followed by the index of its entry.
+fn parse_syn(mut syn: &[u8], entry_count: usize) -> anyhow::Result<Vec<(String, usize)>> {
+    let mut synonyms = vec![];
+    while !syn.is_empty() {
+        let (word, rest) = split_word(syn)?;
+        let (index, rest) = split_number(rest, 4)?;
+        if index >= entry_count {
+            bail!("Synonym {} points to a missing entry", word);
+        }
+        synonyms.push((word, index));
+        syn = rest;
+    }
+    Ok(synonyms)
+}
+
+/// Splits the data of an entry into `(type, content)` fields. Without `sametypesequence`, every
+/// field starts with its type. Lowercase types are NUL terminated strings and uppercase types
+/// are prefixed with their size, except for the last field of a `sametypesequence` entry which
+/// takes the rest of the data.
+fn parse_fields<'a>(
+    mut data: &'a [u8],
+    same_type_sequence: Option<&str>,
+) -> anyhow::Result<Vec<(u8, &'a [u8])>> {
+    let mut fields = vec![];
+    match same_type_sequence {
+        Some(types) => {
+            let types = types.as_bytes();
+            for (i, kind) in types.iter().enumerate() {
+                let content = if i == types.len() - 1 {
+                    mem::take(&mut data)
+                } else {
+                    split_field(&mut data, *kind)?
+                };
+                fields.push((*kind, content));
+            }
+        }
+        None => {
+            while let Some((kind, rest)) = data.split_first() {
+                data = rest;
+                fields.push((*kind, split_field(&mut data, *kind)?));
+            }
+        }
+    }
+    Ok(fields)
+}
+
+fn split_field<'a>(data: &mut &'a [u8], kind: u8) -> anyhow::Result<&'a [u8]> {
+    let (content, rest) = if kind.is_ascii_lowercase() {
+        let end = data.iter().position(|b| *b == 0).unwrap_or(data.len());
+        (&data[..end], data.get(end + 1..).unwrap_or_default())
+    } else {
+        let (size, rest) = split_number(data, 4)?;
+        if rest.len() < size {
+            bail!("Unexpected end of definition");
+        }
+        rest.split_at(size)
+    };
+    *data = rest;
+    Ok(content)
+}
+
+/// Markup is kept as HTML and the other text types as text, binary types are left out.
+fn definition(kind: u8, content: &[u8]) -> Option<Definition> {
+    let text = String::from_utf8_lossy(content).trim().to_string();
+    if text.is_empty() {
+        return None;
+    }
+    match kind {
+        b'h' | b'g' | b'x' => Some(Definition::Detailed(Box::new(DetailedDefinition::Html(
+            HtmlDefinition { html: text },
+        )))),
+        b'm' | b'l' | b't' | b'y' | b'k' | b'w' => Some(Definition::Text(text)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::query::{DEFAULT_PROFILE_ID, FulltextField, FulltextMode};
+    use crate::db::tables::DictionaryEntry;
+    use crate::test_util::{NoProgress, temp_db};
+    use crate::util::dict::{Dict, DictFormat, OnConflict};
+    use std::sync::Arc;
+
+    #[test]
+    fn should_parse_idx() {
+        let mut idx = b"a\0".to_vec();
+        idx.extend(0u32.to_be_bytes());
+        idx.extend(5u32.to_be_bytes());
+        idx.extend("あい\0".as_bytes());
+        idx.extend(5u32.to_be_bytes());
+        idx.extend(3u32.to_be_bytes());
+
+        let entries = parse_idx(&idx, 32).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].word, "あい");
+        assert_eq!((entries[1].offset, entries[1].size), (5, 3));
+        assert!(parse_idx(&idx[..idx.len() - 1], 32).is_err());
+    }
+
+    #[test]
+    fn should_parse_fields() {
+        let fields = parse_fields("あ\0<b>a</b>".as_bytes(), Some("th")).unwrap();
+        assert_eq!(
+            fields,
+            [(b't', "あ".as_bytes()), (b'h', b"<b>a</b>".as_slice())]
+        );
+
+        let mut data = b"mplain\0W".to_vec();
+        data.extend(2u32.to_be_bytes());
+        data.extend(b"\x01\x02h<i>b</i>\0");
+        let fields = parse_fields(&data, None).unwrap();
+        let kinds: Vec<u8> = fields.iter().map(|(kind, _)| *kind).collect();
+        assert_eq!(kinds, b"mWh");
+        let definitions: Vec<Option<Definition>> =
+            fields.into_iter().map(|(k, c)| definition(k, c)).collect();
+        assert!(matches!(definitions[0], Some(Definition::Text(ref text)) if text == "plain"));
+        assert!(definitions[1].is_none());
+        assert!(matches!(definitions[2], Some(Definition::Detailed(_))));
+    }
+
+    #[tokio::test]
+    async fn should_import_a_dictionary() {
+        let (config, db) = temp_db("stardict-test").await;
+        let dictionary_id = Dict::new(config)
+            .parse_source(
+                PathBuf::from("src/fixtures/stardict/test.ifo"),
+                DictFormat::Stardict,
+                &db,
+                OnConflict::Error,
+                Arc::new(NoProgress),
+            )
+            .await
+            .unwrap();
+
+        let dictionary = db
+            .query_dictionary(dictionary_id, DEFAULT_PROFILE_ID)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(dictionary.title, "StarDict Test");
+        assert_eq!(dictionary.revision, "2026.10.18");
+        let entries: Vec<DictionaryEntry> = db
+            .query_dictionary_rows("dictionary_entry", dictionary_id, 0, 100)
+            .await
+            .unwrap();
+        let rows: Vec<(&str, i32)> = entries
+            .iter()
+            .map(|entry| (entry.expression.as_str(), entry.sequence))
+            .collect();
+        // Synonyms come last and share the sequence of their entry
+        assert_eq!(rows, [("read", 0), ("write", 1), ("reads", 0)]);
+        let Definition::Detailed(definition) = &entries[1].definitions[0] else {
+            panic!("Expected an HTML definition");
+        };
+        let DetailedDefinition::Html(definition) = definition.as_ref() else {
+            panic!("Expected an HTML definition");
+        };
+        assert_eq!(definition.html, "to <i>write</i>");
+
+        // The text of the HTML is searchable
+        let result = db
+            .search_dictionary_entry_fulltext(
+                "book & more",
+                FulltextMode::Substring,
+                FulltextField::Glossary,
+                1,
+                10,
+                DEFAULT_PROFILE_ID,
+            )
+            .await
+            .unwrap();
+        let expressions: Vec<&str> = result
+            .entries
+            .iter()
+            .map(|entry| entry.expression.as_str())
+            .collect();
+        assert_eq!(expressions, ["read", "reads"]);
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     db::Db,
-    util::{config::Config, lexer::Lexer},
+    util::{config::Config, jobs::Jobs, lexer::Lexer, translator::Translator, updater::Updater},
 };
 use std::sync::Arc;
 
@@ -9,4 +9,7 @@ pub struct AppState {
     pub db: Arc<Db>,
     pub lexer: Arc<Lexer>,
     pub config: Arc<Config>,
+    pub translator: Arc<Translator>,
+    pub updater: Arc<Updater>,
+    pub jobs: Arc<Jobs>,
 }
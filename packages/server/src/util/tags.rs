@@ -0,0 +1,192 @@
+use crate::db::Db;
+use crate::db::tables::{DefinitionTag, Dictionary, DictionaryEntry};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// Category of the tags a dictionary doesn't describe.
+const DEFAULT_CATEGORY: &str = "default";
+
+/// Tags of an entry resolved to their definitions.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedTags {
+    pub expression: Vec<DefinitionTag>,
+    pub definition: Vec<DefinitionTag>,
+}
+
+/// Definitions of the tags of some entries, from the dictionary of each entry.
+#[derive(Default)]
+pub struct TagResolver {
+    tags: HashMap<(i32, String), DefinitionTag>,
+}
+
+impl TagResolver {
+    /// Loads the tags of `entries`, the profile is only used to read the dictionaries.
+    pub async fn load<'a>(
+        db: &Db,
+        entries: impl IntoIterator<Item = &'a DictionaryEntry>,
+        profile_id: i32,
+    ) -> anyhow::Result<Self> {
+        let wanted: BTreeSet<(i32, String)> = entries
+            .into_iter()
+            .flat_map(|entry| tag_names(entry).map(|name| (entry.dictionary_id, name.to_string())))
+            .collect();
+        if wanted.is_empty() {
+            return Ok(Self::default());
+        }
+
+        let mut dictionary_ids: Vec<i32> = wanted.iter().map(|(id, _)| *id).collect();
+        dictionary_ids.dedup();
+        let mut names: Vec<String> = wanted.iter().map(|(_, name)| name.clone()).collect();
+        names.sort();
+        names.dedup();
+        let tags = db.query_definition_tags_in(&dictionary_ids, &names).await?;
+        let dictionaries = db.query_dictionaries(profile_id).await?;
+        Ok(Self::new(wanted, tags, &dictionaries))
+    }
+
+    /// Tags a dictionary defines more than once resolve to the first one. Tags it doesn't
+    /// define come from the `tag_meta` of its index, or have nothing but their name. These
+    /// aren't rows, their id is 0.
+    fn new(
+        wanted: impl IntoIterator<Item = (i32, String)>,
+        tags: Vec<DefinitionTag>,
+        dictionaries: &[Dictionary],
+    ) -> Self {
+        let mut resolved = HashMap::new();
+        for tag in tags {
+            resolved
+                .entry((tag.dictionary_id, tag.name.clone()))
+                .or_insert(tag);
+        }
+        for (dictionary_id, name) in wanted {
+            let key = (dictionary_id, name);
+            if resolved.contains_key(&key) {
+                continue;
+            }
+            let Some(dictionary) = dictionaries.iter().find(|d| d.id == dictionary_id) else {
+                continue;
+            };
+            let meta = dictionary
+                .tag_meta
+                .as_ref()
+                .and_then(|meta| meta.get(&key.1));
+            let tag = DefinitionTag {
+                id: 0,
+                created_at: dictionary.created_at,
+                updated_at: dictionary.updated_at,
+                dictionary_id,
+                name: key.1.clone(),
+                category: meta
+                    .and_then(|meta| meta.category.clone())
+                    .unwrap_or_else(|| DEFAULT_CATEGORY.to_string()),
+                order: meta.and_then(|meta| meta.order).unwrap_or(0.0),
+                notes: meta.and_then(|meta| meta.notes.clone()).unwrap_or_default(),
+                score: meta.and_then(|meta| meta.score).unwrap_or(0.0),
+            };
+            resolved.insert(key, tag);
+        }
+        Self { tags: resolved }
+    }
+
+    pub fn get(&self, dictionary_id: i32, name: &str) -> Option<&DefinitionTag> {
+        self.tags.get(&(dictionary_id, name.to_string()))
+    }
+
+    /// The tags named in the space-separated `names`, sorted with [`sort_tags`].
+    pub fn resolve(&self, dictionary_id: i32, names: &str) -> Vec<DefinitionTag> {
+        let mut tags: Vec<DefinitionTag> = Vec::new();
+        for name in names.split_whitespace() {
+            if tags.iter().any(|tag| tag.name == name) {
+                continue;
+            }
+            if let Some(tag) = self.get(dictionary_id, name) {
+                tags.push(tag.clone());
+            }
+        }
+        sort_tags(&mut tags);
+        tags
+    }
+
+    pub fn resolve_entry(&self, entry: &DictionaryEntry) -> ResolvedTags {
+        ResolvedTags {
+            expression: self.resolve(entry.dictionary_id, &entry.expression_tags),
+            definition: self.resolve(entry.dictionary_id, &entry.definition_tags),
+        }
+    }
+}
+
+/// Names of the tags of the expression and of the definitions of an entry.
+pub fn tag_names(entry: &DictionaryEntry) -> impl Iterator<Item = &str> {
+    entry
+        .expression_tags
+        .split_whitespace()
+        .chain(entry.definition_tags.split_whitespace())
+}
+
+/// Sorts tags by their order, then by category and name.
+pub fn sort_tags(tags: &mut [DefinitionTag]) {
+    tags.sort_by(|a, b| {
+        a.order
+            .total_cmp(&b.order)
+            .then_with(|| a.category.cmp(&b.category))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util;
+    use chrono::Utc;
+
+    fn tag(id: i32, name: &str, category: &str, order: f32) -> DefinitionTag {
+        DefinitionTag {
+            id,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            dictionary_id: 1,
+            name: name.to_string(),
+            category: category.to_string(),
+            order,
+            notes: String::new(),
+            score: 0.0,
+        }
+    }
+
+    #[test]
+    fn should_resolve_tags_with_fallbacks() {
+        let mut dictionary = test_util::dictionary(1);
+        dictionary.tag_meta = serde_json::from_value(serde_json::json!({
+            "arch": { "category": "archaism", "order": -4, "score": -1 }
+        }))
+        .unwrap();
+        let wanted = ["v1", "n", "P", "arch", "vulg"].map(|name| (1, name.to_string()));
+        let tags = vec![
+            tag(1, "v1", "partOfSpeech", 0.0),
+            tag(2, "n", "partOfSpeech", 0.0),
+            tag(3, "P", "popular", -10.0),
+            tag(4, "v1", "duplicate", 0.0),
+        ];
+        let resolver = TagResolver::new(wanted, tags, &[dictionary]);
+
+        let names = |tags: Vec<DefinitionTag>| -> Vec<String> {
+            tags.into_iter()
+                .map(|tag| format!("{}:{}:{}", tag.id, tag.name, tag.category))
+                .collect()
+        };
+        assert_eq!(
+            names(resolver.resolve(1, "v1 vulg arch n P v1")),
+            [
+                "3:P:popular",
+                "0:arch:archaism",
+                "0:vulg:default",
+                "2:n:partOfSpeech",
+                "1:v1:partOfSpeech",
+            ]
+        );
+        assert_eq!(resolver.get(1, "arch").unwrap().score, -1.0);
+        // Tags are scoped to the dictionary of the entry
+        assert!(resolver.resolve(2, "v1").is_empty());
+    }
+}
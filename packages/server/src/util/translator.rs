@@ -0,0 +1,658 @@
+use crate::db::Db;
+use crate::db::query::{SearchField, normalize_reading};
+use crate::db::tables::{DictionaryEntry, Profile};
+use crate::util::deinflector::{DeinflectionCandidate, Deinflector, japanese};
+use crate::util::kana;
+use crate::util::merge::{self, MergedEntryMatch, ResultMode};
+use crate::util::ranking::Ranking;
+use crate::util::tags::{ResolvedTags, TagResolver};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Most characters a scan looks at.
+pub const MAX_SCAN_LENGTH: usize = 64;
+
+/// A dictionary entry found for a lookup, along with how it was reached.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DictionaryEntryMatch {
+    #[serde(flatten)]
+    pub entry: DictionaryEntry,
+    /// The text that was looked up.
+    pub source: String,
+    /// Every chain of inflections that turns the entry into the source, an empty chain is an exact match.
+    pub inflection_rule_chains: Vec<Vec<String>>,
+    /// Tags of the entry resolved to their definitions, when asked for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved_tags: Option<ResolvedTags>,
+}
+
+/// Entries found for a single prefix of the scanned text.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanGroup {
+    /// The prefix of the scanned text.
+    pub source: String,
+    /// Length of the prefix in UTF-16 code units, the same as a JavaScript string length.
+    pub source_length: usize,
+    pub entries: Vec<DictionaryEntryMatch>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanResult {
+    /// Length of the longest prefix with a match, 0 when nothing was found.
+    pub matched_length: usize,
+    /// Groups ordered from the longest to the shortest prefix.
+    pub groups: Vec<ScanGroup>,
+}
+
+/// Results of a lookup, as [`ResultMode`] asks for.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum SearchResult {
+    Flat(Vec<DictionaryEntryMatch>),
+    Merged(Vec<MergedEntryMatch>),
+}
+
+/// How the texts of a batch lookup are matched and their results returned.
+pub struct BatchOptions {
+    pub field: SearchField,
+    pub mode: ResultMode,
+    pub ranking: Ranking,
+    /// Results per text, in place of the limit of the profile.
+    pub limit: Option<i32>,
+    pub resolve_tags: bool,
+}
+
+/// A deinflection candidate along with the text it was produced from.
+struct SourcedCandidate {
+    source: String,
+    candidate: DeinflectionCandidate,
+}
+
+pub struct Translator {
+    deinflector: Deinflector,
+}
+
+impl Translator {
+    pub fn new() -> anyhow::Result<Self> {
+        let deinflector = Deinflector::new(japanese::descriptor())?;
+        Ok(Self { deinflector })
+    }
+
+    /// Looks up `text` and every deinflected form of it, unless `profile` turns deinflection
+    /// off. Matching ignores the difference between hiragana and katakana, and between
+    /// full-width and half-width characters. Results are sorted by `ranking`, and their tags
+    /// resolved when `resolve_tags` is set.
+    pub async fn find_terms(
+        &self,
+        db: &Db,
+        text: &str,
+        field: SearchField,
+        ranking: &Ranking,
+        resolve_tags: bool,
+        profile: &Profile,
+    ) -> anyhow::Result<Vec<DictionaryEntryMatch>> {
+        let candidates = self.get_candidates(text, profile);
+        let mut matches = self
+            .find_matches(db, &candidates, field, ranking, profile)
+            .await?;
+        truncate(&mut matches, profile.max_results);
+        if resolve_tags {
+            resolve_match_tags(db, std::slice::from_mut(&mut matches), profile).await?;
+        }
+        Ok(matches)
+    }
+
+    /// Same as [`Translator::find_terms`], with the entries of a word merged into one result.
+    /// Entries that share a sequence number with a match are merged in even when they don't
+    /// match themselves, so every spelling of the word is listed. Results are ordered by their
+    /// best ranked entry.
+    pub async fn find_terms_merged(
+        &self,
+        db: &Db,
+        text: &str,
+        field: SearchField,
+        ranking: &Ranking,
+        resolve_tags: bool,
+        profile: &Profile,
+    ) -> anyhow::Result<Vec<MergedEntryMatch>> {
+        let candidates = self.get_candidates(text, profile);
+        let matches = self
+            .find_matches(db, &candidates, field, ranking, profile)
+            .await?;
+        let mut merged = self
+            .merge_each(db, vec![matches], resolve_tags, profile)
+            .await?
+            .remove(0);
+        truncate(&mut merged, profile.max_results);
+        Ok(merged)
+    }
+
+    /// Looks up each of `texts` like [`Translator::find_terms`] does, with a single query for
+    /// all of them.
+    pub async fn find_terms_batch(
+        &self,
+        db: &Db,
+        texts: &[String],
+        options: &BatchOptions,
+        profile: &Profile,
+    ) -> anyhow::Result<BTreeMap<String, SearchResult>> {
+        let mut texts = texts.to_vec();
+        texts.sort();
+        texts.dedup();
+        let candidate_sets: Vec<_> = texts
+            .iter()
+            .map(|text| self.get_candidates(text, profile))
+            .collect();
+        let mut lists = self
+            .find_matches_each(
+                db,
+                &candidate_sets,
+                options.field,
+                &options.ranking,
+                profile,
+            )
+            .await?;
+
+        let limit = options.limit.or(profile.max_results);
+        let results: Vec<SearchResult> = match options.mode {
+            ResultMode::Flat => {
+                for matches in &mut lists {
+                    truncate(matches, limit);
+                }
+                if options.resolve_tags {
+                    resolve_match_tags(db, &mut lists, profile).await?;
+                }
+                lists.into_iter().map(SearchResult::Flat).collect()
+            }
+            ResultMode::Merged => self
+                .merge_each(db, lists, options.resolve_tags, profile)
+                .await?
+                .into_iter()
+                .map(|mut merged| {
+                    truncate(&mut merged, limit);
+                    SearchResult::Merged(merged)
+                })
+                .collect(),
+        };
+        Ok(texts.into_iter().zip(results).collect())
+    }
+
+    /// Looks up every prefix of `text` from the longest to the shortest, including deinflected
+    /// forms, in a single query.
+    pub async fn scan(
+        &self,
+        db: &Db,
+        text: &str,
+        max_length: usize,
+        ranking: &Ranking,
+        resolve_tags: bool,
+        profile: &Profile,
+    ) -> anyhow::Result<ScanResult> {
+        let candidates = self.scan_candidates(text, max_length, profile);
+        let mut matches = self
+            .find_matches(db, &candidates, SearchField::Any, ranking, profile)
+            .await?;
+        truncate(&mut matches, profile.max_results);
+        if resolve_tags {
+            resolve_match_tags(db, std::slice::from_mut(&mut matches), profile).await?;
+        }
+        Ok(group_by_source(matches))
+    }
+
+    /// Candidates of every prefix of `text` from the longest to the shortest, at most
+    /// `max_length` characters long and never more than [`MAX_SCAN_LENGTH`].
+    fn scan_candidates(
+        &self,
+        text: &str,
+        max_length: usize,
+        profile: &Profile,
+    ) -> Vec<SourcedCandidate> {
+        let chars: Vec<char> = text.chars().take(max_length.min(MAX_SCAN_LENGTH)).collect();
+        let mut candidates = Vec::new();
+        for length in (1..=chars.len()).rev() {
+            let prefix: String = chars[..length].iter().collect();
+            candidates.append(&mut self.get_candidates(&prefix, profile));
+        }
+        candidates
+    }
+
+    /// The first candidate is always `source` itself.
+    fn get_candidates(&self, source: &str, profile: &Profile) -> Vec<SourcedCandidate> {
+        let limit = if profile.deinflect { usize::MAX } else { 1 };
+        self.deinflector
+            .deinflect(&kana::normalize(source))
+            .into_iter()
+            .take(limit)
+            .map(|candidate| SourcedCandidate {
+                source: source.to_string(),
+                candidate,
+            })
+            .collect()
+    }
+
+    /// Matches entries against candidates. Each entry is attributed to the first candidate it
+    /// matches, so candidates should be ordered from the most to the least preferred. Matches
+    /// are then sorted by `ranking`, the ones it can't tell apart keep the candidate order.
+    async fn find_matches(
+        &self,
+        db: &Db,
+        candidates: &[SourcedCandidate],
+        field: SearchField,
+        ranking: &Ranking,
+        profile: &Profile,
+    ) -> anyhow::Result<Vec<DictionaryEntryMatch>> {
+        let candidate_sets = std::slice::from_ref(&candidates);
+        let mut lists = self
+            .find_matches_each(db, candidate_sets, field, ranking, profile)
+            .await?;
+        Ok(lists.remove(0))
+    }
+
+    /// Same as [`Translator::find_matches`] for each set of candidates on its own, with a single
+    /// query for all of them.
+    async fn find_matches_each<C: AsRef<[SourcedCandidate]>>(
+        &self,
+        db: &Db,
+        candidate_sets: &[C],
+        field: SearchField,
+        ranking: &Ranking,
+        profile: &Profile,
+    ) -> anyhow::Result<Vec<Vec<DictionaryEntryMatch>>> {
+        let mut seen = HashSet::new();
+        let terms: Vec<String> = candidate_sets
+            .iter()
+            .flat_map(|candidates| candidates.as_ref())
+            .filter(|c| seen.insert(c.candidate.term.as_str()))
+            .map(|c| c.candidate.term.clone())
+            .collect();
+        let entries = db
+            .query_dictionary_entry_by_normalized(&terms, field, profile.id)
+            .await?;
+
+        let mut lists: Vec<_> = candidate_sets
+            .iter()
+            .map(|candidates| self.match_entries(&entries, candidates.as_ref(), field))
+            .collect();
+        ranking.sort(db, &mut lists, profile.id).await?;
+        Ok(lists)
+    }
+
+    fn match_entries(
+        &self,
+        entries: &[DictionaryEntry],
+        candidates: &[SourcedCandidate],
+        field: SearchField,
+    ) -> Vec<DictionaryEntryMatch> {
+        let mut matches: Vec<(usize, DictionaryEntryMatch)> = Vec::new();
+        for entry in entries {
+            let expression = kana::normalize(&entry.expression);
+            let reading = normalize_reading(&entry.expression, &entry.reading);
+            let is_term = |term: &str| match field {
+                SearchField::Expression => term == expression,
+                SearchField::Reading => term == reading,
+                SearchField::Any => term == expression || term == reading,
+            };
+
+            let mut first_candidate: Option<usize> = None;
+            let mut inflection_rule_chains: Vec<Vec<String>> = Vec::new();
+            for (i, c) in candidates.iter().enumerate() {
+                if !is_term(&c.candidate.term)
+                    || !self
+                        .deinflector
+                        .candidate_matches_rules(&c.candidate, &entry.rules)
+                {
+                    continue;
+                }
+                let first = *first_candidate.get_or_insert(i);
+                if candidates[first].source != c.source {
+                    continue;
+                }
+                if !inflection_rule_chains.contains(&c.candidate.inflection_rules) {
+                    inflection_rule_chains.push(c.candidate.inflection_rules.clone());
+                }
+            }
+
+            if let Some(i) = first_candidate {
+                matches.push((
+                    i,
+                    DictionaryEntryMatch {
+                        entry: entry.clone(),
+                        source: candidates[i].source.clone(),
+                        inflection_rule_chains,
+                        resolved_tags: None,
+                    },
+                ));
+            }
+        }
+
+        matches.sort_by_key(|(i, _)| *i);
+        matches.into_iter().map(|(_, m)| m).collect()
+    }
+
+    /// Merges each list of matches on its own, with the entries that share a sequence number
+    /// with them queried once.
+    async fn merge_each(
+        &self,
+        db: &Db,
+        lists: Vec<Vec<DictionaryEntryMatch>>,
+        resolve_tags: bool,
+        profile: &Profile,
+    ) -> anyhow::Result<Vec<Vec<MergedEntryMatch>>> {
+        let dictionaries: HashMap<_, _> = db
+            .query_dictionaries(profile.id)
+            .await?
+            .into_iter()
+            .map(|d| (d.id, d))
+            .collect();
+        let sequences: Vec<Vec<(i32, i32)>> = lists
+            .iter()
+            .map(|matches| merge::merged_sequences(matches, &dictionaries))
+            .collect();
+        let mut all_sequences: Vec<(i32, i32)> = sequences.iter().flatten().copied().collect();
+        all_sequences.sort();
+        all_sequences.dedup();
+        let entries = db
+            .query_dictionary_entry_by_sequence(&all_sequences)
+            .await?;
+        let tags = if resolve_tags {
+            let matched = lists.iter().flatten().map(|m| &m.entry);
+            Some(TagResolver::load(db, matched.chain(&entries), profile.id).await?)
+        } else {
+            None
+        };
+
+        Ok(lists
+            .into_iter()
+            .zip(sequences)
+            .map(|(matches, sequences)| {
+                let matched: HashSet<i32> = matches.iter().map(|m| m.entry.id).collect();
+                let siblings = entries
+                    .iter()
+                    .filter(|entry| {
+                        sequences.contains(&(entry.dictionary_id, entry.sequence))
+                            && !matched.contains(&entry.id)
+                    })
+                    .cloned()
+                    .collect();
+                merge::merge_matches(matches, siblings, &dictionaries, tags.as_ref())
+            })
+            .collect())
+    }
+}
+
+/// Resolves the tags of every match with the dictionary of its entry.
+async fn resolve_match_tags(
+    db: &Db,
+    lists: &mut [Vec<DictionaryEntryMatch>],
+    profile: &Profile,
+) -> anyhow::Result<()> {
+    let entries = lists.iter().flatten().map(|m| &m.entry);
+    let tags = TagResolver::load(db, entries, profile.id).await?;
+    for m in lists.iter_mut().flatten() {
+        m.resolved_tags = Some(tags.resolve_entry(&m.entry));
+    }
+    Ok(())
+}
+
+/// Groups scan matches by the prefix they were found for, from the longest prefix.
+fn group_by_source(matches: Vec<DictionaryEntryMatch>) -> ScanResult {
+    let mut groups: Vec<ScanGroup> = Vec::new();
+    for m in matches {
+        match groups.iter_mut().find(|g| g.source == m.source) {
+            Some(group) => group.entries.push(m),
+            None => groups.push(ScanGroup {
+                source: m.source.clone(),
+                source_length: m.source.encode_utf16().count(),
+                entries: vec![m],
+            }),
+        }
+    }
+    groups.sort_by_key(|g| Reverse(g.source_length));
+
+    let matched_length = groups.first().map_or(0, |g| g.source_length);
+    ScanResult {
+        matched_length,
+        groups,
+    }
+}
+
+/// Keeps only the first results when their number is limited.
+fn truncate<T>(results: &mut Vec<T>, max_results: Option<i32>) {
+    if let Some(max_results) = max_results {
+        results.truncate(max_results.max(0) as usize);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::schemas::dictionary_index::DictionaryIndex;
+    use crate::test_util::temp_db;
+    use chrono::Utc;
+
+    fn profile(deinflect: bool) -> Profile {
+        Profile {
+            id: 1,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            name: "Default".to_string(),
+            max_results: None,
+            scan_length: 16,
+            deinflect,
+            search_field: SearchField::Any,
+        }
+    }
+
+    fn matched(id: i32, source: &str) -> DictionaryEntryMatch {
+        DictionaryEntryMatch {
+            entry: DictionaryEntry {
+                id,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                dictionary_id: 1,
+                expression: source.to_string(),
+                reading: String::new(),
+                definitions: Vec::new(),
+                rules: String::new(),
+                score: 0.0,
+                sequence: 0,
+                definition_tags: String::new(),
+                expression_tags: String::new(),
+            },
+            source: source.to_string(),
+            inflection_rule_chains: vec![vec![]],
+            resolved_tags: None,
+        }
+    }
+
+    #[test]
+    fn should_scan_prefixes_from_the_longest() {
+        let translator = Translator::new().unwrap();
+        let sources = |candidates: Vec<SourcedCandidate>| {
+            let mut sources: Vec<String> = candidates.into_iter().map(|c| c.source).collect();
+            sources.dedup();
+            sources
+        };
+        let candidates = translator.scan_candidates("食べた本", 3, &profile(true));
+        assert_eq!(sources(candidates), ["食べた", "食べ", "食"]);
+
+        let candidates = translator.scan_candidates("食べた", 16, &profile(true));
+        assert!(
+            candidates
+                .iter()
+                .any(|c| c.source == "食べた" && c.candidate.term == "食べる")
+        );
+        let candidates = translator.scan_candidates("食べた", 16, &profile(false));
+        assert_eq!(candidates.len(), 3);
+
+        let text = "あ".repeat(MAX_SCAN_LENGTH * 2);
+        let candidates = translator.scan_candidates(&text, usize::MAX, &profile(false));
+        assert_eq!(candidates.len(), MAX_SCAN_LENGTH);
+    }
+
+    #[test]
+    fn should_group_scan_matches_by_prefix() {
+        let result = group_by_source(vec![
+            matched(1, "食べ"),
+            matched(2, "食べた"),
+            matched(3, "食べ"),
+            matched(4, "𠮟る"),
+        ]);
+        let groups: Vec<(&str, usize, Vec<i32>)> = result
+            .groups
+            .iter()
+            .map(|g| {
+                let ids = g.entries.iter().map(|m| m.entry.id).collect();
+                (g.source.as_str(), g.source_length, ids)
+            })
+            .collect();
+        // Lengths are in UTF-16 code units
+        assert_eq!(
+            groups,
+            [
+                ("食べた", 3, vec![2]),
+                ("𠮟る", 3, vec![4]),
+                ("食べ", 2, vec![1, 3]),
+            ]
+        );
+        assert_eq!(result.matched_length, 3);
+        assert_eq!(group_by_source(Vec::new()).matched_length, 0);
+    }
+
+    #[tokio::test]
+    async fn should_look_up_batches_larger_than_a_query() {
+        let (_config, db) = temp_db("translator-test").await;
+        let index: DictionaryIndex =
+            serde_json::from_str(r#"{"title":"Test","revision":"1","format":3}"#).unwrap();
+        let count = 260;
+        let terms: Vec<_> = (0..count)
+            .map(|i| {
+                serde_json::from_value(serde_json::json!([
+                    format!("語{}", i),
+                    format!("よみ{}", i),
+                    "",
+                    "",
+                    0,
+                    ["definition"],
+                    0,
+                    ""
+                ]))
+                .unwrap()
+            })
+            .collect();
+        let mut writer = db.begin_dictionary(&index).await.unwrap();
+        writer.insert_terms(&terms).await.unwrap();
+        writer.commit().await.unwrap();
+
+        // Expressions and readings land in different queries
+        let texts: Vec<String> = (0..count)
+            .flat_map(|i| [format!("語{}", i), format!("よみ{}", i)])
+            .collect();
+        let options = BatchOptions {
+            field: SearchField::Any,
+            mode: ResultMode::Flat,
+            ranking: Ranking::default(),
+            limit: None,
+            resolve_tags: false,
+        };
+        let results = Translator::new()
+            .unwrap()
+            .find_terms_batch(&db, &texts, &options, &profile(true))
+            .await
+            .unwrap();
+        assert_eq!(results.len(), texts.len());
+        for i in 0..count {
+            for text in [format!("語{}", i), format!("よみ{}", i)] {
+                let SearchResult::Flat(matches) = &results[&text] else {
+                    panic!("expected flat results");
+                };
+                let expressions: Vec<&str> = matches
+                    .iter()
+                    .map(|m| m.entry.expression.as_str())
+                    .collect();
+                assert_eq!(expressions, [format!("語{}", i)]);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn should_merge_and_rank_batches_larger_than_a_query() {
+        let (_config, db) = temp_db("translator-test").await;
+        // Each entry binds a sequence, an expression and a tag of its own
+        let count = 1100;
+        let index: DictionaryIndex =
+            serde_json::from_str(r#"{"title":"Terms","revision":"1","format":3,"sequenced":true}"#)
+                .unwrap();
+        // Every matched entry has a sibling that is only found by its sequence
+        let terms: Vec<_> = (0..count)
+            .flat_map(|i| {
+                [(format!("語{}", i), "よみ"), (format!("別{}", i), "べつ")].map(
+                    |(expression, reading)| {
+                        serde_json::from_value(serde_json::json!([
+                            expression,
+                            reading,
+                            format!("t{}", i),
+                            "",
+                            0,
+                            ["definition"],
+                            i + 1,
+                            ""
+                        ]))
+                        .unwrap()
+                    },
+                )
+            })
+            .collect();
+        let tags: Vec<_> = (0..count)
+            .map(|i| {
+                serde_json::from_value(serde_json::json!([format!("t{}", i), "bank", 0, "", 0]))
+                    .unwrap()
+            })
+            .collect();
+        let mut writer = db.begin_dictionary(&index).await.unwrap();
+        writer.insert_terms(&terms).await.unwrap();
+        writer.insert_tags(&tags).await.unwrap();
+        writer.commit().await.unwrap();
+
+        let index: DictionaryIndex =
+            serde_json::from_str(r#"{"title":"Frequencies","revision":"1","format":3}"#).unwrap();
+        // The last term is the most common
+        let metas: Vec<_> = (0..count)
+            .map(|i| {
+                serde_json::from_value(serde_json::json!([format!("語{}", i), "freq", count - i]))
+                    .unwrap()
+            })
+            .collect();
+        let mut writer = db.begin_dictionary(&index).await.unwrap();
+        writer.insert_term_metas(&metas).await.unwrap();
+        writer.commit().await.unwrap();
+
+        let options = BatchOptions {
+            field: SearchField::Any,
+            mode: ResultMode::Merged,
+            ranking: Ranking::default(),
+            limit: None,
+            resolve_tags: true,
+        };
+        let results = Translator::new()
+            .unwrap()
+            .find_terms_batch(&db, &["よみ".to_string()], &options, &profile(true))
+            .await
+            .unwrap();
+        let SearchResult::Merged(merged) = &results["よみ"] else {
+            panic!("expected merged results");
+        };
+        let sequences: Vec<Option<i32>> = merged.iter().map(|m| m.sequence).collect();
+        let expected: Vec<Option<i32>> = (1..=count).rev().map(Some).collect();
+        assert_eq!(sequences, expected);
+        for m in merged {
+            assert_eq!(m.headwords.len(), 2);
+            let resolved = m.definitions[0].resolved_tags.as_ref().unwrap();
+            assert_eq!(resolved[0].category, "bank");
+        }
+    }
+}
@@ -0,0 +1,319 @@
+use crate::db::Db;
+use crate::db::tables::Dictionary;
+use crate::schemas::dictionary_index::DictionaryIndex;
+use crate::util::config::Config;
+use crate::util::dict::Dict;
+use crate::util::progress::ImportProgress;
+use crate::util::validation::ValidationReport;
+use anyhow::{Context, bail};
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+
+/// Downloads remote files. Implemented separately from [`Updater`] so updates can be tested
+/// against local files or a local server.
+pub trait Fetcher: Send + Sync {
+    fn fetch(&self, url: &str) -> impl Future<Output = anyhow::Result<Vec<u8>>> + Send;
+    /// Writes the file at `url` to `path` as it is received.
+    fn download(&self, url: &str, path: &Path) -> impl Future<Output = anyhow::Result<()>> + Send;
+}
+
+#[derive(Default)]
+pub struct HttpFetcher {
+    client: reqwest::Client,
+}
+
+impl Fetcher for HttpFetcher {
+    async fn fetch(&self, url: &str) -> anyhow::Result<Vec<u8>> {
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .with_context(|| format!("Failed to fetch {}", url))?
+            .error_for_status()?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    async fn download(&self, url: &str, path: &Path) -> anyhow::Result<()> {
+        let mut response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .with_context(|| format!("Failed to fetch {}", url))?
+            .error_for_status()?;
+        let mut file = File::create(path)
+            .await
+            .context("Failed to create downloaded dictionary")?;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .with_context(|| format!("Failed to download {}", url))?
+        {
+            file.write_all(&chunk)
+                .await
+                .context("Failed to write downloaded dictionary")?;
+        }
+        file.flush()
+            .await
+            .context("Failed to write downloaded dictionary")?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateStatus {
+    pub dictionary_id: i32,
+    pub title: String,
+    /// Revision currently imported.
+    pub revision: String,
+    /// Revision found in the remote index.
+    pub latest_revision: String,
+    pub update_available: bool,
+    /// Where the latest revision is downloaded from, the remote index takes precedence.
+    pub download_url: Option<String>,
+}
+
+pub struct Updater<F: Fetcher = HttpFetcher> {
+    fetcher: F,
+}
+
+impl<F: Fetcher> Updater<F> {
+    pub fn new(fetcher: F) -> Self {
+        Self { fetcher }
+    }
+
+    /// Fetches the remote index of the dictionary and compares its revision.
+    pub async fn check(&self, dictionary: &Dictionary) -> anyhow::Result<UpdateStatus> {
+        let index_url = match (&dictionary.index_url, dictionary.is_updatable) {
+            (Some(index_url), true) => index_url,
+            _ => bail!("Dictionary {} is not updatable", dictionary.title),
+        };
+
+        let index = self.fetcher.fetch(index_url).await?;
+        let index: DictionaryIndex =
+            serde_json::from_slice(&index).context("Failed to parse remote index")?;
+
+        Ok(UpdateStatus {
+            dictionary_id: dictionary.id,
+            title: dictionary.title.clone(),
+            revision: dictionary.revision.clone(),
+            update_available: compare_revisions(&index.revision, &dictionary.revision)
+                == Ordering::Greater,
+            latest_revision: index.revision,
+            download_url: index.download_url.or(dictionary.download_url.clone()),
+        })
+    }
+
+    /// Downloads and imports the latest revision in place of the dictionary, keeping its id.
+    /// Returns whether an update was installed.
+    pub async fn update(
+        &self,
+        config: Arc<Config>,
+        db: &Db,
+        dictionary: &Dictionary,
+        progress: Arc<dyn ImportProgress>,
+    ) -> anyhow::Result<bool> {
+        let status = self.check(dictionary).await?;
+        if !status.update_available {
+            return Ok(false);
+        }
+        let Some(download_url) = status.download_url else {
+            bail!("Dictionary {} has no download url", dictionary.title);
+        };
+
+        // Removed once dropped, whether the import succeeds or not
+        let archive_path = tempfile::Builder::new()
+            .prefix("update-")
+            .suffix(".zip")
+            .tempfile_in(&config.dir.temp)
+            .context("Failed to create downloaded dictionary")?
+            .into_temp_path();
+        self.fetcher.download(&download_url, &archive_path).await?;
+
+        let dict = Dict::new(config.clone());
+        dict.replace_dict(
+            archive_path.to_path_buf(),
+            db,
+            dictionary.id,
+            &mut ValidationReport::default(),
+            progress,
+        )
+        .await?;
+
+        Ok(true)
+    }
+}
+
+/// Compares revisions so that runs of digits are ordered by value, e.g. "1.10" > "1.9" and
+/// "2024-02-01" > "2024-1-31".
+pub fn compare_revisions(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+    loop {
+        match (a.peek(), b.peek()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x = take_number(&mut a);
+                let y = take_number(&mut b);
+                // Compare by length first so numbers of any size are ordered by value
+                let ordering = x.len().cmp(&y.len()).then_with(|| x.cmp(&y));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x.cmp(y);
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+/// Takes a run of digits without leading zeros.
+fn take_number(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut number = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+        if !(number.is_empty() && c == '0') {
+            number.push(c);
+        }
+    }
+    number
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::query::DEFAULT_PROFILE_ID;
+    use crate::test_util::{self, NoProgress, temp_db, write_zip};
+    use crate::util::dict::OnConflict;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    struct MapFetcher(HashMap<&'static str, Vec<u8>>);
+
+    impl Fetcher for MapFetcher {
+        async fn fetch(&self, url: &str) -> anyhow::Result<Vec<u8>> {
+            match self.0.get(url) {
+                Some(content) => Ok(content.clone()),
+                None => bail!("Not found: {}", url),
+            }
+        }
+
+        async fn download(&self, url: &str, path: &Path) -> anyhow::Result<()> {
+            let content = self.fetch(url).await?;
+            std::fs::write(path, content)?;
+            Ok(())
+        }
+    }
+
+    fn dictionary(revision: &str, is_updatable: bool) -> Dictionary {
+        let mut dictionary = test_util::dictionary(1);
+        dictionary.title = "Test".to_string();
+        dictionary.revision = revision.to_string();
+        dictionary.is_updatable = is_updatable;
+        dictionary.index_url = Some("http://localhost/index.json".to_string());
+        dictionary.download_url = Some("http://localhost/old.zip".to_string());
+        dictionary
+    }
+
+    #[test]
+    fn should_compare_revisions() {
+        assert_eq!(compare_revisions("1.10", "1.9"), Ordering::Greater);
+        assert_eq!(
+            compare_revisions("2024-02-01", "2024-1-31"),
+            Ordering::Greater
+        );
+        assert_eq!(compare_revisions("v2", "v02"), Ordering::Equal);
+        assert_eq!(compare_revisions("1.0", "1.0.1"), Ordering::Less);
+        assert_eq!(compare_revisions("jmdict4", "jmdict3"), Ordering::Greater);
+    }
+
+    #[tokio::test]
+    async fn should_check_for_updates() {
+        let updater = Updater::new(MapFetcher(HashMap::from([(
+            "http://localhost/index.json",
+            br#"{"title": "Test", "revision": "1.1", "downloadUrl": "http://localhost/new.zip"}"#
+                .to_vec(),
+        )])));
+
+        let status = updater.check(&dictionary("1.0", true)).await.unwrap();
+        assert!(status.update_available);
+        assert_eq!(status.latest_revision, "1.1");
+        assert_eq!(
+            status.download_url.as_deref(),
+            Some("http://localhost/new.zip")
+        );
+
+        let status = updater.check(&dictionary("1.1", true)).await.unwrap();
+        assert!(!status.update_available);
+
+        assert!(updater.check(&dictionary("1.0", false)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn should_install_the_latest_revision() {
+        let (config, db) = temp_db("updater-test").await;
+        let index = |revision: &str| {
+            json!({
+                "title": "Test",
+                "revision": revision,
+                "format": 3,
+                "isUpdatable": true,
+                "indexUrl": "http://localhost/index.json",
+                "downloadUrl": "http://localhost/new.zip",
+            })
+            .to_string()
+        };
+        let path = config.dir.temp.join("old.zip");
+        write_zip(&path, &[("index.json", &index("1.0"))]);
+        let dictionary_id = Dict::new(config.clone())
+            .parse_dict(
+                path.clone(),
+                &db,
+                OnConflict::Error,
+                &mut ValidationReport::default(),
+                Arc::new(NoProgress),
+            )
+            .await
+            .unwrap();
+        let latest = index("1.1");
+        write_zip(&path, &[("index.json", &latest)]);
+        let updater = Updater::new(MapFetcher(HashMap::from([
+            ("http://localhost/index.json", latest.clone().into_bytes()),
+            ("http://localhost/new.zip", std::fs::read(&path).unwrap()),
+        ])));
+        std::fs::remove_file(&path).unwrap();
+
+        let dictionary = db
+            .query_dictionary(dictionary_id, DEFAULT_PROFILE_ID)
+            .await
+            .unwrap()
+            .unwrap();
+        let updated = updater
+            .update(config.clone(), &db, &dictionary, Arc::new(NoProgress))
+            .await
+            .unwrap();
+
+        assert!(updated);
+        let dictionary = db
+            .query_dictionary(dictionary_id, DEFAULT_PROFILE_ID)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(dictionary.revision, "1.1");
+        // The download is removed once imported
+        assert_eq!(std::fs::read_dir(&config.dir.temp).unwrap().count(), 0);
+    }
+}
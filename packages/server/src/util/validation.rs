@@ -0,0 +1,208 @@
+use anyhow::Context;
+use console::style;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_valid::Validate;
+use serde_valid::validation::{ArrayErrors, Error, Errors, ObjectErrors};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// How dictionary files are checked against the Yomitan schemas while importing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValidationMode {
+    /// Banks are parsed as a whole without the schema rules, a malformed row fails the import.
+    #[default]
+    Normal,
+    /// Every row is checked and the import fails if any of them is invalid.
+    Strict,
+    /// Every row is checked and invalid rows are skipped.
+    Lenient,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationIssue {
+    pub file: String,
+    /// Index of the row in the bank, `None` for the index.
+    pub row: Option<usize>,
+    /// Path of the invalid value within the row, e.g. `[5][0].content.href`.
+    pub path: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationReport {
+    pub mode: ValidationMode,
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    pub fn new(mode: ValidationMode) -> Self {
+        Self {
+            mode,
+            issues: vec![],
+        }
+    }
+
+    /// Number of bank rows with at least one issue.
+    pub fn invalid_rows(&self) -> usize {
+        self.issues
+            .iter()
+            .filter_map(|issue| Some((&issue.file, issue.row?)))
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    /// Prints up to `limit` issues followed by a summary.
+    pub fn print(&self, limit: usize) {
+        for issue in self.issues.iter().take(limit) {
+            let location = match issue.row {
+                Some(row) => format!("{} row {}", issue.file, row),
+                None => issue.file.clone(),
+            };
+            println!(
+                "{} {} {}",
+                style(location).bold(),
+                style(&issue.path).dim(),
+                issue.reason
+            );
+        }
+        if self.issues.len() > limit {
+            println!("... and {} more", self.issues.len() - limit);
+        }
+        println!(
+            "{} issues in {} rows",
+            self.issues.len(),
+            self.invalid_rows()
+        );
+    }
+
+    pub fn write(&self, path: &Path) -> anyhow::Result<()> {
+        let report = serde_json::to_vec_pretty(self)?;
+        fs::write(path, report)
+            .with_context(|| format!("Failed to write report to {}", path.display()))?;
+        Ok(())
+    }
+}
+
+/// Checks `value` against the schema rules, including the ones spanning several fields.
+pub fn validate<T: Validate>(value: &T, file: &str, row: Option<usize>) -> Vec<ValidationIssue> {
+    let Err(errors) = value.validate() else {
+        return vec![];
+    };
+    let mut reasons = vec![];
+    collect_errors("", &errors, &mut reasons);
+    reasons
+        .into_iter()
+        .map(|(path, reason)| ValidationIssue {
+            file: file.to_string(),
+            row,
+            path,
+            reason,
+        })
+        .collect()
+}
+
+/// Parses a bank one row at a time and checks every row against the schema.
+/// Rows that fail either are left out and reported instead of failing the whole bank.
+pub fn parse_rows<R>(content: &[u8], file: &str) -> anyhow::Result<(Vec<R>, Vec<ValidationIssue>)>
+where
+    R: DeserializeOwned + Validate,
+{
+    let values: Vec<serde_json::Value> = serde_json::from_slice(content)?;
+
+    let mut rows = Vec::with_capacity(values.len());
+    let mut issues = vec![];
+    for (index, value) in values.into_iter().enumerate() {
+        let row: R = match serde_path_to_error::deserialize(value) {
+            Ok(row) => row,
+            Err(err) => {
+                issues.push(ValidationIssue {
+                    file: file.to_string(),
+                    row: Some(index),
+                    path: err.path().to_string(),
+                    reason: err.into_inner().to_string(),
+                });
+                continue;
+            }
+        };
+        let row_issues = validate(&row, file, Some(index));
+        if row_issues.is_empty() {
+            rows.push(row);
+        } else {
+            issues.extend(row_issues);
+        }
+    }
+    Ok((rows, issues))
+}
+
+/// Flattens nested errors into `(path, reason)` pairs, with paths formatted the same way as
+/// deserialization errors.
+fn collect_errors(path: &str, errors: &Errors, out: &mut Vec<(String, String)>) {
+    match errors {
+        Errors::Array(errors) => collect_array_errors(path, errors, out),
+        Errors::Object(errors) => collect_object_errors(path, errors, out),
+        Errors::NewType(errors) => collect_error_list(path, errors, out),
+    }
+}
+
+fn collect_array_errors(path: &str, errors: &ArrayErrors, out: &mut Vec<(String, String)>) {
+    collect_error_list(path, &errors.errors, out);
+    for (index, errors) in &errors.items {
+        collect_errors(&format!("{}[{}]", path, index), errors, out);
+    }
+}
+
+fn collect_object_errors(path: &str, errors: &ObjectErrors, out: &mut Vec<(String, String)>) {
+    collect_error_list(path, &errors.errors, out);
+    for (key, errors) in &errors.properties {
+        let path = if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        };
+        collect_errors(&path, errors, out);
+    }
+}
+
+fn collect_error_list(path: &str, errors: &[Error], out: &mut Vec<(String, String)>) {
+    for error in errors {
+        match error {
+            Error::Items(errors) => collect_array_errors(path, errors, out),
+            Error::Properties(errors) => collect_object_errors(path, errors, out),
+            error => {
+                let path = if path.is_empty() { "." } else { path };
+                out.push((path.to_string(), error.to_string()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::schemas::dictionary_term_bank_v3::DictionaryTermBankV3Row;
+    use serde_json::json;
+
+    #[test]
+    fn should_report_invalid_rows() {
+        let bank = json!([
+            ["読む", "よむ", "", "v5", 0, ["to read"], 1, ""],
+            ["読む", "よむ", "", "v5", 0],
+            ["見る", "みる", "", "v1", 0, [{"type": "image", "path": "a.png", "width": 0}], 2, ""],
+            ["書く", "かく", "", "v5", 0, [{"type": "structured-content", "content": {"tag": "a", "href": "file:///etc"}}], 3, ""],
+        ]);
+        let content = serde_json::to_vec(&bank).unwrap();
+
+        let (rows, issues) =
+            parse_rows::<DictionaryTermBankV3Row>(&content, "term_bank_1.json").unwrap();
+        assert_eq!(rows.len(), 1);
+        let rows: Vec<Option<usize>> = issues.iter().map(|issue| issue.row).collect();
+        assert_eq!(rows, [Some(1), Some(2), Some(3)]);
+        assert!(issues[1].path.ends_with("width"), "{}", issues[1].path);
+        assert!(issues[2].path.ends_with("href"), "{}", issues[2].path);
+    }
+}
@@ -357,11 +357,11 @@ pub fn parse_into_lexemes(tokens: Vec<PreparedToken>) -> Result<Vec<Lexeme>> {
                     POS::Hijiritsu | POS::Tokushu => {
                         if let Some(following) = iter.peek() {
                             match token.pos3 {
-                                POS::Fukushikanou => {
-                                    if following.pos == POS::Joshi && following.literal == NI {
-                                        pos = Some(PartOfSpeech::Adverb);
-                                        eat_next = true;
-                                    }
+                                POS::Fukushikanou
+                                    if following.pos == POS::Joshi && following.literal == NI =>
+                                {
+                                    pos = Some(PartOfSpeech::Adverb);
+                                    eat_next = true;
                                 }
                                 POS::Jodoushigokan => {
                                     if following.inflection_type == POS::TokushuDa {